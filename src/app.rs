@@ -1,24 +1,313 @@
 use eframe::egui;
-use crate::highlight;
-use crate::search;
-use egui::{text::LayoutJob, RichText, TextureHandle};
+use egui::{RichText, TextureHandle};
 use rfd::FileDialog;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-const MAX_FILE_SIZE_BYTES: u64 = 10_000_000; // 10MB
-const MAX_RECENT_FILES: usize = 10;
-const BIG_TEXT_CHAR_THRESHOLD: usize = 500_000; // Disable heavy features beyond this
-pub(crate) const HIGHLIGHT_CHAR_THRESHOLD: usize = 200_000; // Disable syntax/mark highlights beyond this
+use crate::keys::Action;
+
+pub(crate) const APP_TITLE: &str = "Gemini File Viewer 2.1";
+const DEFAULT_MAX_FILE_MB: u64 = 10; // Images, hex and archives
+// Text over the limit is indexed and read a screenful at a time; from LARGE_TEXT_BYTES up it always is
+const MAX_TEXT_MB: u64 = crate::io::LARGE_TEXT_BYTES / 1_000_000;
+const DEFAULT_MAX_TEXT_MB: u64 = MAX_TEXT_MB;
+const MAX_RECENT_FILES_CAP: usize = 100; // Upper bound for the configurable Recent capacity
+const BIG_TEXT_CHAR_THRESHOLD: usize = 500_000; // Default: disable heavy features beyond this
+const HIGHLIGHT_CHAR_THRESHOLD: usize = 200_000; // Default: disable syntax/mark highlights beyond this
+const BIG_TEXT_LINE_THRESHOLD: usize = 50_000;
+pub(crate) const HEX_ROW_SIZES: [usize; 3] = [8, 16, 32];
+pub(crate) const LINE_FLASH_SECS: f32 = 1.5; // How long flashed lines (tail reload, go to line) stay highlighted
+pub(crate) const IMAGE_ZOOM_PRESETS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0]; // Bound to keys 1-5
+const THEME_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+const DENSITY_PRESETS: [(&str, f32); 3] = [("Compact", 0.75), ("Comfortable", 1.0), ("Spacious", 1.35)];
 
 pub enum Content {
     Text(String),
     Image(TextureHandle),
+    Archive(crate::archive::Listing),
+    Hex(Vec<u8>),
+    /// Text too large to load, read from the file a screenful at a time
+    LargeText(crate::io::LineIndex),
+}
+
+/// How to interpret a file being opened; `Auto` goes by extension.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OpenAs {
+    Auto,
+    Text,
+    Hex,
+    Image,
+}
+
+/// What to do with files whose extension isn't a known text, image or archive type.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownFiles {
+    #[default]
+    Text,
+    Hex,
+    Ask,
+}
+
+/// Whether to reopen the files that were open when the viewer last closed.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResumeSession {
+    Never,
+    #[default]
+    Ask,
+    Always,
+}
+
+/// Hover preview of a Recent entry, cached with the modification time it was made from.
+pub(crate) enum RecentPreview {
+    Text(String),
+    Image(TextureHandle),
+    Unavailable(String),
+}
+
+// A Recent preview as the worker makes it, before the image is uploaded
+enum MadePreview {
+    Text(String),
+    Image(egui::ColorImage),
+    Unavailable(String),
+}
+
+type PreviewJob = (PathBuf, std::sync::mpsc::Receiver<(Option<std::time::SystemTime>, MadePreview)>);
+
+/// Second image overlaid on the current one for before/after comparison, aligned top-left.
+pub(crate) struct CompareImage {
+    pub(crate) path: PathBuf,
+    pub(crate) texture: TextureHandle,
+    // Pixels that differ from the current image, and how many
+    pub(crate) diff: TextureHandle,
+    pub(crate) diff_count: usize,
+}
+
+/// Page navigation for a PDF, whose current page is shown as an image.
+pub(crate) struct PdfView {
+    // The page shown; it only changes once the next one has rendered
+    pub(crate) page: usize,
+    pub(crate) pages: usize,
+    // The page asked for and still rendering
+    requested: Option<usize>,
+    renderer: crate::pdf::Renderer,
+}
+
+impl PdfView {
+    /// The page being moved to, or the shown one; page steps count from here.
+    pub(crate) fn target(&self) -> usize {
+        self.requested.unwrap_or(self.page)
+    }
+}
+
+/// Lines found by a folder search, filled in by a worker while it runs.
+pub(crate) struct FolderSearch {
+    pub(crate) hits: Vec<crate::search::FolderHit>,
+    // Files searched so far
+    pub(crate) files: usize,
+    pub(crate) done: bool,
+    rx: Option<std::sync::mpsc::Receiver<crate::search::FolderEvent>>,
+}
+
+/// Frames of an animated GIF or APNG; the current one is shown as the image.
+pub(crate) struct Animation {
+    // Oriented as shown; the current frame's pixels are moved out to `image_pixels` meanwhile
+    pub(crate) frames: Vec<crate::io::Frame>,
+    pub(crate) index: usize,
+    pub(crate) playing: bool,
+    // When the current frame went up
+    shown_at: std::time::Instant,
+    // Texture and histogram of each frame already shown, kept until the adjustments or
+    // orientation change; the current frame's are in use instead
+    shown: Vec<Option<(TextureHandle, crate::imaging::Histogram)>>,
+}
+
+/// Images of the current folder shown one after another.
+pub(crate) struct Slideshow {
+    pub(crate) paused: bool,
+    // When the current image went up
+    shown_at: std::time::Instant,
+}
+
+/// A file plus the zoom and scroll it was viewed with; used for reopening and sessions.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub(crate) struct FileViewState {
+    pub(crate) path: PathBuf,
+    pub(crate) text_zoom: f32,
+    pub(crate) image_zoom: f32,
+    pub(crate) image_fit: bool,
+    pub(crate) scroll_offset: [f32; 2],
+    #[serde(default)]
+    pub(crate) search_query: String,
+}
+
+/// One tab of the strip. The active tab's file and view live in `FileViewerApp` itself; its entry
+/// here is `Empty` until another tab is shown.
+pub(crate) enum Tab {
+    Empty,
+    /// A file from a session or the command line, loaded when the tab is first shown
+    Pending(FileViewState),
+    /// A tab switched away from, kept exactly as it was
+    Open(Box<TabState>),
+}
+
+impl Tab {
+    /// The file the tab shows or will show.
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match self {
+            Tab::Empty => None,
+            Tab::Pending(state) => Some(&state.path),
+            Tab::Open(tab) => tab.current_path.as_deref().or(tab.loading.as_ref().map(|l| l.path.as_path())),
+        }
+    }
+
+    /// What reopening the tab's file needs, for sessions and reopening closed tabs. None for
+    /// clipboard contents and archive entries, which can't be read again from a path.
+    fn view_state(&self) -> Option<FileViewState> {
+        match self {
+            Tab::Empty => None,
+            Tab::Pending(state) => Some(state.clone()),
+            Tab::Open(tab) => tab.view.clone(),
+        }
+    }
+}
+
+/// Everything about a file shown in a background tab: its content, position, search and the
+/// work still running for it. See `FileViewerApp::stash_tab`.
+pub(crate) struct TabState {
+    view: Option<FileViewState>,
+    content: Option<Content>,
+    current_path: Option<PathBuf>,
+    error_message: Option<String>,
+    error_path: Option<PathBuf>,
+    link_target: Option<PathBuf>,
+    archive_path: Option<PathBuf>,
+    format_alt: Option<String>,
+    format_shown: bool,
+    format_warning: Option<String>,
+    text_is_big: bool,
+    text_line_count: usize,
+    text_is_lossy: bool,
+    text_invalid_offsets: Vec<usize>,
+    text_line_offsets: Vec<usize>,
+    text_line_starts: Vec<usize>,
+    text_encoding: &'static str,
+    encoding_override: Option<&'static encoding_rs::Encoding>,
+    hex_goto_input: String,
+    hex_marked: Option<usize>,
+    hex_scroll_to: Option<usize>,
+    hex_selection: Option<(usize, usize)>,
+    outline: Vec<crate::outline::OutlineEntry>,
+    fold_ranges: BTreeMap<usize, usize>,
+    folded: BTreeSet<usize>,
+    image_pixels: Option<image::RgbaImage>,
+    image_histogram: Option<crate::imaging::Histogram>,
+    measure_points: Vec<egui::Pos2>,
+    crop_rect: Option<egui::Rect>,
+    crop_anchor: Option<egui::Pos2>,
+    compare: Option<CompareImage>,
+    compare_job: Option<CompareJob>,
+    compare_split: f32,
+    compare_diff: bool,
+    pdf: Option<PdfView>,
+    animation: Option<Animation>,
+    archive_entry_job: Option<EntryJob>,
+    table: Option<crate::table::Table>,
+    table_error: Option<String>,
+    json_tree: Option<crate::jsontree::JsonTree>,
+    text_diff: Option<crate::diff::TextDiff>,
+    text_diff_job: Option<DiffJob>,
+    replace_preview: Option<(String, usize)>,
+    search_query: String,
+    search_pattern: Option<crate::search::Pattern>,
+    search_error: Option<String>,
+    search_count: usize,
+    search_current: usize,
+    large_matches: Vec<usize>,
+    large_search: Option<std::sync::mpsc::Receiver<Vec<usize>>>,
+    bookmark_cursor: Option<usize>,
+    cursor_line: Option<usize>,
+    first_visible_line: Option<usize>,
+    text_scroll_offset: egui::Vec2,
+    text_zoom: f32,
+    image_zoom: f32,
+    image_fit: bool,
+    tail_mode: bool,
+    file_watch: Option<crate::watch::FileWatch>,
+    disk_stamp: Option<(std::time::SystemTime, u64)>,
+    line_flash: Option<(std::ops::Range<usize>, std::time::Instant)>,
+    siblings: Option<crate::io::Siblings>,
+    sibling_scan: Option<(PathBuf, std::time::Instant, std::sync::mpsc::Receiver<crate::io::Siblings>)>,
+    loading: Option<Loading>,
+    text_reload: Option<TextReload>,
+    syntax_spans: Vec<crate::syntax::LineSpans>,
+    syntax_key: Option<(&'static str, Theme)>,
+    syntax_job: Option<std::sync::mpsc::Receiver<Vec<crate::syntax::LineSpans>>>,
+    language_override: Option<String>,
+}
+
+const MAX_RECENTLY_CLOSED: usize = 10;
+// Longest stretch of text sent to the printer at once
+const MAX_PRINT_LINES: usize = 100_000;
+const PRINT_IMAGE_NAME: &str = "print.png";
+// In private mode print files are only kept long enough for the browser to load them
+const PRIVATE_PRINT_KEEP: std::time::Duration = std::time::Duration::from_secs(60);
+/// Stand-in path for content pasted from the clipboard
+pub(crate) const CLIPBOARD_PATH: &str = "(clipboard)";
+
+/// Pointer interaction mode over an image.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageTool {
+    #[default]
+    None,
+    Measure,
+    Crop,
+}
+
+/// Page of the Settings window.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum SettingsPage {
+    #[default]
+    Appearance,
+    Text,
+    Files,
+    Privacy,
+    Images,
+    Hex,
+    Keyboard,
+    Diagnostics,
+}
+
+impl SettingsPage {
+    const ALL: [SettingsPage; 8] = [
+        SettingsPage::Appearance,
+        SettingsPage::Text,
+        SettingsPage::Files,
+        SettingsPage::Privacy,
+        SettingsPage::Images,
+        SettingsPage::Hex,
+        SettingsPage::Keyboard,
+        SettingsPage::Diagnostics,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SettingsPage::Appearance => "Appearance",
+            SettingsPage::Text => "Text",
+            SettingsPage::Files => "Files",
+            SettingsPage::Privacy => "Privacy",
+            SettingsPage::Images => "Images",
+            SettingsPage::Hex => "Hex",
+            SettingsPage::Keyboard => "Keyboard",
+            SettingsPage::Diagnostics => "Diagnostics",
+        }
+    }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Theme {
     Light,
+    #[default]
     Dark,
     SolarizedLight,
     SolarizedDark,
@@ -28,8 +317,6 @@ pub enum Theme {
     Allison,
 }
 
-impl Default for Theme { fn default() -> Self { Theme::Dark } }
-
 impl Theme {
     pub fn is_dark(self) -> bool {
         matches!(self, Theme::Dark | Theme::SolarizedDark | Theme::Dracula | Theme::GruvboxDark | Theme::Allison)
@@ -57,18 +344,113 @@ pub struct FileViewerApp {
     pub(crate) current_path: Option<PathBuf>,
     #[serde(skip)]
     pub(crate) error_message: Option<String>,
+    // File whose load produced `error_message`, for the error details
+    #[serde(skip)]
+    pub(crate) error_path: Option<PathBuf>,
+    // Where the current path points when it is a symbolic link
+    #[serde(skip)]
+    pub(crate) link_target: Option<PathBuf>,
+    // Archive being browsed; entries opened from it get `<archive>/<entry>` as their path
+    #[serde(skip)]
+    pub(crate) archive_path: Option<PathBuf>,
+    pub(crate) follow_symlinks: bool,
+    // Include dotfiles (and hidden-attribute files on Windows) in Prev/Next navigation
+    pub(crate) show_hidden_files: bool,
+    // Open files so other programs can keep writing them (matters on Windows, e.g. while tailing logs)
+    #[serde(default = "default_shared_read")]
+    pub(crate) shared_read: bool,
+    pub(crate) unknown_files: UnknownFiles,
+    // Tabs open at the last exit, with their zoom and scroll, and whether to reopen them on startup
+    pub(crate) resume_session: ResumeSession,
+    pub(crate) last_session: Option<crate::settings::Session>,
+    // Modified/created times in the status bar
+    pub(crate) show_file_times: bool,
+    // Unknown-type file waiting for the user to pick how to open it
+    #[serde(skip)]
+    pub(crate) pending_unknown: Option<PathBuf>,
+    // Show paths relative to `path_base` (or the working directory when unset)
+    pub(crate) relative_paths: bool,
+    pub(crate) path_base: Option<PathBuf>,
     pub(crate) dark_mode: bool,
     pub(crate) theme: Theme,
+    // Themes the light/dark quick toggle switches between
+    pub(crate) last_dark_theme: Theme,
+    pub(crate) last_light_theme: Theme,
     #[serde(default = "default_follow_system_true")]
     pub(crate) follow_system_theme: bool,
+    // Last system light/dark answer and when it was asked
+    #[serde(skip)]
+    pub(crate) system_dark: Option<bool>,
+    #[serde(skip)]
+    pub(crate) theme_checked: Option<std::time::Instant>,
+    #[serde(skip)]
+    pub(crate) window_focused: bool,
+    // Poll less often and skip animations to save battery
+    pub(crate) low_power: bool,
+    // Seconds each slideshow image stays up, and whether the slideshow goes full screen without toolbars
+    pub(crate) slideshow_secs: f32,
+    pub(crate) slideshow_fullscreen: bool,
+    // Largest image, hex or archive file opened, and largest text loaded whole rather than indexed, in MB
+    pub(crate) max_file_mb: u64,
+    pub(crate) max_text_mb: u64,
     pub(crate) recent_files: Vec<PathBuf>,
+    // Favorites listed above the recent files and never dropped from the menu
+    pub(crate) pinned_files: Vec<PathBuf>,
+    #[serde(default = "default_max_recent_files")]
+    pub(crate) max_recent_files: usize,
+    // Privacy: never record opened files in the Recent list
+    pub(crate) private_mode: bool,
+    // Bookmarked (0-based) line indices per file, kept sorted
+    pub(crate) bookmarks: BTreeMap<PathBuf, Vec<usize>>,
     pub(crate) show_line_numbers: bool,
+    // Overview of the text beside the view
+    pub(crate) show_minimap: bool,
+    pub(crate) show_outline: bool,
+    // Lowercase extensions rendered with the proportional font instead of monospace
+    pub(crate) proportional_exts: BTreeSet<String>,
     pub(crate) word_wrap: bool,
+    // Column marked by a ruler in the text view, where word wrap breaks lines instead of the view's edge
+    pub(crate) wrap_column: Option<usize>,
+    // With word wrap off, still wrap lines longer than `wrap_line_threshold` characters
+    pub(crate) wrap_long_lines: bool,
+    pub(crate) wrap_line_threshold: usize,
+    // Pretty-print JSON/TOML/XML when opened; the original stays one click away
+    pub(crate) auto_format: bool,
+    // The other version of a reformatted file (the raw text while formatted is shown, and vice versa)
+    #[serde(skip)]
+    pub(crate) format_alt: Option<String>,
+    #[serde(skip)]
+    pub(crate) format_shown: bool,
+    // Why the current file couldn't be reformatted
+    #[serde(skip)]
+    pub(crate) format_warning: Option<String>,
+    // Soft tabs: expand to stops every `tab_width` columns; off leaves egui's fixed-width tab
+    pub(crate) expand_tabs: bool,
+    pub(crate) tab_width: usize,
     pub(crate) text_zoom: f32,
+    // Keep text/image zoom (and image fit) when switching files instead of resetting them
+    pub(crate) preserve_zoom: bool,
+    // Zoom each newly opened file starts at when zoom isn't preserved
+    pub(crate) default_text_zoom: f32,
+    pub(crate) default_image_zoom: f32,
+    pub(crate) default_image_fit: bool,
+    // Zoom step per wheel notch or Ctrl+=/- press, in percent
+    #[serde(default = "default_text_zoom_step")]
+    pub(crate) text_zoom_step: f32,
+    #[serde(default = "default_image_zoom_step")]
+    pub(crate) image_zoom_step: f32,
+    // Multiplier for normal wheel scrolling, and flipping the wheel direction (scroll and zoom)
+    #[serde(default = "default_scroll_speed")]
+    pub(crate) scroll_speed: f32,
+    pub(crate) invert_scroll: bool,
+    // Extra vertical spacing between text rows (1.0 = font default)
+    pub(crate) line_height_scale: f32,
     pub(crate) image_zoom: f32,
     #[serde(skip)]
     pub(crate) show_about: bool,
     pub(crate) image_fit: bool,
+    // Nearest-neighbor sampling keeps pixel art crisp when zoomed; linear suits photos
+    pub(crate) image_nearest: bool,
     pub(crate) accent_rgb: [u8; 3],
     #[serde(default = "default_spacing_scale")]
     pub(crate) spacing_scale: f32,
@@ -76,60 +458,381 @@ pub struct FileViewerApp {
     pub(crate) theme_rounding: u8,
     #[serde(skip)]
     pub(crate) show_theme_editor: bool,
+    #[serde(skip)]
+    pub(crate) show_settings: bool,
+    #[serde(skip)]
+    pub(crate) settings_page: SettingsPage,
+    pub(crate) key_bindings: crate::keys::KeyBindings,
+    // Action waiting in Settings for its new key
+    #[serde(skip)]
+    pub(crate) key_capture: Option<Action>,
+    // Last top-left corner of tool windows by title, reused when they are opened again
+    pub(crate) remember_window_positions: bool,
+    pub(crate) window_positions: std::collections::HashMap<String, [f32; 2]>,
+    pub(crate) show_perf_overlay: bool,
+    // Smoothed frame interval in seconds for the performance overlay
+    #[serde(skip)]
+    pub(crate) perf_frame_dt: f32,
+    #[serde(skip)]
+    pub(crate) split_view: bool,
+    // Second file beside the current one, how the window is split for it, and whether both scroll together
+    #[serde(skip)]
+    pub(crate) side_pane: Option<crate::pane::SidePane>,
+    #[serde(skip)]
+    side_pane_load: Option<std::sync::mpsc::Receiver<Result<ReadFile, String>>>,
+    pub(crate) split_direction: crate::pane::SplitDirection,
+    pub(crate) sync_scroll: bool,
     // Derived/runtime-only state for text rendering
     #[serde(skip)]
     pub(crate) text_is_big: bool,
+    // Size limits (in chars) above which heavy text features are switched off
+    pub(crate) big_text_threshold: usize,
+    pub(crate) highlight_threshold: usize,
     #[serde(skip)]
     pub(crate) text_line_count: usize,
     #[serde(skip)]
     pub(crate) text_is_lossy: bool,
-    // Simple find state
     #[serde(skip)]
+    pub(crate) text_invalid_offsets: Vec<usize>,
+    // File byte offset where each line starts, for the offset gutter; empty when the same as `text_line_starts`
+    #[serde(skip)]
+    pub(crate) text_line_offsets: Vec<usize>,
+    pub(crate) show_byte_offsets: bool,
+    // Encoding the current text was decoded as
+    #[serde(skip)]
+    pub(crate) text_encoding: &'static str,
+    // Encoding picked by hand for the current file, used again when it reloads
+    #[serde(skip)]
+    pub(crate) encoding_override: Option<&'static encoding_rs::Encoding>,
+    pub(crate) show_control_chars: bool,
+    // Hex view layout: bytes per row, offset base and the ASCII column
+    pub(crate) hex_row_bytes: usize,
+    pub(crate) hex_decimal_offsets: bool,
+    pub(crate) hex_show_ascii: bool,
+    // Go-to-offset input, the offset it marked, and a one-shot request to scroll there
+    #[serde(skip)]
+    pub(crate) hex_goto_input: String,
+    #[serde(skip)]
+    pub(crate) hex_marked: Option<usize>,
+    #[serde(skip)]
+    pub(crate) hex_scroll_to: Option<usize>,
+    // Selected byte range as (anchor, cursor), either order
+    #[serde(skip)]
+    pub(crate) hex_selection: Option<(usize, usize)>,
+    #[serde(skip)]
+    pub(crate) outline: Vec<crate::outline::OutlineEntry>,
+    // Foldable markup elements (start line -> end line) and the ones currently collapsed
+    #[serde(skip)]
+    pub(crate) fold_ranges: BTreeMap<usize, usize>,
+    #[serde(skip)]
+    pub(crate) folded: BTreeSet<usize>,
+    // Decoded pixels of the current image, kept for analysis and export
+    #[serde(skip)]
+    pub(crate) image_pixels: Option<image::RgbaImage>,
+    #[serde(skip)]
+    pub(crate) image_histogram: Option<crate::imaging::Histogram>,
+    pub(crate) show_histogram: bool,
+    // Strip of the folder's images along the bottom, with thumbnails made in the background
+    pub(crate) show_thumbnails: bool,
+    #[serde(skip)]
+    pub(crate) thumbnails: crate::thumbs::Thumbnails,
+    #[serde(skip)]
+    pub(crate) image_tool: ImageTool,
+    // Measurement endpoints in image pixel coordinates
+    #[serde(skip)]
+    pub(crate) measure_points: Vec<egui::Pos2>,
+    // Crop selection in image pixel coordinates, plus the drag anchor while selecting
+    #[serde(skip)]
+    pub(crate) crop_rect: Option<egui::Rect>,
+    #[serde(skip)]
+    pub(crate) crop_anchor: Option<egui::Pos2>,
+    #[serde(skip)]
+    pub(crate) image_adjust: crate::imaging::Adjustments,
+    // Rotation and mirroring per image, kept for the session so returning to a file shows it the same way
+    #[serde(skip)]
+    image_orientations: std::collections::HashMap<PathBuf, crate::imaging::Orientation>,
+    // Set when adjustment sliders move; the texture is rebuilt once they settle
+    #[serde(skip)]
+    pub(crate) image_adjust_changed: Option<std::time::Instant>,
+    #[serde(skip)]
+    pub(crate) show_adjust: bool,
+    #[serde(skip)]
+    pub(crate) compare: Option<CompareImage>,
+    // The second image being read and diffed on a worker thread
+    #[serde(skip)]
+    pub(crate) compare_job: Option<CompareJob>,
+    #[serde(skip)]
+    pub(crate) pdf: Option<PdfView>,
+    #[serde(skip)]
+    pub(crate) animation: Option<Animation>,
+    // An archive entry being extracted and decoded on a worker thread; the listing stays up meanwhile
+    #[serde(skip)]
+    archive_entry_job: Option<EntryJob>,
+    #[serde(skip)]
+    pub(crate) slideshow: Option<Slideshow>,
+    // CSV/TSV files parsed into cells, or why parsing failed; shown as a table unless switched off
+    #[serde(skip)]
+    pub(crate) table: Option<crate::table::Table>,
+    #[serde(skip)]
+    pub(crate) table_error: Option<String>,
+    pub(crate) table_view: bool,
+    // JSON files parsed for the tree view, shown instead of the text unless switched off
+    #[serde(skip)]
+    pub(crate) json_tree: Option<crate::jsontree::JsonTree>,
+    pub(crate) json_view: bool,
+    // Divider position as a fraction of the displayed width; the compared image shows right of it
+    #[serde(skip)]
+    pub(crate) compare_split: f32,
+    #[serde(skip)]
+    pub(crate) compare_diff: bool,
+    // Second text file diffed against the current one, shown side by side or unified
+    #[serde(skip)]
+    pub(crate) text_diff: Option<crate::diff::TextDiff>,
+    // The diff being worked out on a worker thread; the old one stays shown until it's ready
+    #[serde(skip)]
+    text_diff_job: Option<DiffJob>,
+    pub(crate) diff_unified: bool,
+    // Simple find state; the query survives file switches and, optionally, restarts
     pub(crate) search_query: String,
+    pub(crate) remember_search: bool,
+    // Keep match highlights after the find field loses focus
+    pub(crate) persistent_highlights: bool,
+    // Treat the query as a regular expression; match case; only match whole words
+    pub(crate) search_regex: bool,
+    pub(crate) search_match_case: bool,
+    pub(crate) search_whole_word: bool,
+    // The query compiled for matching, or why it doesn't compile
+    #[serde(skip)]
+    pub(crate) search_pattern: Option<crate::search::Pattern>,
+    #[serde(skip)]
+    pub(crate) search_error: Option<String>,
+    #[serde(skip)]
+    pub(crate) search_focused: bool,
+    // Find & replace preview; the original file is never modified
+    #[serde(skip)]
+    pub(crate) show_replace: bool,
+    #[serde(skip)]
+    pub(crate) replace_with: String,
+    #[serde(skip)]
+    pub(crate) replace_preview: Option<(String, usize)>,
     #[serde(skip)]
     pub(crate) search_active: bool,
     #[serde(skip)]
     pub(crate) search_count: usize,
     #[serde(skip)]
     pub(crate) search_current: usize,
+    // Line of every match in an indexed large file, in order, and the search still adding to them
+    #[serde(skip)]
+    pub(crate) large_matches: Vec<usize>,
+    #[serde(skip)]
+    large_search: Option<std::sync::mpsc::Receiver<Vec<usize>>>,
+    // One-shot request to scroll the text view to a (0-based) line
+    #[serde(skip)]
+    pub(crate) scroll_to_line: Option<usize>,
+    // Search in folder panel (Ctrl+Shift+F): folder, query and results
+    #[serde(skip)]
+    pub(crate) show_folder_search: bool,
+    #[serde(skip)]
+    pub(crate) folder_search_dir: Option<PathBuf>,
+    #[serde(skip)]
+    pub(crate) folder_search_query: String,
+    #[serde(skip)]
+    pub(crate) folder_search: Option<FolderSearch>,
+    #[serde(skip)]
+    pub(crate) folder_search_error: Option<String>,
+    // Print options (Ctrl+P): the 1-based line range and what to include
+    #[serde(skip)]
+    pub(crate) print_open: bool,
+    #[serde(skip)]
+    pub(crate) print_range: (usize, usize),
+    // Folder holding the latest print page and when it was opened; removed when replaced and on exit
+    #[serde(skip)]
+    print_dir: Option<(tempfile::TempDir, std::time::Instant)>,
+    pub(crate) print_line_numbers: bool,
+    pub(crate) print_colors: bool,
+    // Go-to-line popup (Ctrl+G) and its 1-based line number field
+    #[serde(skip)]
+    pub(crate) goto_line_open: bool,
+    #[serde(skip)]
+    pub(crate) goto_line_input: String,
+    #[serde(skip)]
+    pub(crate) bookmark_cursor: Option<usize>,
+    // Where Ctrl+B puts a bookmark: the line last clicked or jumped to, else the top line in view
+    #[serde(skip)]
+    pub(crate) cursor_line: Option<usize>,
+    #[serde(skip)]
+    pub(crate) first_visible_line: Option<usize>,
+    // Last known text scroll offset, and a one-shot offset to restore
+    #[serde(skip)]
+    pub(crate) text_scroll_offset: egui::Vec2,
+    #[serde(skip)]
+    pub(crate) pending_scroll_offset: Option<egui::Vec2>,
+    // Tail mode: re-read the text file when it changes and keep the view pinned to its end
+    #[serde(skip)]
+    pub(crate) tail_mode: bool,
+    // Auto-reload: re-read the open file whenever it changes on disk
+    pub(crate) auto_reload: bool,
+    #[serde(skip)]
+    pub(crate) file_watch: Option<crate::watch::FileWatch>,
+    // Modification time and length of the open file when it was last read
+    #[serde(skip)]
+    pub(crate) disk_stamp: Option<(std::time::SystemTime, u64)>,
+    // Lines to flash with a fading highlight, and since when: those changed by a tail reload, or a go-to-line target
+    #[serde(skip)]
+    pub(crate) line_flash: Option<(std::ops::Range<usize>, std::time::Instant)>,
+    // Sibling files of the current folder for Prev/Next, and a background rescan when stale
+    #[serde(skip)]
+    pub(crate) siblings: Option<crate::io::Siblings>,
+    // Where each line starts in the shown text, built with it on the loader thread
+    #[serde(skip)]
+    pub(crate) text_line_starts: Vec<usize>,
+    #[serde(skip)]
+    pub(crate) loading: Option<Loading>,
+    #[serde(skip)]
+    text_reload: Option<TextReload>,
+    // Colored spans per line from the syntax highlighter, the language and theme they were made for,
+    // and the worker computing them
+    #[serde(skip)]
+    pub(crate) syntax_spans: Vec<crate::syntax::LineSpans>,
+    #[serde(skip)]
+    pub(crate) syntax_key: Option<(&'static str, Theme)>,
+    #[serde(skip)]
+    pub(crate) syntax_job: Option<std::sync::mpsc::Receiver<Vec<crate::syntax::LineSpans>>>,
+    // Language picked in the toolbar instead of the one detected from the extension
+    #[serde(skip)]
+    pub(crate) language_override: Option<String>,
+    #[serde(skip)]
+    pub(crate) sibling_scan: Option<(PathBuf, std::time::Instant, std::sync::mpsc::Receiver<crate::io::Siblings>)>,
+    // Prev/Next (false/true) pressed while the scan was still running
+    #[serde(skip)]
+    pub(crate) pending_neighbor: Option<bool>,
+    #[serde(skip)]
+    pub(crate) recently_closed: Vec<FileViewState>,
+    #[serde(skip)]
+    pub(crate) tabs: Vec<Tab>,
+    #[serde(skip)]
+    pub(crate) active_tab: usize,
+    #[serde(skip)]
+    pub(crate) session_name_input: String,
+    #[serde(skip)]
+    pub(crate) recent_filter: String,
+    #[serde(skip)]
+    pub(crate) recent_previews: std::collections::HashMap<PathBuf, (Option<std::time::SystemTime>, RecentPreview)>,
+    // The Recent entry whose preview is being made on a worker thread
+    #[serde(skip)]
+    recent_preview_job: Option<PreviewJob>,
+    // Transient confirmation message shown briefly over the content
+    #[serde(skip)]
+    pub(crate) toast: Option<(String, std::time::Instant)>,
+    // System clipboard handle, kept alive so copied images outlive the call; None when unsupported
+    #[serde(skip)]
+    pub(crate) clipboard: Option<arboard::Clipboard>,
+    // Other viewer windows, each with its own files and view. Settings changed in any window are
+    // copied to all of them (see `share_settings`) and only the main window writes them to disk
+    #[serde(skip)]
+    pub(crate) extra_windows: Vec<(egui::ViewportId, FileViewerApp)>,
+    #[serde(skip)]
+    pub(crate) windows_opened: u64,
+    #[serde(skip)]
+    pub(crate) secondary: bool,
+    #[serde(skip)]
+    pub(crate) new_window_requested: bool,
+    // Unsaved settings changes and when settings were last written; see `settings::flush_settings`
+    #[serde(skip)]
+    pub(crate) settings_dirty: std::cell::Cell<bool>,
+    // Settings changed since they were last copied to the other windows
+    #[serde(skip)]
+    pub(crate) settings_changed: std::cell::Cell<bool>,
+    #[serde(skip)]
+    pub(crate) settings_written: Option<std::time::Instant>,
 }
 
 impl FileViewerApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Load custom fonts if present (from Allison fork)
         load_custom_fonts(&cc.egui_ctx);
-        if let Some(storage) = cc.storage
+        let mut app = if let Some(storage) = cc.storage
             && let Some(s) = storage.get_string(eframe::APP_KEY)
-            && let Ok(mut app) = serde_json::from_str::<FileViewerApp>(&s)
+            && let Ok(app) = serde_json::from_str::<FileViewerApp>(&s)
         {
-            app.text_is_big = false;
-            app.text_line_count = 0;
-            app.text_is_lossy = false;
-            app.search_query = String::new();
-            app.search_active = false;
-            app.search_count = 0;
-            if app.dark_mode != app.theme.is_dark() {
-                app.theme = if app.dark_mode { Theme::Dark } else { Theme::Light };
-            }
-            if app.spacing_scale <= 0.0 { app.spacing_scale = default_spacing_scale(); }
-            if app.theme_rounding == 0 { app.theme_rounding = default_rounding(); }
-            return app;
-        }
-        if let Some(mut app) = crate::settings::load_settings_from_disk() {
-            app.text_is_big = false;
-            app.text_line_count = 0;
-            app.text_is_lossy = false;
-            app.search_query = String::new();
-            app.search_active = false;
-            app.search_count = 0;
-            if app.dark_mode != app.theme.is_dark() {
-                app.theme = if app.dark_mode { Theme::Dark } else { Theme::Light };
-            }
-            if app.spacing_scale <= 0.0 { app.spacing_scale = default_spacing_scale(); }
-            if app.theme_rounding == 0 { app.theme_rounding = default_rounding(); }
-            return app;
-        }
-        Default::default()
+            app.restored()
+        } else {
+            match crate::settings::load_settings_from_disk() {
+                Ok(Some(app)) => app.restored(),
+                Ok(None) => Self::default(),
+                Err(e) => {
+                    let mut app = Self::default();
+                    app.show_toast(e);
+                    app
+                }
+            }
+        };
+        app.clipboard = arboard::Clipboard::new().ok();
+        app
+    }
+
+    /// Open the files named on the command line: the first in the current tab, the rest as
+    /// background tabs. `line` (1-based) is scrolled to once the first file is shown.
+    /// Without files, the last session is resumed if set to do so.
+    pub fn open_startup_files(&mut self, files: Vec<PathBuf>, line: Option<usize>, ctx: &egui::Context) {
+        let mut files = files.into_iter();
+        let Some(first) = files.next() else {
+            if self.resume_session == ResumeSession::Always {
+                self.resume_last_session(ctx);
+            }
+            return;
+        };
+        self.load_file(first, ctx);
+        if let Some(loading) = &mut self.loading {
+            loading.line = line.map(|l| l.saturating_sub(1));
+        }
+        self.add_background_tabs(files);
+    }
+
+    /// Reset runtime-only state and repair out-of-range values after deserializing.
+    fn restored(mut self) -> Self {
+        self.text_is_big = false;
+        self.text_line_count = 0;
+        self.text_is_lossy = false;
+        self.text_invalid_offsets.clear();
+        if !self.remember_search { self.search_query = String::new(); }
+        self.search_active = false;
+        self.search_count = 0;
+        if !self.last_dark_theme.is_dark() { self.last_dark_theme = Theme::Dark; }
+        if self.last_light_theme.is_dark() { self.last_light_theme = Theme::Light; }
+        // The saved theme wins; `dark_mode` only mirrors it
+        self.dark_mode = self.theme.is_dark();
+        if self.spacing_scale <= 0.0 { self.spacing_scale = default_spacing_scale(); }
+        self.line_height_scale = self.line_height_scale.clamp(1.0, 2.5);
+        self.default_text_zoom = self.default_text_zoom.clamp(0.6, 3.0);
+        self.default_image_zoom = self.default_image_zoom.clamp(0.1, 6.0);
+        self.max_recent_files = self.max_recent_files.clamp(1, MAX_RECENT_FILES_CAP);
+        self.max_file_mb = self.max_file_mb.clamp(1, 10_000);
+        self.max_text_mb = self.max_text_mb.clamp(1, MAX_TEXT_MB);
+        self.big_text_threshold = self.big_text_threshold.clamp(50_000, 50_000_000);
+        self.highlight_threshold = self.highlight_threshold.clamp(10_000, 50_000_000);
+        self.wrap_line_threshold = self.wrap_line_threshold.clamp(40, 100_000);
+        // A NaN from a hand-edited file becomes the shortest interval
+        self.slideshow_secs = self.slideshow_secs.clamp(1.0, 60.0).max(1.0);
+        self.wrap_column = self.wrap_column.map(|column| column.clamp(20, 1000));
+        self.trim_recent();
+        self.tab_width = self.tab_width.clamp(1, 16);
+        self.text_zoom_step = self.text_zoom_step.clamp(1.0, 50.0);
+        self.image_zoom_step = self.image_zoom_step.clamp(1.0, 50.0);
+        self.scroll_speed = self.scroll_speed.clamp(0.25, 4.0);
+        if !HEX_ROW_SIZES.contains(&self.hex_row_bytes) { self.hex_row_bytes = 16; }
+        self
+    }
+
+    /// Switch to the remembered theme of the other brightness, keeping custom picks like Gruvbox.
+    pub(crate) fn set_dark_mode(&mut self, dark: bool) {
+        self.remember_theme();
+        self.dark_mode = dark;
+        self.theme = if dark { self.last_dark_theme } else { self.last_light_theme };
+    }
+
+    fn remember_theme(&mut self) {
+        if self.theme.is_dark() { self.last_dark_theme = self.theme; } else { self.last_light_theme = self.theme; }
     }
 
     pub(crate) fn apply_theme(&self, ctx: &egui::Context) {
@@ -153,128 +856,2376 @@ impl FileViewerApp {
         };
 
         let mut style = (*ctx.style()).clone();
-        let s = self.spacing_scale.max(0.5).min(2.0);
+        let s = self.spacing_scale.clamp(0.5, 2.0);
         style.spacing.item_spacing = egui::vec2(8.0 * s, 6.0 * s);
         style.spacing.button_padding = egui::vec2(10.0 * s, 6.0 * s);
         style.spacing.interact_size = egui::vec2(36.0 * s, 28.0 * s);
         let wm_x: i8 = (12.0 * s).round() as i8;
         let wm_y: i8 = (8.0 * s).round() as i8;
         style.spacing.window_margin = egui::Margin::symmetric(wm_x, wm_y);
-        // Global rounding is more limited in egui 0.31; skip if not available
+        // Windows and menus use the full radius; widgets keep egui's smaller proportion
+        let r = self.theme_rounding.min(24);
+        let widget_r = egui::CornerRadius::same(r.div_ceil(2));
+        visuals.window_corner_radius = egui::CornerRadius::same(r);
+        visuals.menu_corner_radius = egui::CornerRadius::same(r);
+        for w in [
+            &mut visuals.widgets.noninteractive,
+            &mut visuals.widgets.inactive,
+            &mut visuals.widgets.hovered,
+            &mut visuals.widgets.active,
+            &mut visuals.widgets.open,
+        ] {
+            w.corner_radius = widget_r;
+        }
         style.visuals = visuals;
         ctx.set_style(style);
     }
 
-    pub fn load_file(&mut self, path: PathBuf, ctx: &egui::Context) {
+    fn reset_content(&mut self) {
+        self.loading = None;
+        self.text_reload = None;
         self.content = None;
         self.error_message = None;
+        self.error_path = None;
         self.current_path = None;
+        self.link_target = None;
+        self.scroll_to_line = None;
+        self.bookmark_cursor = None;
+        self.cursor_line = None;
+        self.first_visible_line = None;
+        self.encoding_override = None;
+        self.outline.clear();
+        self.replace_preview = None;
+        self.image_pixels = None;
+        self.image_histogram = None;
+        self.measure_points.clear();
+        self.crop_rect = None;
+        self.crop_anchor = None;
+        self.compare = None;
+        self.compare_job = None;
+        self.text_diff = None;
+        self.text_diff_job = None;
+        self.pdf = None;
+        self.animation = None;
+        self.archive_entry_job = None;
+        self.table = None;
+        self.table_error = None;
+        self.json_tree = None;
+        self.large_search = None;
+        self.large_matches.clear();
+        self.hex_marked = None;
+        self.hex_scroll_to = None;
+        self.hex_selection = None;
+        self.format_alt = None;
+        self.format_shown = false;
+        self.format_warning = None;
+        self.tail_mode = false;
+        self.disk_stamp = None;
+        self.line_flash = None;
+        self.language_override = None;
+        self.clear_syntax();
+    }
 
-        if let Ok(metadata) = fs::metadata(&path)
-            && metadata.len() > MAX_FILE_SIZE_BYTES
-        {
-            self.error_message = Some(format!(
-                "File is too large (> {:.1}MB)",
-                MAX_FILE_SIZE_BYTES as f64 / 1_000_000.0
-            ));
-            return;
-        }
-
-        let loaded = if crate::io::is_supported_image(&path) {
-            match crate::io::load_image(&path) {
-                Ok(color_image) => {
-                    let texture = ctx.load_texture(
-                        path.to_string_lossy(),
-                        color_image,
-                        egui::TextureOptions::LINEAR,
-                    );
-                    Ok(Content::Image(texture))
-                }
-                Err(e) => Err(e),
-            }
+    fn image_content(&mut self, name: &str, rgba: image::RgbaImage, ctx: &egui::Context) -> Content {
+        let rgba = self.image_orientations.get(Path::new(name)).copied().unwrap_or_default().apply(rgba);
+        let texture = ctx.load_texture(
+            name,
+            crate::imaging::adjusted_color_image(&rgba, self.image_adjust),
+            self.texture_options(),
+        );
+        self.image_histogram = Some(crate::imaging::histogram(&rgba));
+        self.image_pixels = Some(rgba);
+        Content::Image(texture)
+    }
+
+    pub(crate) fn texture_options(&self) -> egui::TextureOptions {
+        if self.image_nearest { egui::TextureOptions::NEAREST } else { egui::TextureOptions::LINEAR }
+    }
+
+    /// Re-upload the image texture from the decoded pixels with the current adjustments and sampling.
+    pub(crate) fn rebuild_texture(&mut self) {
+        let options = self.texture_options();
+        // Other animation frames are redone as they come up
+        if let Some(animation) = &mut self.animation {
+            animation.shown.fill_with(|| None);
+        }
+        if let (Some(Content::Image(texture)), Some(rgba)) = (&mut self.content, &self.image_pixels) {
+            texture.set(crate::imaging::adjusted_color_image(rgba, self.image_adjust), options);
+        }
+    }
+
+    fn text_content(&mut self, loaded: crate::io::LoadedText, ext: &str) -> Content {
+        let crate::io::LoadedText { text, invalid_offsets, lines, encoding, line_starts, line_offsets } = loaded;
+        self.text_line_starts = line_starts;
+        self.text_line_offsets = line_offsets;
+        self.text_encoding = encoding;
+        self.text_line_count = lines;
+        self.text_is_lossy = !invalid_offsets.is_empty();
+        self.text_invalid_offsets = invalid_offsets;
+        self.folded.clear();
+        self.text_views(&text, ext);
+        Content::Text(text)
+    }
+
+    /// Add text appended to the shown file, flashing it and keeping folds and the search position.
+    fn append_text(&mut self, appended: &str, ext: &str) {
+        let Some(Content::Text(mut text)) = self.content.take() else { return };
+        let base = text.len();
+        // The last line may have grown as well as new ones been added
+        let first = self.text_line_starts.len() - 1;
+        let first_start = self.text_line_starts[first];
+        let counted = !self.search_query.is_empty() && base <= self.highlight_threshold;
+        let old_last = if counted { crate::search::recompute_count(self.search_pattern.as_ref(), &text[first_start..]) } else { 0 };
+        text.push_str(appended);
+        self.text_line_starts.extend(memchr::memchr_iter(b'\n', appended.as_bytes()).map(|i| base + i + 1));
+        self.text_line_count = crate::io::line_count_of(&text, &self.text_line_starts);
+        self.line_flash = Some((first..usize::MAX, std::time::Instant::now()));
+        self.text_views(&text, ext);
+        // Only the grown last line and the new ones need counting again
+        self.search_count = if self.search_query.is_empty() || text.len() > self.highlight_threshold {
+            0
+        } else if counted {
+            self.search_count - old_last + crate::search::recompute_count(self.search_pattern.as_ref(), &text[first_start..])
         } else {
-            match crate::io::load_text(&path) {
-                Ok((text, lossy, lines)) => {
-                    self.text_is_big = text.len() >= BIG_TEXT_CHAR_THRESHOLD || lines >= 50_000;
-                    self.text_line_count = lines;
-                    self.text_is_lossy = lossy;
-                    Ok(Content::Text(text))
-                }
-                Err(e) => Err(e),
-            }
+            crate::search::recompute_count(self.search_pattern.as_ref(), &text)
         };
+        self.search_current = self.search_current.min(self.search_count.saturating_sub(1));
+        self.content = Some(Content::Text(text));
+    }
 
-        match loaded {
-            Ok(content) => {
-                self.content = Some(content);
-                self.current_path = Some(path.clone());
-                self.recent_files.retain(|p| p != &path);
-                self.recent_files.push(path);
-                if self.recent_files.len() > MAX_RECENT_FILES {
-                    let overflow = self.recent_files.len() - MAX_RECENT_FILES;
-                    self.recent_files.drain(0..overflow);
+    /// Rebuild what's derived from the shown text: the table, JSON tree, outline, folds and diff.
+    fn text_views(&mut self, text: &str, ext: &str) {
+        // Old spans stay until the new ones arrive, so a tail reload doesn't flash uncolored
+        self.syntax_key = None;
+        self.syntax_job = None;
+        self.text_is_big = self.is_big_text(text.len(), self.text_line_count);
+        self.fold_ranges.clear();
+        self.outline.clear();
+        // A reload keeps the table sorted as it was
+        let sort = self.table.take().and_then(|table| table.sort);
+        self.table_error = None;
+        if !self.text_is_big && let Some(delimiter) = crate::table::delimiter(ext) {
+            match crate::table::parse(text, delimiter) {
+                Ok(mut table) => {
+                    if let Some((column, ascending)) = sort.filter(|(column, _)| *column < table.header.len()) {
+                        table.sort_by(column, ascending);
+                    }
+                    self.table = Some(table);
                 }
-                crate::settings::save_settings_to_disk(self);
+                Err(e) => self.table_error = Some(e),
+            }
+        }
+        if let Some(diff) = &self.text_diff {
+            let (path, left, right) = (diff.path.clone(), text.to_string(), diff.right_text.clone());
+            self.start_text_diff(move || Ok(crate::diff::TextDiff::new(path, &left, right)));
+        }
+        // Invalid JSON has no tree; the format check already says why. A reload keeps the filter.
+        let filter = self.json_tree.take().map(|tree| tree.filter).unwrap_or_default();
+        if !self.text_is_big && ext == "json" && let Ok(mut tree) = crate::jsontree::JsonTree::parse(text) {
+            if !filter.is_empty() {
+                tree.set_filter(&filter);
+            }
+            self.json_tree = Some(tree);
+        }
+        if !self.text_is_big {
+            self.outline = crate::outline::extract(text, ext);
+            if matches!(ext, "xml" | "html" | "htm") {
+                self.fold_ranges = crate::format::xml_folds(text);
             }
-            Err(e) => self.error_message = Some(e),
         }
     }
-}
 
-impl Default for FileViewerApp {
-    fn default() -> Self {
-        Self {
-            content: None,
-            current_path: None,
-            error_message: None,
-            dark_mode: true,
-            theme: Theme::Dark,
-            follow_system_theme: true,
-            recent_files: Vec::new(),
-            show_line_numbers: true,
-            word_wrap: true,
-            text_zoom: 1.0,
-            image_zoom: 1.0,
-            show_about: false,
-            image_fit: false,
-            accent_rgb: [93, 156, 255],
-            spacing_scale: 1.0,
-            theme_rounding: 6,
-            show_theme_editor: false,
-            text_is_big: false,
-            text_line_count: 0,
-            text_is_lossy: false,
-            search_query: String::new(),
-            search_active: false,
-            search_count: 0,
-            search_current: 0,
-        }
+    fn clear_syntax(&mut self) {
+        self.syntax_spans.clear();
+        self.syntax_key = None;
+        self.syntax_job = None;
     }
-}
 
-impl eframe::App for FileViewerApp {
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        if let Ok(s) = serde_json::to_string(self) {
-            storage.set_string(eframe::APP_KEY, s);
-        }
-        crate::settings::save_settings_to_disk(self);
+    /// Language of the shown text: the toolbar override, else detected from the extension or first line.
+    pub(crate) fn language(&self) -> Option<&'static str> {
+        let Some(Content::Text(text)) = &self.content else { return None };
+        let first_line = text.lines().next().unwrap_or("");
+        crate::syntax::language_for(&self.current_ext(), first_line, self.language_override.as_deref())
+    }
+
+    pub(crate) fn set_language_override(&mut self, language: Option<String>) {
+        self.language_override = language;
+        self.clear_syntax();
     }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Follow system theme if enabled
-        if self.follow_system_theme {
-            let sys_dark = matches!(dark_light::detect(), Ok(dark_light::Mode::Dark));
-            if sys_dark != self.dark_mode {
-                self.dark_mode = sys_dark;
-                self.theme = if self.dark_mode { Theme::Dark } else { Theme::Light };
+    /// Highlight the shown text on a worker thread whenever its language or the theme changes.
+    fn poll_syntax(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.syntax_job {
+            match rx.try_recv() {
+                Ok(spans) => self.syntax_spans = spans,
+                Err(std::sync::mpsc::TryRecvError::Empty) => return,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {}
             }
+            self.syntax_job = None;
         }
-        // Apply visuals each frame
-        self.apply_theme(ctx);
-
-        let mut file_to_load: Option<PathBuf> = None;
+        let Some(Content::Text(text)) = &self.content else { return };
+        if self.text_is_big || text.len() > self.highlight_threshold {
+            return;
+        }
+        let Some(language) = self.language() else {
+            self.clear_syntax();
+            return;
+        };
+        let key = (language, self.theme);
+        if self.syntax_key == Some(key) {
+            return;
+        }
+        self.syntax_key = Some(key);
+        self.syntax_job = Some(crate::syntax::highlight_in_background(text.clone(), language, self.theme, ctx));
+    }
 
-        // Drag & Drop: preview and open files
+    /// With auto-format on, the pretty-printed version of structured text, keeping the original in `format_alt`.
+    fn auto_formatted(&mut self, loaded: crate::io::LoadedText, ext: &str) -> crate::io::LoadedText {
+        if !self.auto_format || !loaded.invalid_offsets.is_empty() {
+            return loaded;
+        }
+        match crate::format::pretty(&loaded.text, ext) {
+            Some(Ok(pretty)) => {
+                self.format_alt = Some(loaded.text);
+                self.format_shown = true;
+                crate::io::LoadedText::plain(pretty, loaded.encoding)
+            }
+            Some(Err(e)) => {
+                self.format_warning = Some(e);
+                loaded
+            }
+            None => loaded,
+        }
+    }
+
+    /// Swap between the formatted and original text of an auto-formatted file.
+    pub(crate) fn toggle_formatted(&mut self) {
+        let Some(alt) = self.format_alt.take() else { return };
+        let Some(Content::Text(current)) = self.content.take() else { return };
+        self.format_alt = Some(current);
+        self.format_shown = !self.format_shown;
+        let ext = self.current_ext();
+        // Formatted text has no file offsets; count within the text shown
+        let loaded = crate::io::LoadedText::plain(alt, self.text_encoding);
+        self.content = Some(self.text_content(loaded, &ext));
+        self.refresh_search();
+    }
+
+    /// Re-read the current file decoded as `encoding`, or detected afresh when None, keeping zoom,
+    /// scroll and bookmarks. The choice also applies to reloads of the file.
+    pub(crate) fn reopen_with_encoding(&mut self, encoding: Option<&'static encoding_rs::Encoding>, ctx: &egui::Context) {
+        if !self.can_tail() || !matches!(self.content, Some(Content::Text(_))) {
+            return;
+        }
+        let Some(path) = self.current_path.clone() else { return };
+        self.encoding_override = encoding;
+        let shared = self.shared_read;
+        self.start_text_reload(true, ctx, move || crate::io::load_text(&path, shared, encoding).map(Reloaded::Whole));
+    }
+
+    /// File byte offset where each line of the shown text starts.
+    pub(crate) fn text_file_offsets(&self) -> &[usize] {
+        if self.text_line_offsets.is_empty() { &self.text_line_starts } else { &self.text_line_offsets }
+    }
+
+    /// Re-read the shown text with `job` on a worker thread; `poll_text_reload` shows it.
+    fn start_text_reload(&mut self, reencode: bool, ctx: &egui::Context, job: impl FnOnce() -> Result<Reloaded, String> + Send + 'static) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(job());
+            ctx.request_repaint();
+        });
+        self.text_reload = Some(TextReload { rx, reencode });
+    }
+
+    /// Show the re-read text in place: zoom, search and bookmarks stay as they are.
+    fn poll_text_reload(&mut self) {
+        let Some(reload) = &self.text_reload else { return };
+        let result = match reload.rx.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("Reloading stopped unexpectedly".to_string()),
+        };
+        let Some(TextReload { reencode, .. }) = self.text_reload.take() else { return };
+        let ext = self.current_ext();
+        match result {
+            Ok(Reloaded::Appended(appended)) => {
+                if !appended.is_empty() {
+                    self.append_text(&appended, &ext);
+                }
+            }
+            Ok(Reloaded::Whole(loaded)) => {
+                // Flash from the first changed line down: the appended lines, or everything after an edit
+                if !reencode && let Some(Content::Text(old)) = &self.content {
+                    let same = old.bytes().zip(loaded.text.bytes()).take_while(|(a, b)| a == b).count();
+                    if same < loaded.text.len() {
+                        let line = loaded.line_starts.partition_point(|&start| start <= same) - 1;
+                        self.line_flash = Some((line..usize::MAX, std::time::Instant::now()));
+                    }
+                }
+                // Tailing follows the raw file, as does a change of encoding
+                self.format_alt = None;
+                self.format_shown = false;
+                self.format_warning = None;
+                let loaded = if self.tail_mode || reencode { loaded } else { self.auto_formatted(loaded, &ext) };
+                self.content = Some(self.text_content(loaded, &ext));
+                self.refresh_search();
+            }
+            Ok(Reloaded::Index(None)) => {}
+            Ok(Reloaded::Index(Some(index))) => {
+                let Some(Content::LargeText(old)) = &self.content else { return };
+                let (old_len, old_lines) = (old.len(), old.line_count());
+                self.text_line_count = index.line_count();
+                let shrank = index.len() < old_len;
+                self.content = Some(Content::LargeText(index));
+                if shrank {
+                    self.refresh_search();
+                } else {
+                    // The last line may have grown as well as new ones been added
+                    let first = old_lines.saturating_sub(1);
+                    self.line_flash = Some((first..usize::MAX, std::time::Instant::now()));
+                    self.search_large_from(first);
+                }
+            }
+            Err(e) => {
+                if !reencode {
+                    self.tail_mode = false;
+                }
+                self.error_message = Some(e);
+                self.error_path = None;
+            }
+        }
+    }
+
+    pub fn load_file(&mut self, path: PathBuf, ctx: &egui::Context) {
+        if self.unknown_files == UnknownFiles::Ask && !crate::io::is_known_type(&path) {
+            self.pending_unknown = Some(path);
+            return;
+        }
+        self.load_file_as(path, OpenAs::Auto, ctx);
+    }
+
+    pub(crate) fn load_file_as(&mut self, path: PathBuf, open_as: OpenAs, ctx: &egui::Context) {
+        self.reset_content();
+        self.archive_path = None;
+        // Reading and decoding happen on a worker thread; `poll_loading` picks up the result
+        let (tx, rx) = std::sync::mpsc::channel();
+        let options = self.read_options(open_as);
+        let worker_path = path.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(read_for_view(worker_path, options));
+            ctx.request_repaint();
+        });
+        self.loading = Some(Loading { path, started: std::time::Instant::now(), rx, restore: None, line: None });
+    }
+
+    fn read_options(&self, open_as: OpenAs) -> ReadOptions {
+        ReadOptions {
+            open_as,
+            follow_symlinks: self.follow_symlinks,
+            unknown_files: self.unknown_files,
+            shared: self.shared_read,
+            max_file_bytes: self.max_file_bytes(),
+            max_text_bytes: self.max_text_bytes(),
+        }
+    }
+
+    /// Show the file once the worker has read it. Dropping `loading` abandons a load in progress.
+    fn poll_loading(&mut self, ctx: &egui::Context) {
+        let Some(loading) = &self.loading else { return };
+        let result = match loading.rx.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("Loading stopped unexpectedly".to_string()),
+        };
+        let Some(Loading { path: attempted, restore, line, .. }) = self.loading.take() else { return };
+        let file = match result {
+            Ok(file) => file,
+            Err(e) => {
+                self.error_message = Some(e);
+                self.error_path = Some(attempted);
+                return;
+            }
+        };
+        let path = file.path;
+        self.link_target = file.link_target;
+        let content = match file.data {
+            FileData::Hex(bytes) => Content::Hex(bytes),
+            FileData::Archive(listing) => {
+                self.archive_path = Some(path.clone());
+                Content::Archive(listing)
+            }
+            FileData::Image(rgba) => self.image_content(&path.to_string_lossy(), rgba, ctx),
+            FileData::Animation(mut frames) => {
+                // The first frame is oriented by `image_content`
+                let orientation = self.image_orientations.get(&path).copied().unwrap_or_default();
+                for frame in &mut frames[1..] {
+                    frame.0 = orientation.apply(std::mem::take(&mut frame.0));
+                }
+                let first = std::mem::take(&mut frames[0].0);
+                let shown = frames.iter().map(|_| None).collect();
+                self.animation = Some(Animation { frames, index: 0, playing: true, shown_at: std::time::Instant::now(), shown });
+                self.image_content(&path.to_string_lossy(), first, ctx)
+            }
+            FileData::Pdf(page) => {
+                self.pdf = Some(PdfView { page: 0, pages: page.count, requested: None, renderer: crate::pdf::Renderer::new(path.clone(), ctx) });
+                self.image_content(&path.to_string_lossy(), page.image, ctx)
+            }
+            FileData::Text(loaded) => {
+                let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+                let loaded = self.auto_formatted(loaded, &ext);
+                self.text_content(loaded, &ext)
+            }
+            FileData::LargeText(index) => {
+                self.text_line_offsets.clear();
+                self.text_line_starts.clear();
+                self.text_invalid_offsets.clear();
+                self.text_is_lossy = false;
+                self.text_encoding = index.encoding.name();
+                self.text_line_count = index.line_count();
+                self.text_is_big = true;
+                Content::LargeText(index)
+            }
+        };
+        // List the folder in the background so the first Prev/Next is instant
+        if matches!(content, Content::Text(_) | Content::LargeText(_) | Content::Image(_))
+            && let Some(dir) = path.parent()
+        {
+            self.scan_siblings(dir.to_path_buf());
+        }
+        self.content = Some(content);
+        self.disk_stamp = file_stamp(&path);
+        self.current_path = Some(path.clone());
+        self.refresh_search();
+        if let Some(line) = line
+            && matches!(self.content, Some(Content::Text(_) | Content::LargeText(_)))
+        {
+            self.jump_to_line(line);
+        }
+        if let Some(state) = restore {
+            self.text_zoom = state.text_zoom;
+            self.image_zoom = state.image_zoom;
+            self.image_fit = state.image_fit;
+            self.pending_scroll_offset = Some(egui::vec2(state.scroll_offset[0], state.scroll_offset[1]));
+        } else if !self.preserve_zoom {
+            self.text_zoom = self.default_text_zoom;
+            self.image_zoom = self.default_image_zoom;
+            self.image_fit = self.default_image_fit;
+        }
+        if !self.is_pinned(&path) {
+            self.add_recent(path);
+        }
+        crate::settings::mark_settings_dirty(self);
+    }
+
+    /// Show page `index` of the open PDF, rendered on a worker thread.
+    pub(crate) fn go_to_pdf_page(&mut self, index: usize) {
+        let Some(pdf) = &mut self.pdf else { return };
+        let index = index.min(pdf.pages.saturating_sub(1));
+        if index == pdf.target() {
+            return;
+        }
+        pdf.requested = Some(index);
+        pdf.renderer.request(index);
+    }
+
+    fn poll_pdf(&mut self, ctx: &egui::Context) {
+        let Some(pdf) = &mut self.pdf else { return };
+        if pdf.requested.is_none() {
+            return;
+        }
+        let Some(result) = pdf.renderer.poll() else { return };
+        match result {
+            Ok((index, page)) => {
+                pdf.page = index;
+                // Pages rendered on the way to a later request are shown but keep it pending
+                if pdf.requested == Some(index) {
+                    pdf.requested = None;
+                }
+                let name = self.current_path.as_ref().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+                self.compare = None;
+                self.compare_job = None;
+                self.measure_points.clear();
+                self.crop_rect = None;
+                self.content = Some(self.image_content(&name, page.image, ctx));
+            }
+            Err(e) => {
+                pdf.requested = None;
+                self.show_toast(e);
+            }
+        }
+    }
+
+    /// Show frame `index` of the animation, wrapping around at the end.
+    pub(crate) fn show_frame(&mut self, index: usize, ctx: &egui::Context) {
+        let options = self.texture_options();
+        let Some(animation) = &mut self.animation else { return };
+        let index = index % animation.frames.len();
+        animation.shown_at = std::time::Instant::now();
+        if index == animation.index {
+            return;
+        }
+        let (Some(Content::Image(texture)), Some(pixels), Some(histogram)) = (&self.content, self.image_pixels.take(), self.image_histogram.take()) else { return };
+        // The shown frame's pixels, texture and histogram go back with it for next time round
+        let old = animation.index;
+        animation.frames[old].0 = pixels;
+        animation.shown[old] = Some((texture.clone(), histogram));
+        animation.index = index;
+        let pixels = std::mem::take(&mut animation.frames[index].0);
+        let (texture, histogram) = animation.shown[index].take().unwrap_or_else(|| {
+            let name = self.current_path.as_ref().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+            let texture = ctx.load_texture(name, crate::imaging::adjusted_color_image(&pixels, self.image_adjust), options);
+            (texture, crate::imaging::histogram(&pixels))
+        });
+        self.content = Some(Content::Image(texture));
+        self.image_histogram = Some(histogram);
+        self.image_pixels = Some(pixels);
+    }
+
+    /// Rotate or mirror the shown image; the file keeps that orientation for the rest of the session.
+    pub(crate) fn transform_image(&mut self, t: crate::imaging::Transform) {
+        let (Some(path), Some(rgba)) = (self.current_path.clone(), &self.image_pixels) else { return };
+        self.image_pixels = Some(crate::imaging::transformed(rgba, t));
+        if let Some(animation) = &mut self.animation {
+            for frame in &mut animation.frames {
+                frame.0 = crate::imaging::transformed(&frame.0, t);
+            }
+        }
+        let orientation = self.image_orientations.get(&path).copied().unwrap_or_default().then(t);
+        if orientation.is_identity() {
+            self.image_orientations.remove(&path);
+        } else {
+            self.image_orientations.insert(path, orientation);
+        }
+        // Points and selections were in the old pixel coordinates
+        self.measure_points.clear();
+        self.crop_rect = None;
+        self.crop_anchor = None;
+        self.rebuild_texture();
+    }
+
+    /// Pause the animation and move one frame forward or back.
+    pub(crate) fn step_frame(&mut self, forward: bool, ctx: &egui::Context) {
+        let Some(animation) = &mut self.animation else { return };
+        animation.playing = false;
+        let count = animation.frames.len();
+        let index = if forward { animation.index + 1 } else { animation.index + count - 1 };
+        self.show_frame(index, ctx);
+    }
+
+    pub(crate) fn toggle_animation(&mut self) {
+        if let Some(animation) = &mut self.animation {
+            animation.playing = !animation.playing;
+            animation.shown_at = std::time::Instant::now();
+        }
+    }
+
+    /// Advance a playing animation once its frame's delay is up.
+    fn poll_animation(&mut self, ctx: &egui::Context) {
+        let Some(animation) = &self.animation else { return };
+        if !animation.playing {
+            return;
+        }
+        let delay = animation.frames[animation.index].1;
+        let shown = animation.shown_at.elapsed();
+        if shown < delay {
+            ctx.request_repaint_after(delay - shown);
+            return;
+        }
+        self.show_frame(animation.index + 1, ctx);
+        if let Some(animation) = &self.animation {
+            ctx.request_repaint_after(animation.frames[animation.index].1);
+        }
+    }
+
+    /// Make any missing thumbnails for the listed folder, starting with the image at `first`.
+    pub(crate) fn request_thumbnails(&mut self, first: usize, ctx: &egui::Context) {
+        let Some(siblings) = &self.siblings else { return };
+        self.thumbnails.request(&siblings.dir, siblings.images(), first, self.max_file_bytes(), self.shared_read, ctx);
+    }
+
+    /// Start or stop stepping through the folder's images.
+    pub(crate) fn toggle_slideshow(&mut self, ctx: &egui::Context) {
+        if self.slideshow.is_some() {
+            self.stop_slideshow(ctx);
+            return;
+        }
+        self.slideshow = Some(Slideshow { paused: false, shown_at: std::time::Instant::now() });
+        if self.slideshow_fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        }
+        self.show_toast("Slideshow: Space pauses, Esc stops");
+    }
+
+    pub(crate) fn stop_slideshow(&mut self, ctx: &egui::Context) {
+        if self.slideshow.take().is_some() && self.slideshow_fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+        }
+    }
+
+    pub(crate) fn toggle_slideshow_pause(&mut self) {
+        if let Some(show) = &mut self.slideshow {
+            show.paused = !show.paused;
+            show.shown_at = std::time::Instant::now();
+        }
+    }
+
+    /// The next image once the current one has been up long enough. Leaving images for other
+    /// content ends the slideshow.
+    fn poll_slideshow(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        let show = self.slideshow.as_mut()?;
+        if self.loading.is_some() {
+            // Time the next image from when it appears
+            show.shown_at = std::time::Instant::now();
+            return None;
+        }
+        if !matches!(self.content, Some(Content::Image(_))) || self.pdf.is_some() {
+            self.stop_slideshow(ctx);
+            return None;
+        }
+        if show.paused {
+            return None;
+        }
+        let interval = std::time::Duration::from_secs_f32(self.slideshow_secs);
+        let shown = show.shown_at.elapsed();
+        if shown < interval {
+            ctx.request_repaint_after(interval - shown);
+            return None;
+        }
+        show.shown_at = std::time::Instant::now();
+        ctx.request_repaint_after(interval);
+        self.neighbor(true)
+    }
+
+    /// The file being shown, or the one being loaded.
+    pub(crate) fn shown_path(&self) -> Option<&Path> {
+        self.current_path.as_deref().or(self.loading.as_ref().map(|l| l.path.as_path()))
+    }
+
+    /// Read `name` from the archive being browsed and show it like a regular file.
+    /// Extract and decode entry `name` of the open archive on a worker thread; `poll_archive_entry` shows it.
+    pub(crate) fn open_archive_entry(&mut self, name: &str, ctx: &egui::Context) {
+        let Some(archive) = self.archive_path.clone() else { return };
+        let (max_bytes, shared) = (self.max_file_bytes(), self.shared_read);
+        let name = name.to_string();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let read = || {
+                let bytes = crate::archive::read_entry(&archive, &name, max_bytes, shared)?;
+                let entry_path = archive.join(&name);
+                let data = if crate::io::is_supported_image(&entry_path) {
+                    FileData::Image(crate::io::load_image_bytes(&bytes)?)
+                } else {
+                    FileData::Text(crate::io::decode_text(bytes))
+                };
+                Ok((entry_path, data))
+            };
+            let _ = tx.send(read());
+            ctx.request_repaint();
+        });
+        // A newer pick replaces one still being read
+        self.archive_entry_job = Some(rx);
+    }
+
+    fn poll_archive_entry(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.archive_entry_job else { return };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("Loading stopped unexpectedly".to_string()),
+        };
+        self.archive_entry_job = None;
+        let (entry_path, data) = match result {
+            Ok(read) => read,
+            Err(e) => {
+                self.error_message = Some(e);
+                return;
+            }
+        };
+        self.reset_content();
+        let content = match data {
+            FileData::Image(rgba) => self.image_content(&entry_path.to_string_lossy(), rgba, ctx),
+            FileData::Text(loaded) => {
+                let ext = entry_path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+                let loaded = self.auto_formatted(loaded, &ext);
+                self.text_content(loaded, &ext)
+            }
+            _ => return,
+        };
+        self.content = Some(content);
+        self.current_path = Some(entry_path);
+        self.refresh_search();
+    }
+
+    /// True while an archive entry is being extracted to be shown.
+    pub(crate) fn is_opening_archive_entry(&self) -> bool {
+        self.archive_entry_job.is_some()
+    }
+
+    /// True while showing a file extracted from an archive rather than one on disk.
+    pub(crate) fn is_archive_entry_view(&self) -> bool {
+        self.archive_path.is_some() && self.current_path != self.archive_path
+    }
+
+    /// Show the clipboard image, or its text, under the `CLIPBOARD_PATH` stand-in path.
+    /// `fallback_text` is used when the clipboard can't be read directly (e.g. from a paste event).
+    pub(crate) fn paste_from_clipboard(&mut self, fallback_text: Option<String>, ctx: &egui::Context) {
+        let image = self.clipboard.as_mut().and_then(|c| c.get_image().ok());
+        let text = if image.is_none() {
+            self.clipboard.as_mut().and_then(|c| c.get_text().ok()).or(fallback_text)
+        } else {
+            None
+        };
+        let content = if let Some(img) = image {
+            let Some(rgba) = image::RgbaImage::from_raw(img.width as u32, img.height as u32, img.bytes.into_owned()) else {
+                self.error_message = Some("Clipboard image has an unexpected pixel layout".to_string());
+                return;
+            };
+            self.reset_content();
+            self.image_content(CLIPBOARD_PATH, rgba, ctx)
+        } else if let Some(text) = text.filter(|t| !t.is_empty()) {
+            self.reset_content();
+            self.text_content(crate::io::LoadedText::plain(text, "UTF-8"), "")
+        } else {
+            self.show_toast("Clipboard has no image or text");
+            return;
+        };
+        self.content = Some(content);
+        self.current_path = Some(PathBuf::from(CLIPBOARD_PATH));
+        self.refresh_search();
+    }
+
+    pub(crate) fn can_tail(&self) -> bool {
+        matches!(self.content, Some(Content::Text(_) | Content::LargeText(_))) && !self.is_clipboard_view() && !self.is_archive_entry_view()
+    }
+
+    pub(crate) fn set_tail_mode(&mut self, on: bool) {
+        self.tail_mode = on && self.can_tail();
+        if self.tail_mode {
+            // Jump to the end; the scroll area then sticks there until scrolled away from
+            self.pending_scroll_offset = Some(egui::vec2(self.text_scroll_offset.x, f32::MAX));
+        }
+    }
+
+    /// Next or previous file of the current kind in the folder. Returns None while the folder is
+    /// still being scanned; the move then happens once the scan finishes.
+    pub(crate) fn neighbor(&mut self, forward: bool) -> Option<PathBuf> {
+        let image = match self.content {
+            Some(Content::Image(_)) => true,
+            Some(Content::Text(_) | Content::LargeText(_)) => false,
+            _ => return None,
+        };
+        let cur = self.current_path.clone()?;
+        let dir = cur.parent()?;
+        if let Some(siblings) = &self.siblings
+            && siblings.is_fresh(dir, self.show_hidden_files)
+        {
+            return siblings.neighbor(&cur, image, forward);
+        }
+        self.scan_siblings(dir.to_path_buf());
+        self.pending_neighbor = Some(forward);
+        None
+    }
+
+    /// Start listing `dir` on a worker thread unless it is cached or already being scanned.
+    fn scan_siblings(&mut self, dir: PathBuf) {
+        if self.siblings.as_ref().is_some_and(|s| s.is_fresh(&dir, self.show_hidden_files))
+            || self.sibling_scan.as_ref().is_some_and(|(scanning, _, _)| scanning == &dir)
+        {
+            return;
+        }
+        let (tx, rx) = std::sync::mpsc::channel();
+        let scan_dir = dir.clone();
+        let include_hidden = self.show_hidden_files;
+        std::thread::spawn(move || {
+            let _ = tx.send(crate::io::Siblings::scan(&scan_dir, include_hidden));
+        });
+        self.sibling_scan = Some((dir, std::time::Instant::now(), rx));
+    }
+
+    /// Pick up a finished scan; returns the file to open if Prev/Next was pressed meanwhile.
+    fn poll_sibling_scan(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        let (_, _, rx) = self.sibling_scan.as_ref()?;
+        match rx.try_recv() {
+            Ok(siblings) => {
+                self.siblings = Some(siblings);
+                self.sibling_scan = None;
+                let forward = self.pending_neighbor.take()?;
+                self.neighbor(forward)
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(50));
+                None
+            }
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.sibling_scan = None;
+                self.pending_neighbor = None;
+                None
+            }
+        }
+    }
+
+    /// How often to wake up for background checks; stretched in low power mode.
+    pub(crate) fn poll_interval(&self, normal: std::time::Duration) -> std::time::Duration {
+        if self.low_power { normal * 5 } else { normal }
+    }
+
+    /// The open file can be re-read from disk: not the clipboard or an archive entry.
+    pub(crate) fn can_reload(&self) -> bool {
+        self.current_path.is_some() && !self.is_clipboard_view() && !self.is_archive_entry_view()
+    }
+
+    /// Reload the open file when it changes on disk, in tail mode or with auto-reload on.
+    fn poll_file_changes(&mut self, ctx: &egui::Context) {
+        if self.loading.is_some() || self.text_reload.is_some() {
+            return;
+        }
+        if !(self.tail_mode || self.auto_reload) || !self.can_reload() {
+            self.file_watch = None;
+            return;
+        }
+        let Some(path) = self.current_path.clone() else { return };
+        if self.file_watch.as_ref().is_none_or(|watch| watch.path != path) {
+            self.file_watch = Some(crate::watch::FileWatch::new(path.clone(), ctx));
+        }
+        let gap = self.poll_interval(std::time::Duration::from_millis(250));
+        let poll = self.poll_interval(std::time::Duration::from_secs(1));
+        if !self.file_watch.as_mut().is_some_and(|watch| watch.changed(ctx, gap, poll)) {
+            return;
+        }
+        let stamp = file_stamp(&path);
+        if stamp.is_none() || stamp == self.disk_stamp {
+            return;
+        }
+        self.disk_stamp = stamp;
+        if !self.can_tail() {
+            // Images and the like load afresh, keeping zoom
+            if let Some(state) = self.view_state() {
+                self.restore_view_state(state, ctx);
+            }
+            return;
+        }
+        if let Some(Content::LargeText(index)) = &self.content {
+            // Indexing what was appended, or the whole file again after it shrank, happens on a worker
+            let mut index = index.clone();
+            self.start_text_reload(false, ctx, move || {
+                let changed = index.refresh()?;
+                Ok(Reloaded::Index(changed.then_some(index)))
+            });
+            return;
+        }
+        if stamp.is_some_and(|(_, len)| len > self.max_text_bytes()) {
+            self.tail_mode = false;
+            self.error_message = Some(format!("Reloading stopped: file grew beyond {}MB; open it again to view it indexed", self.max_text_mb));
+            return;
+        }
+        // A tailed UTF-8 file that only grew is read from where the shown text ends
+        let shown = match &self.content {
+            Some(Content::Text(text)) if self.tail_mode && self.text_encoding == "UTF-8" && !self.text_is_lossy && self.format_alt.is_none() => {
+                let tail = text.len().saturating_sub(APPEND_CHECK_BYTES);
+                Some(ShownText { len: text.len(), tail: text.as_bytes()[tail..].to_vec() })
+            }
+            _ => None,
+        };
+        let (shared, encoding) = (self.shared_read, self.encoding_override);
+        self.start_text_reload(false, ctx, move || reload_text(&path, shared, encoding, shown));
+    }
+
+    /// Saved position of a tool window, pulled back on screen if the monitor layout changed.
+    fn remembered_window_pos(&self, ctx: &egui::Context, title: &str) -> Option<egui::Pos2> {
+        if !self.remember_window_positions {
+            return None;
+        }
+        let [x, y] = *self.window_positions.get(title)?;
+        let screen = ctx.screen_rect();
+        // Keep enough of the title bar visible to grab it again
+        let x = x.clamp(screen.left(), (screen.right() - 80.0).max(screen.left()));
+        let y = y.clamp(screen.top(), (screen.bottom() - 30.0).max(screen.top()));
+        Some(egui::pos2(x, y))
+    }
+
+    fn track_window_pos(&mut self, ctx: &egui::Context, title: &str, rect: Option<egui::Rect>) {
+        let Some(rect) = rect else { return };
+        // Wait for drags to finish rather than saving every frame of one
+        if !self.remember_window_positions || ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        let pos = [rect.min.x.round(), rect.min.y.round()];
+        if self.window_positions.get(title) != Some(&pos) {
+            self.window_positions.insert(title.to_string(), pos);
+            crate::settings::mark_settings_dirty(self);
+        }
+    }
+
+    pub(crate) fn open_in_browser(&mut self) {
+        let Some(path) = self.current_path.clone() else { return };
+        if let Err(e) = opener::open_browser(&path) {
+            self.error_message = Some(format!("Couldn't open a browser: {}", e));
+            self.error_path = None;
+        }
+    }
+
+    /// Print an image straight away; for text, offer the line range and options first.
+    pub(crate) fn start_print(&mut self) {
+        match &self.content {
+            Some(Content::Image(_)) => self.print_image(),
+            Some(Content::Text(_) | Content::LargeText(_)) => {
+                self.print_range = (1, self.text_line_count.clamp(1, MAX_PRINT_LINES));
+                self.print_open = true;
+            }
+            _ => self.show_toast("Only text and images can be printed"),
+        }
+    }
+
+    /// Print the chosen lines, through a page the browser opens with its print dialog up.
+    pub(crate) fn print_text(&mut self) {
+        let (from, to) = self.print_range;
+        let (from, to) = (from.max(1), to.min(self.text_line_count).max(from.max(1)));
+        if to - from >= MAX_PRINT_LINES {
+            self.show_toast(format!("Print at most {} lines at a time", MAX_PRINT_LINES));
+            return;
+        }
+        let mut title = self.print_title();
+        if (from, to) != (1, self.text_line_count) {
+            title = format!("{} — lines {}–{}", title, from, to);
+        }
+        let page = match &self.content {
+            Some(Content::Text(text)) => {
+                let lines: Vec<&str> = text.lines().skip(from - 1).take(to - from + 1).collect();
+                // Colors for paper: the light palette's whatever the theme on screen
+                let (screen, paper) = (crate::highlight::Palette::for_theme(self.theme), crate::highlight::Palette::for_theme(Theme::Light));
+                let spans: Option<Vec<crate::syntax::LineSpans>> = self.syntax_spans.get(from - 1..).filter(|spans| self.print_colors && !spans.is_empty()).map(|spans| {
+                    spans
+                        .iter()
+                        .take(lines.len())
+                        .map(|line| line.iter().map(|(range, color)| (range.clone(), screen.translate(*color, &paper))).collect())
+                        .collect()
+                });
+                crate::html::print_text(&title, &lines, from, self.print_line_numbers, spans.as_deref())
+            }
+            Some(Content::LargeText(index)) => {
+                let lines = index.lines(from - 1..to);
+                let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                crate::html::print_text(&title, &lines, from, self.print_line_numbers, None)
+            }
+            _ => return,
+        };
+        self.print_open = false;
+        self.open_print_page(page, None);
+    }
+
+    fn print_image(&mut self) {
+        let title = self.print_title();
+        let Some(rgba) = &self.image_pixels else { return };
+        // Printed as shown: rotated, flipped and on the current animation frame
+        let page = crate::html::print_image(&title, PRINT_IMAGE_NAME);
+        let rgba = rgba.clone();
+        self.open_print_page(page, Some(rgba));
+    }
+
+    fn print_title(&self) -> String {
+        self.current_path.as_ref().and_then(|p| p.file_name()).map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+    }
+
+    /// Write a print page, and the image it shows, to a new private temporary folder and open it in the browser.
+    fn open_print_page(&mut self, page: String, image: Option<image::RgbaImage>) {
+        // Only the latest print is kept
+        self.print_dir = None;
+        let written = tempfile::Builder::new().prefix("gemini-file-viewer-print-").tempdir().map_err(|e| e.to_string()).and_then(|dir| {
+            if let Some(rgba) = image {
+                rgba.save(dir.path().join(PRINT_IMAGE_NAME)).map_err(|e| e.to_string())?;
+            }
+            let page_path = dir.path().join("print.html");
+            fs::write(&page_path, page).map_err(|e| e.to_string())?;
+            opener::open_browser(&page_path).map_err(|e| e.to_string())?;
+            Ok(dir)
+        });
+        match written {
+            Ok(dir) => {
+                self.print_dir = Some((dir, std::time::Instant::now()));
+                self.show_toast("Opened for printing in the browser");
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Couldn't print: {}", e));
+                self.error_path = None;
+            }
+        }
+    }
+
+    fn expire_print_files(&mut self, ctx: &egui::Context) {
+        let Some((_, opened)) = &self.print_dir else { return };
+        if !self.private_mode {
+            return;
+        }
+        match PRIVATE_PRINT_KEEP.checked_sub(opened.elapsed()) {
+            Some(left) => ctx.request_repaint_after(left),
+            None => self.print_dir = None,
+        }
+    }
+
+    /// Save the text as an HTML page in the current theme's colors, highlighted as on screen.
+    pub(crate) fn export_html(&mut self, ctx: &egui::Context) {
+        if self.syntax_job.is_some() {
+            self.show_toast("Still highlighting; try again in a moment");
+            return;
+        }
+        let Some(Content::Text(text)) = &self.content else { return };
+        let ext = self.current_ext();
+        let visuals = ctx.style().visuals.clone();
+        let plain_spans: Vec<crate::syntax::LineSpans>;
+        // The spans the text is shown with: the highlighter's, or the built-in coloring without a language
+        let spans = if text.len() > self.highlight_threshold || self.text_is_big {
+            &[]
+        } else if self.language().is_some() {
+            self.syntax_spans.as_slice()
+        } else {
+            let palette = crate::highlight::Palette::for_theme(self.theme);
+            let (mut depth, mut in_block_comment) = (0, false);
+            plain_spans = text
+                .lines()
+                .map(|line| crate::highlight::line_spans(line, &ext, &palette, visuals.text_color(), &mut depth, &mut in_block_comment))
+                .collect();
+            plain_spans.as_slice()
+        };
+        let title = self.print_title();
+        let lines: Vec<&str> = text.lines().collect();
+        let page = crate::html::export_text(&title, &lines, spans, visuals.panel_fill, visuals.text_color());
+        let Some(dest) = FileDialog::new().set_file_name(format!("{}.html", title)).add_filter("HTML", &["html", "htm"]).save_file() else {
+            return;
+        };
+        match fs::write(&dest, page) {
+            Ok(()) => self.show_toast(format!("Exported to {}", dest.display())),
+            Err(e) => self.error_message = Some(format!("Failed to export: {}", e)),
+        }
+    }
+
+    pub(crate) fn is_clipboard_view(&self) -> bool {
+        self.current_path.as_deref() == Some(Path::new(CLIPBOARD_PATH))
+    }
+
+    /// Preview for a Recent entry's tooltip, made on a worker thread on first hover and again when the
+    /// file changes. None while it's being made.
+    pub(crate) fn recent_preview(&mut self, path: &Path, ctx: &egui::Context) -> Option<&RecentPreview> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if self.recent_previews.get(path).is_some_and(|(stamp, _)| *stamp == modified) {
+            return self.recent_previews.get(path).map(|(_, preview)| preview);
+        }
+        if self.recent_preview_job.as_ref().is_none_or(|(job, _)| job != path) {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let (worker_path, max_bytes, shared) = (path.to_path_buf(), self.max_file_bytes(), self.shared_read);
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                let _ = tx.send(make_recent_preview(&worker_path, max_bytes, shared));
+                ctx.request_repaint();
+            });
+            self.recent_preview_job = Some((path.to_path_buf(), rx));
+        }
+        None
+    }
+
+    fn poll_recent_preview(&mut self, ctx: &egui::Context) {
+        let Some((_, rx)) = &self.recent_preview_job else { return };
+        let made = match rx.try_recv() {
+            Ok(made) => Some(made),
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => None,
+        };
+        let Some((path, _)) = self.recent_preview_job.take() else { return };
+        let Some((modified, made)) = made else { return };
+        let preview = match made {
+            MadePreview::Text(text) => RecentPreview::Text(text),
+            MadePreview::Image(image) => RecentPreview::Image(ctx.load_texture(format!("recent_preview:{}", path.display()), image, self.texture_options())),
+            MadePreview::Unavailable(reason) => RecentPreview::Unavailable(reason),
+        };
+        // Only previews of files still in the Recent list are kept
+        let recent = &self.recent_files;
+        self.recent_previews.retain(|path, _| recent.contains(path));
+        self.recent_previews.insert(path, (modified, preview));
+    }
+
+    /// One zoom step in or out on the current content, using the configured step sizes.
+    pub(crate) fn step_zoom(&mut self, zoom_in: bool) {
+        match &self.content {
+            Some(Content::Text(_) | Content::LargeText(_) | Content::Hex(_)) => {
+                let factor = 1.0 + self.text_zoom_step / 100.0;
+                let factor = if zoom_in { factor } else { 1.0 / factor };
+                self.text_zoom = (self.text_zoom * factor).clamp(0.6, 3.0);
+            }
+            Some(Content::Image(_)) => {
+                self.image_fit = false;
+                let factor = 1.0 + self.image_zoom_step / 100.0;
+                let factor = if zoom_in { factor } else { 1.0 / factor };
+                self.image_zoom = (self.image_zoom * factor).clamp(0.1, 6.0);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn max_file_bytes(&self) -> u64 {
+        self.max_file_mb * 1_000_000
+    }
+
+    pub(crate) fn max_text_bytes(&self) -> u64 {
+        self.max_text_mb * 1_000_000
+    }
+
+    fn add_recent(&mut self, path: PathBuf) {
+        if !self.private_mode {
+            self.recent_files.retain(|p| p != &path);
+            self.recent_files.push(path);
+            self.trim_recent();
+        }
+    }
+
+    pub(crate) fn is_pinned(&self, path: &Path) -> bool {
+        self.pinned_files.iter().any(|p| p == path)
+    }
+
+    /// Pin `path` above the recent files, where their limit never drops it; unpinning makes it the most recent file.
+    pub(crate) fn toggle_pin(&mut self, path: &Path) {
+        if self.is_pinned(path) {
+            self.pinned_files.retain(|p| p != path);
+            self.add_recent(path.to_path_buf());
+        } else {
+            self.recent_files.retain(|p| p != path);
+            self.pinned_files.push(path.to_path_buf());
+        }
+        crate::settings::mark_settings_dirty(self);
+    }
+
+    /// Drop the oldest recent files beyond `max_recent_files`.
+    pub(crate) fn trim_recent(&mut self) {
+        if self.recent_files.len() > self.max_recent_files {
+            let overflow = self.recent_files.len() - self.max_recent_files;
+            self.recent_files.drain(0..overflow);
+        }
+    }
+
+    /// Forget every file-specific trace kept between runs: recents, bookmarks, reopen history, the last session and the last search.
+    pub(crate) fn clear_history(&mut self) {
+        self.recent_files.clear();
+        self.bookmarks.clear();
+        self.recently_closed.clear();
+        self.last_session = None;
+        self.search_query.clear();
+        self.refresh_search();
+        crate::settings::mark_settings_dirty(self);
+    }
+
+    pub(crate) fn is_big_text(&self, chars: usize, lines: usize) -> bool {
+        chars >= self.big_text_threshold || lines >= BIG_TEXT_LINE_THRESHOLD
+    }
+
+    /// The minimap gives each line one row, which only matches the view while lines neither wrap nor fold.
+    pub(crate) fn minimap_fits(&self) -> bool {
+        self.text_is_big || (!self.word_wrap && !self.wrap_long_lines && self.folded.is_empty())
+    }
+
+    /// Recount matches of the current query in the loaded text and restart from the first one.
+    pub(crate) fn refresh_search(&mut self) {
+        self.search_count = 0;
+        self.search_current = 0;
+        (self.search_pattern, self.search_error) = match crate::search::Pattern::new(&self.search_query, self.search_regex, self.search_match_case, self.search_whole_word) {
+            Ok(pattern) => (pattern, None),
+            Err(e) => (None, Some(e)),
+        };
+        if let Some(Content::Text(ref text)) = self.content
+            && !self.search_query.is_empty()
+            && text.len() <= self.highlight_threshold
+        {
+            self.search_count = crate::search::recompute_count(self.search_pattern.as_ref(), text);
+        }
+        self.large_search = None;
+        self.large_matches.clear();
+        self.search_large_from(0);
+    }
+
+    /// Search an indexed large file from line `first` on in the background, replacing matches from
+    /// there. A search still running is restarted from the top, as it can't see appended lines.
+    fn search_large_from(&mut self, first: usize) {
+        let (Some(Content::LargeText(index)), Some(pattern)) = (&self.content, &self.search_pattern) else { return };
+        let first = if self.large_search.is_some() { 0 } else { first };
+        let keep = self.large_matches.partition_point(|&line| line < first);
+        self.large_matches.truncate(keep);
+        self.search_count = self.large_matches.len();
+        self.search_current = self.search_current.min(self.search_count.saturating_sub(1));
+        let (index, pattern) = (index.clone(), pattern.clone());
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || crate::search::search_large_file(&index, &pattern, first, &tx));
+        self.large_search = Some(rx);
+    }
+
+    pub(crate) fn large_search_running(&self) -> bool {
+        self.large_search.is_some()
+    }
+
+    fn poll_large_search(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.large_search else { return };
+        let had_matches = !self.large_matches.is_empty();
+        loop {
+            match rx.try_recv() {
+                Ok(lines) => self.large_matches.extend(lines),
+                Err(std::sync::mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                    break;
+                }
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    self.large_search = None;
+                    break;
+                }
+            }
+        }
+        self.large_matches.truncate(crate::search::MAX_LARGE_MATCHES);
+        self.search_count = self.large_matches.len();
+        // Go to the first match as soon as it is found, as typing a query does for loaded text
+        if !had_matches && self.search_count > 0 {
+            self.jump_to_current_match();
+        }
+    }
+
+    /// Scroll the primary text view to the current match. `refresh_search` leaves the count at
+    /// zero above the highlight threshold, so big loaded files never jump; indexed ones go by the
+    /// matches their search has found so far.
+    pub(crate) fn jump_to_current_match(&mut self) {
+        match &self.content {
+            Some(Content::Text(text)) if self.search_count > 0 => {
+                self.scroll_to_line = crate::search::find_target_line(text, self.search_pattern.as_ref(), self.search_current);
+            }
+            Some(Content::LargeText(_)) => {
+                if let Some(&line) = self.large_matches.get(self.search_current) {
+                    self.scroll_to_line = Some(line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Open (or refresh) the preview of replacing every match with `replace_with`.
+    pub(crate) fn preview_replace(&mut self) {
+        self.replace_preview = match &self.content {
+            Some(Content::Text(text)) => Some(crate::search::replace_all(text, self.search_pattern.as_ref(), &self.replace_with)),
+            _ => None,
+        };
+    }
+
+    /// Keep an open replace preview in step with edits to the query or replacement.
+    pub(crate) fn update_replace_preview(&mut self) {
+        if self.replace_preview.is_some() { self.preview_replace(); }
+    }
+
+    /// Save the replace preview to a new file chosen by the user; the original is never overwritten.
+    pub(crate) fn export_replaced(&mut self) {
+        let Some((text, _)) = &self.replace_preview else { return };
+        let name = self
+            .current_path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|s| format!("replaced-{}", s.to_string_lossy()))
+            .unwrap_or_else(|| "replaced.txt".to_string());
+        let Some(dest) = FileDialog::new().set_file_name(name).save_file() else { return };
+        let same_file = self.current_path.as_ref().is_some_and(|cur| {
+            fs::canonicalize(cur).ok().zip(fs::canonicalize(&dest).ok()).is_some_and(|(a, b)| a == b)
+        });
+        if same_file {
+            self.error_message = Some("Refusing to overwrite the original file; choose a different name".to_string());
+            return;
+        }
+        match fs::write(&dest, text) {
+            Ok(()) => self.show_toast(format!("Saved to {}", dest.display())),
+            Err(e) => self.error_message = Some(format!("Failed to save: {}", e)),
+        }
+    }
+
+    /// Approximate memory held by the loaded content: text bytes or the RGBA texture size.
+    pub(crate) fn approx_content_bytes(&self) -> usize {
+        match &self.content {
+            Some(Content::Text(text)) => text.len(),
+            Some(Content::Image(texture)) => {
+                let size = texture.size();
+                size[0].saturating_mul(size[1]).saturating_mul(4)
+            }
+            Some(Content::Hex(bytes)) => bytes.len(),
+            Some(Content::LargeText(index)) => index.approx_bytes(),
+            Some(Content::Archive(_)) | None => 0,
+        }
+    }
+
+    /// Save the current crop selection to a user-chosen file; the format follows the extension.
+    pub(crate) fn export_crop(&mut self) {
+        let (Some(img), Some(sel)) = (&self.image_pixels, self.crop_rect) else { return };
+        let cropped = crate::imaging::crop(img, sel);
+        let stem = self
+            .current_path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "image".to_string());
+        let Some(dest) = FileDialog::new()
+            .set_file_name(format!("{}-crop.png", stem))
+            .add_filter("PNG", &["png"])
+            .add_filter("JPEG", &["jpg", "jpeg"])
+            .add_filter("BMP", &["bmp"])
+            .save_file()
+        else {
+            return;
+        };
+        match cropped.save(&dest) {
+            Ok(()) => self.show_toast(format!("Saved {}x{} crop to {}", cropped.width(), cropped.height(), dest.display())),
+            Err(e) => self.error_message = Some(format!("Failed to save crop: {}", e)),
+        }
+    }
+
+    /// Pick a second image to compare against the current one.
+    /// It's read and diffed on a worker thread; `poll_compare_image` shows it.
+    pub(crate) fn pick_compare_image(&mut self, ctx: &egui::Context) {
+        let Some(base) = &self.image_pixels else { return };
+        let Some(path) = FileDialog::new()
+            .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
+            .pick_file()
+        else {
+            return;
+        };
+        let (base, shared) = (base.clone(), self.shared_read);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let compared = crate::io::load_image(&path, shared).map(|rgba| {
+                let (diff, diff_count) = crate::imaging::difference(&base, &rgba);
+                (path, rgba, diff, diff_count)
+            });
+            let _ = tx.send(compared);
+            ctx.request_repaint();
+        });
+        self.compare_job = Some(rx);
+    }
+
+    fn poll_compare_image(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.compare_job else { return };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("Loading stopped unexpectedly".to_string()),
+        };
+        self.compare_job = None;
+        match result {
+            Ok((path, rgba, diff, diff_count)) => {
+                let options = self.texture_options();
+                self.compare = Some(CompareImage {
+                    texture: ctx.load_texture("compare", crate::imaging::to_color_image(&rgba), options),
+                    diff: ctx.load_texture("compare_diff", diff, egui::TextureOptions::NEAREST),
+                    diff_count,
+                    path,
+                });
+                self.compare_split = 0.5;
+                self.image_tool = ImageTool::None;
+            }
+            Err(e) => {
+                self.error_message = Some(e);
+                self.error_path = None;
+            }
+        }
+    }
+
+    /// Pick a second text file to diff against the current text. It's read and compared on a worker thread.
+    pub(crate) fn pick_compare_text(&mut self) {
+        let Some(Content::Text(text)) = &self.content else { return };
+        if self.text_is_big {
+            self.show_toast("Files this large can't be compared");
+            return;
+        }
+        let Some(path) = crate::io::open_dialog().pick_file() else { return };
+        let (left, shared, big_chars) = (text.clone(), self.shared_read, self.big_text_threshold);
+        self.start_text_diff(move || {
+            let loaded = crate::io::load_text(&path, shared, None)?;
+            if loaded.text.len() >= big_chars || loaded.lines >= BIG_TEXT_LINE_THRESHOLD {
+                return Err("Files this large can't be compared".to_string());
+            }
+            Ok(crate::diff::TextDiff::new(path, &left, loaded.text))
+        });
+    }
+
+    pub(crate) fn end_compare(&mut self) {
+        self.text_diff = None;
+        self.text_diff_job = None;
+    }
+
+    fn start_text_diff(&mut self, job: impl FnOnce() -> Result<crate::diff::TextDiff, String> + Send + 'static) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(job());
+        });
+        self.text_diff_job = Some(rx);
+    }
+
+    fn poll_text_diff(&mut self, ctx: &egui::Context) {
+        let Some(rx) = &self.text_diff_job else { return };
+        match rx.try_recv() {
+            Ok(Ok(diff)) => self.text_diff = Some(diff),
+            Ok(Err(e)) => self.show_toast(e),
+            Err(std::sync::mpsc::TryRecvError::Empty) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                return;
+            }
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {}
+        }
+        self.text_diff_job = None;
+    }
+
+    /// Pick a text file to show beside the current view, read by the same worker as the main view.
+    pub(crate) fn open_beside(&mut self, ctx: &egui::Context) {
+        let Some(path) = crate::io::open_dialog().pick_file() else { return };
+        let (tx, rx) = std::sync::mpsc::channel();
+        let options = self.read_options(OpenAs::Auto);
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(read_for_view(path, options));
+            ctx.request_repaint();
+        });
+        self.side_pane_load = Some(rx);
+    }
+
+    /// Show the file picked for the side pane once read, and color it for the current theme.
+    fn poll_side_pane(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.side_pane_load {
+            let result = match rx.try_recv() {
+                Ok(result) => Some(result),
+                Err(std::sync::mpsc::TryRecvError::Empty) => None,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => Some(Err("Loading stopped unexpectedly".to_string())),
+            };
+            if let Some(result) = result {
+                self.side_pane_load = None;
+                match result {
+                    Ok(ReadFile { path, data: FileData::Text(loaded), .. }) => {
+                        self.side_pane = Some(crate::pane::SidePane::new(path, loaded, self.text_zoom));
+                    }
+                    Ok(ReadFile { data: FileData::LargeText(_), .. }) => {
+                        self.show_toast(format!("Text over {}MB can't be opened beside", self.max_text_mb));
+                    }
+                    Ok(_) => self.show_toast("Only text files can be opened beside"),
+                    Err(e) => self.show_toast(e),
+                }
+            }
+        }
+        if let Some(pane) = &mut self.side_pane {
+            pane.poll_highlight(self.theme, self.highlight_threshold, ctx);
+        }
+    }
+
+    fn apply_side_pane_actions(&mut self, actions: crate::ui::SidePaneActions) {
+        if actions.close {
+            self.side_pane = None;
+            return;
+        }
+        let Some(pane) = &mut self.side_pane else { return };
+        if let Some(zoom) = actions.zoom { pane.zoom = zoom; }
+        if let Some(y) = actions.main_offset {
+            // Taken as the main view's offset right away, so the pane isn't pulled back for a frame
+            self.text_scroll_offset.y = y;
+            self.pending_scroll_offset = Some(self.text_scroll_offset);
+        }
+        if actions.direction.is_some() || actions.sync_scroll.is_some() {
+            self.split_direction = actions.direction.unwrap_or(self.split_direction);
+            self.sync_scroll = actions.sync_scroll.unwrap_or(self.sync_scroll);
+            crate::settings::mark_settings_dirty(self);
+        }
+    }
+
+    pub(crate) fn copy_image_to_clipboard(&mut self) {
+        let Some(img) = &self.image_pixels else { return };
+        let Some(clipboard) = self.clipboard.as_mut() else { return };
+        let data = arboard::ImageData {
+            width: img.width() as usize,
+            height: img.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(img.as_raw()),
+        };
+        let (w, h) = (img.width(), img.height());
+        match clipboard.set_image(data) {
+            Ok(()) => self.show_toast(format!("Copied {}x{} image", w, h)),
+            Err(e) => self.error_message = Some(format!("Failed to copy image: {}", e)),
+        }
+    }
+
+    pub(crate) fn clear_view(&mut self) {
+        if let Some(state) = self.view_state() {
+            self.remember_closed(state);
+        }
+        self.reset_content();
+    }
+
+    fn remember_closed(&mut self, state: FileViewState) {
+        self.recently_closed.retain(|c| c.path != state.path);
+        self.recently_closed.push(state);
+        if self.recently_closed.len() > MAX_RECENTLY_CLOSED {
+            self.recently_closed.remove(0);
+        }
+    }
+
+    pub(crate) fn view_state(&self) -> Option<FileViewState> {
+        if let Some(loading) = &self.loading {
+            // Not shown yet: keep what it was opened with
+            return Some(loading.restore.clone().unwrap_or_else(|| FileViewState {
+                path: loading.path.clone(),
+                text_zoom: self.text_zoom,
+                image_zoom: self.image_zoom,
+                image_fit: self.image_fit,
+                scroll_offset: [0.0, 0.0],
+                search_query: self.search_query.clone(),
+            }));
+        }
+        if self.is_clipboard_view() || self.is_archive_entry_view() { return None; }
+        Some(FileViewState {
+            path: self.current_path.clone()?,
+            text_zoom: self.text_zoom,
+            image_zoom: self.image_zoom,
+            image_fit: self.image_fit,
+            scroll_offset: [self.text_scroll_offset.x, self.text_scroll_offset.y],
+            search_query: self.search_query.clone(),
+        })
+    }
+
+    /// Load the file in `state` and reapply its zoom, scroll position and search.
+    pub(crate) fn restore_view_state(&mut self, state: FileViewState, ctx: &egui::Context) {
+        self.search_query = state.search_query.clone();
+        self.load_file(state.path.clone(), ctx);
+        if let Some(loading) = &mut self.loading {
+            loading.restore = Some(state);
+        }
+    }
+
+    /// Reopen the most recently closed file in a new tab, with the zoom and scroll it had when closed.
+    pub(crate) fn reopen_closed(&mut self, ctx: &egui::Context) {
+        if let Some(closed) = self.recently_closed.pop() {
+            self.tab_for_new_file();
+            self.restore_view_state(closed, ctx);
+        }
+    }
+
+    /// Tab strip label: the file name, or "New tab" for an empty one.
+    pub(crate) fn tab_title(&self, index: usize) -> String {
+        let path = if index == self.active_tab { self.shown_path() } else { self.tabs.get(index).and_then(Tab::path) };
+        path.and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "New tab".to_string())
+    }
+
+    /// Add an empty tab after the current one and switch to it.
+    pub(crate) fn new_tab(&mut self) {
+        self.tabs[self.active_tab] = self.stash_tab();
+        self.active_tab += 1;
+        self.tabs.insert(self.active_tab, Tab::Empty);
+    }
+    /// Move the shown file and its view out of the window into a tab entry, leaving the window empty.
+    fn stash_tab(&mut self) -> Tab {
+        if self.current_path.is_none() && self.loading.is_none() {
+            self.reset_content();
+            self.archive_path = None;
+            return Tab::Empty;
+        }
+        let tab = TabState {
+            view: self.view_state(),
+            content: std::mem::take(&mut self.content),
+            current_path: std::mem::take(&mut self.current_path),
+            error_message: std::mem::take(&mut self.error_message),
+            error_path: std::mem::take(&mut self.error_path),
+            link_target: std::mem::take(&mut self.link_target),
+            archive_path: std::mem::take(&mut self.archive_path),
+            format_alt: std::mem::take(&mut self.format_alt),
+            format_shown: std::mem::take(&mut self.format_shown),
+            format_warning: std::mem::take(&mut self.format_warning),
+            text_is_big: std::mem::take(&mut self.text_is_big),
+            text_line_count: std::mem::take(&mut self.text_line_count),
+            text_is_lossy: std::mem::take(&mut self.text_is_lossy),
+            text_invalid_offsets: std::mem::take(&mut self.text_invalid_offsets),
+            text_line_offsets: std::mem::take(&mut self.text_line_offsets),
+            text_line_starts: std::mem::take(&mut self.text_line_starts),
+            text_encoding: std::mem::replace(&mut self.text_encoding, "UTF-8"),
+            encoding_override: std::mem::take(&mut self.encoding_override),
+            hex_goto_input: std::mem::take(&mut self.hex_goto_input),
+            hex_marked: std::mem::take(&mut self.hex_marked),
+            hex_scroll_to: std::mem::take(&mut self.hex_scroll_to),
+            hex_selection: std::mem::take(&mut self.hex_selection),
+            outline: std::mem::take(&mut self.outline),
+            fold_ranges: std::mem::take(&mut self.fold_ranges),
+            folded: std::mem::take(&mut self.folded),
+            image_pixels: std::mem::take(&mut self.image_pixels),
+            image_histogram: std::mem::take(&mut self.image_histogram),
+            measure_points: std::mem::take(&mut self.measure_points),
+            crop_rect: std::mem::take(&mut self.crop_rect),
+            crop_anchor: std::mem::take(&mut self.crop_anchor),
+            compare: std::mem::take(&mut self.compare),
+            compare_job: std::mem::take(&mut self.compare_job),
+            compare_split: std::mem::replace(&mut self.compare_split, 0.5),
+            compare_diff: std::mem::take(&mut self.compare_diff),
+            pdf: std::mem::take(&mut self.pdf),
+            animation: std::mem::take(&mut self.animation),
+            archive_entry_job: std::mem::take(&mut self.archive_entry_job),
+            table: std::mem::take(&mut self.table),
+            table_error: std::mem::take(&mut self.table_error),
+            json_tree: std::mem::take(&mut self.json_tree),
+            text_diff: std::mem::take(&mut self.text_diff),
+            text_diff_job: std::mem::take(&mut self.text_diff_job),
+            replace_preview: std::mem::take(&mut self.replace_preview),
+            search_query: self.search_query.clone(),
+            search_pattern: self.search_pattern.clone(),
+            search_error: self.search_error.clone(),
+            search_count: std::mem::take(&mut self.search_count),
+            search_current: std::mem::take(&mut self.search_current),
+            large_matches: std::mem::take(&mut self.large_matches),
+            large_search: std::mem::take(&mut self.large_search),
+            bookmark_cursor: std::mem::take(&mut self.bookmark_cursor),
+            cursor_line: std::mem::take(&mut self.cursor_line),
+            first_visible_line: std::mem::take(&mut self.first_visible_line),
+            text_scroll_offset: std::mem::take(&mut self.text_scroll_offset),
+            text_zoom: self.text_zoom,
+            image_zoom: self.image_zoom,
+            image_fit: self.image_fit,
+            tail_mode: std::mem::take(&mut self.tail_mode),
+            file_watch: std::mem::take(&mut self.file_watch),
+            disk_stamp: std::mem::take(&mut self.disk_stamp),
+            line_flash: std::mem::take(&mut self.line_flash),
+            siblings: std::mem::take(&mut self.siblings),
+            sibling_scan: std::mem::take(&mut self.sibling_scan),
+            loading: std::mem::take(&mut self.loading),
+            text_reload: std::mem::take(&mut self.text_reload),
+            syntax_spans: std::mem::take(&mut self.syntax_spans),
+            syntax_key: std::mem::take(&mut self.syntax_key),
+            syntax_job: std::mem::take(&mut self.syntax_job),
+            language_override: std::mem::take(&mut self.language_override),
+        };
+        self.reset_content();
+        Tab::Open(Box::new(tab))
+    }
+
+    /// Put a tab's entry back into the window: a kept tab as it was, a pending one by loading it.
+    fn show_tab(&mut self, tab: Tab, ctx: &egui::Context) {
+        let tab = match tab {
+            Tab::Empty => return,
+            Tab::Pending(state) => return self.restore_view_state(state, ctx),
+            Tab::Open(tab) => *tab,
+        };
+        let TabState {
+            view: _,
+            content,
+            current_path,
+            error_message,
+            error_path,
+            link_target,
+            archive_path,
+            format_alt,
+            format_shown,
+            format_warning,
+            text_is_big,
+            text_line_count,
+            text_is_lossy,
+            text_invalid_offsets,
+            text_line_offsets,
+            text_line_starts,
+            text_encoding,
+            encoding_override,
+            hex_goto_input,
+            hex_marked,
+            hex_scroll_to,
+            hex_selection,
+            outline,
+            fold_ranges,
+            folded,
+            image_pixels,
+            image_histogram,
+            measure_points,
+            crop_rect,
+            crop_anchor,
+            compare,
+            compare_job,
+            compare_split,
+            compare_diff,
+            pdf,
+            animation,
+            archive_entry_job,
+            table,
+            table_error,
+            json_tree,
+            text_diff,
+            text_diff_job,
+            replace_preview,
+            search_query,
+            search_pattern,
+            search_error,
+            search_count,
+            search_current,
+            large_matches,
+            large_search,
+            bookmark_cursor,
+            cursor_line,
+            first_visible_line,
+            text_scroll_offset,
+            text_zoom,
+            image_zoom,
+            image_fit,
+            tail_mode,
+            file_watch,
+            disk_stamp,
+            line_flash,
+            siblings,
+            sibling_scan,
+            loading,
+            text_reload,
+            syntax_spans,
+            syntax_key,
+            syntax_job,
+            language_override,
+        } = tab;
+        self.content = content;
+        self.current_path = current_path;
+        self.error_message = error_message;
+        self.error_path = error_path;
+        self.link_target = link_target;
+        self.archive_path = archive_path;
+        self.format_alt = format_alt;
+        self.format_shown = format_shown;
+        self.format_warning = format_warning;
+        self.text_is_big = text_is_big;
+        self.text_line_count = text_line_count;
+        self.text_is_lossy = text_is_lossy;
+        self.text_invalid_offsets = text_invalid_offsets;
+        self.text_line_offsets = text_line_offsets;
+        self.text_line_starts = text_line_starts;
+        self.text_encoding = text_encoding;
+        self.encoding_override = encoding_override;
+        self.hex_goto_input = hex_goto_input;
+        self.hex_marked = hex_marked;
+        self.hex_scroll_to = hex_scroll_to;
+        self.hex_selection = hex_selection;
+        self.outline = outline;
+        self.fold_ranges = fold_ranges;
+        self.folded = folded;
+        self.image_pixels = image_pixels;
+        self.image_histogram = image_histogram;
+        self.measure_points = measure_points;
+        self.crop_rect = crop_rect;
+        self.crop_anchor = crop_anchor;
+        self.compare = compare;
+        self.compare_job = compare_job;
+        self.compare_split = compare_split;
+        self.compare_diff = compare_diff;
+        self.pdf = pdf;
+        self.animation = animation;
+        self.archive_entry_job = archive_entry_job;
+        self.table = table;
+        self.table_error = table_error;
+        self.json_tree = json_tree;
+        self.text_diff = text_diff;
+        self.text_diff_job = text_diff_job;
+        self.replace_preview = replace_preview;
+        self.search_query = search_query;
+        self.search_pattern = search_pattern;
+        self.search_error = search_error;
+        self.search_count = search_count;
+        self.search_current = search_current;
+        self.large_matches = large_matches;
+        self.large_search = large_search;
+        self.bookmark_cursor = bookmark_cursor;
+        self.cursor_line = cursor_line;
+        self.first_visible_line = first_visible_line;
+        self.text_scroll_offset = text_scroll_offset;
+        self.text_zoom = text_zoom;
+        self.image_zoom = image_zoom;
+        self.image_fit = image_fit;
+        self.tail_mode = tail_mode;
+        self.file_watch = file_watch;
+        self.disk_stamp = disk_stamp;
+        self.line_flash = line_flash;
+        self.siblings = siblings;
+        self.sibling_scan = sibling_scan;
+        self.loading = loading;
+        self.text_reload = text_reload;
+        self.syntax_spans = syntax_spans;
+        self.syntax_key = syntax_key;
+        self.syntax_job = syntax_job;
+        self.language_override = language_override;
+        // The scroll area is shared by all tabs and still holds the last one's position
+        self.pending_scroll_offset = Some(self.text_scroll_offset);
+    }
+
+
+    /// Files opened from the Open dialog or by dropping get their own tab unless the current one is empty.
+    pub(crate) fn tab_for_new_file(&mut self) {
+        if self.current_path.is_some() {
+            self.new_tab();
+        }
+    }
+
+    /// Queue `paths` as background tabs after the current one; each loads when first shown.
+    fn add_background_tabs(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        for (n, path) in paths.into_iter().enumerate() {
+            let state = FileViewState {
+                path,
+                text_zoom: self.text_zoom,
+                image_zoom: self.image_zoom,
+                image_fit: self.image_fit,
+                scroll_offset: [0.0, 0.0],
+                search_query: String::new(),
+            };
+            self.tabs.insert(self.active_tab + 1 + n, Tab::Pending(state));
+        }
+    }
+
+    pub(crate) fn switch_tab(&mut self, index: usize, ctx: &egui::Context) {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return;
+        }
+        self.tabs[self.active_tab] = self.stash_tab();
+        self.active_tab = index;
+        let tab = std::mem::replace(&mut self.tabs[index], Tab::Empty);
+        self.show_tab(tab, ctx);
+    }
+
+    /// Next or previous tab, wrapping around.
+    pub(crate) fn cycle_tab(&mut self, forward: bool, ctx: &egui::Context) {
+        let len = self.tabs.len();
+        let next = if forward { (self.active_tab + 1) % len } else { (self.active_tab + len - 1) % len };
+        self.switch_tab(next, ctx);
+    }
+
+    /// Close a tab; closing the last one leaves a single empty tab.
+    pub(crate) fn close_tab(&mut self, index: usize, ctx: &egui::Context) {
+        if index >= self.tabs.len() {
+            return;
+        }
+        if index != self.active_tab {
+            if let Some(state) = self.tabs.remove(index).view_state() {
+                self.remember_closed(state);
+            }
+            if index < self.active_tab {
+                self.active_tab -= 1;
+            }
+            return;
+        }
+        self.clear_view();
+        self.reset_content();
+        self.archive_path = None;
+        self.tabs.remove(index);
+        if self.tabs.is_empty() {
+            self.tabs.push(Tab::Empty);
+        }
+        self.active_tab = index.min(self.tabs.len() - 1);
+        let tab = std::mem::replace(&mut self.tabs[self.active_tab], Tab::Empty);
+        self.show_tab(tab, ctx);
+    }
+
+    /// The open tabs and which one is active.
+    fn current_session(&self) -> crate::settings::Session {
+        let mut session = crate::settings::Session::default();
+        for (i, tab) in self.tabs.iter().enumerate() {
+            let state = if i == self.active_tab { self.view_state() } else { tab.view_state() };
+            if let Some(state) = state {
+                if i == self.active_tab { session.active = session.files.len(); }
+                session.files.push(state);
+            }
+        }
+        session
+    }
+
+    pub(crate) fn save_session(&mut self, name: &str) {
+        let session = self.current_session();
+        match crate::settings::save_session(name, &session) {
+            Ok(()) => self.show_toast(format!("Saved session \"{}\"", name)),
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+
+    /// Replace the open tabs with the session's files and show the one that was active.
+    pub(crate) fn load_session(&mut self, name: &str, ctx: &egui::Context) {
+        match crate::settings::load_session(name) {
+            Ok(session) => self.open_session(session, ctx),
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+
+    fn open_session(&mut self, session: crate::settings::Session, ctx: &egui::Context) {
+        if session.files.is_empty() {
+            return;
+        }
+        let active = session.active.min(session.files.len() - 1);
+        self.reset_content();
+        self.archive_path = None;
+        self.tabs = session.files.into_iter().map(Tab::Pending).collect();
+        self.active_tab = active;
+        let tab = std::mem::replace(&mut self.tabs[active], Tab::Empty);
+        self.show_tab(tab, ctx);
+    }
+
+    /// Reopen the tabs that were open at the last exit.
+    pub(crate) fn resume_last_session(&mut self, ctx: &egui::Context) {
+        if let Some(session) = self.last_session.clone() {
+            self.open_session(session, ctx);
+        }
+    }
+
+    /// Record the open tabs for resuming next time. With nothing open the previous session is
+    /// kept, so a run that never opened a file doesn't lose it.
+    fn remember_session(&mut self) {
+        if self.secondary {
+            return;
+        }
+        if self.private_mode {
+            self.last_session = None;
+            return;
+        }
+        let session = self.current_session();
+        if !session.files.is_empty() {
+            self.last_session = Some(session);
+        }
+    }
+
+    /// Scroll the text to the 1-based line typed in the go-to-line popup and flash it.
+    pub(crate) fn goto_line(&mut self) {
+        let input = self.goto_line_input.trim();
+        match input.parse::<usize>() {
+            Ok(line) if line >= 1 => {
+                self.jump_to_line(line - 1);
+                self.goto_line_open = false;
+            }
+            _ => self.show_toast(format!("Not a valid line number: {}", input)),
+        }
+    }
+
+    /// Scroll the text to 0-based `line`, clamped to the last one, and flash it.
+    pub(crate) fn jump_to_line(&mut self, line: usize) {
+        let line = line.min(self.text_line_count.saturating_sub(1));
+        self.scroll_to_line = Some(line);
+        self.line_flash = Some((line..line + 1, std::time::Instant::now()));
+    }
+
+    /// Search the chosen folder for the folder search query on a worker thread, replacing earlier results.
+    pub(crate) fn start_folder_search(&mut self) {
+        self.folder_search = None;
+        self.folder_search_error = None;
+        let Some(dir) = self.folder_search_dir.clone() else {
+            self.folder_search_error = Some("Choose a folder to search".to_string());
+            return;
+        };
+        let pattern = match crate::search::Pattern::new(&self.folder_search_query, self.search_regex, self.search_match_case, self.search_whole_word) {
+            Ok(Some(pattern)) => pattern,
+            Ok(None) => return,
+            Err(e) => {
+                self.folder_search_error = Some(e);
+                return;
+            }
+        };
+        let (tx, rx) = std::sync::mpsc::channel();
+        let include_hidden = self.show_hidden_files;
+        std::thread::spawn(move || crate::search::search_folder(&dir, &pattern, include_hidden, &tx));
+        self.folder_search = Some(FolderSearch { hits: Vec::new(), files: 0, done: false, rx: Some(rx) });
+    }
+
+    /// Stop a running folder search, keeping what it found so far.
+    pub(crate) fn stop_folder_search(&mut self) {
+        if let Some(search) = &mut self.folder_search {
+            // The worker notices on its next send
+            search.rx = None;
+            search.done = true;
+        }
+    }
+
+    fn poll_folder_search(&mut self, ctx: &egui::Context) {
+        let Some(search) = &mut self.folder_search else { return };
+        let Some(rx) = &search.rx else { return };
+        loop {
+            match rx.try_recv() {
+                Ok(crate::search::FolderEvent::Hits(hits)) => search.hits.extend(hits),
+                Ok(crate::search::FolderEvent::Progress(files)) => search.files = files,
+                Ok(crate::search::FolderEvent::Done(files)) => {
+                    search.files = files;
+                    break;
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                    return;
+                }
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+        search.rx = None;
+        search.done = true;
+    }
+
+    /// Show `line` of `path` as text, loading the file unless it is the one already shown.
+    pub(crate) fn open_at_line(&mut self, path: PathBuf, line: usize, ctx: &egui::Context) {
+        if self.current_path.as_ref() == Some(&path) && matches!(self.content, Some(Content::Text(_) | Content::LargeText(_))) {
+            self.jump_to_line(line);
+            return;
+        }
+        self.load_file_as(path, OpenAs::Text, ctx);
+        if let Some(loading) = &mut self.loading {
+            loading.line = Some(line);
+        }
+    }
+
+    /// Parse the go-to-offset input (`0x` prefix for hex, otherwise the offset column's base) and scroll there.
+    pub(crate) fn goto_hex_offset(&mut self) {
+        let Some(Content::Hex(bytes)) = &self.content else { return };
+        let len = bytes.len();
+        let input = self.hex_goto_input.trim().replace('_', "");
+        let parsed = match input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+            Some(digits) => usize::from_str_radix(digits, 16),
+            None if self.hex_decimal_offsets => input.parse(),
+            None => usize::from_str_radix(&input, 16),
+        };
+        match parsed {
+            Ok(offset) if offset < len => {
+                self.hex_marked = Some(offset);
+                self.hex_scroll_to = Some(offset);
+            }
+            Ok(_) => self.show_toast(format!("Offset is past the end of the file ({} bytes)", len)),
+            Err(_) => self.show_toast(format!("Not a valid offset: {}", self.hex_goto_input.trim())),
+        }
+    }
+
+    pub(crate) fn show_toast(&mut self, msg: impl Into<String>) {
+        self.toast = Some((msg.into(), std::time::Instant::now()));
+    }
+
+    /// `path` for display: relative to the chosen base when that option is on and the path lies inside it.
+    pub(crate) fn display_path(&self, path: &Path) -> String {
+        if self.relative_paths
+            && let Some(base) = self.path_base.clone().or_else(|| std::env::current_dir().ok())
+            && let Ok(rel) = path.strip_prefix(&base)
+        {
+            return if rel.as_os_str().is_empty() { ".".to_string() } else { rel.to_string_lossy().into_owned() };
+        }
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Lowercase extension of the open file, or "" when it has none.
+    pub(crate) fn current_ext(&self) -> String {
+        self.current_path
+            .as_ref()
+            .and_then(|p| p.extension().and_then(|s| s.to_str()))
+            .unwrap_or("")
+            .to_lowercase()
+    }
+
+    /// Bookmarks of the shown file. They belong to its lines as saved, so a reformatted view has none.
+    pub(crate) fn current_bookmarks(&self) -> &[usize] {
+        if self.format_shown {
+            return &[];
+        }
+        self.current_path
+            .as_ref()
+            .and_then(|p| self.bookmarks.get(p))
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns whether the bookmark could be toggled.
+    pub(crate) fn toggle_bookmark(&mut self, line: usize) -> bool {
+        if self.format_shown {
+            self.show_toast("Bookmarks are set on the original text; switch back to it first");
+            return false;
+        }
+        let Some(path) = self.current_path.clone() else { return false };
+        let marks = self.bookmarks.entry(path.clone()).or_default();
+        match marks.binary_search(&line) {
+            Ok(idx) => { marks.remove(idx); }
+            Err(idx) => marks.insert(idx, line),
+        }
+        if marks.is_empty() { self.bookmarks.remove(&path); }
+        crate::settings::mark_settings_dirty(self);
+        true
+    }
+
+    /// Jump to the next (or previous) bookmark relative to the last one visited, wrapping around.
+    pub(crate) fn cycle_bookmark(&mut self, forward: bool) {
+        let marks = self.current_bookmarks();
+        let target = if forward {
+            marks.iter().copied().find(|&l| self.bookmark_cursor.is_none_or(|c| l > c)).or(marks.first().copied())
+        } else {
+            marks.iter().rev().copied().find(|&l| self.bookmark_cursor.is_none_or(|c| l < c)).or(marks.last().copied())
+        };
+        if let Some(line) = target {
+            self.bookmark_cursor = Some(line);
+            self.scroll_to_line = Some(line);
+        }
+    }
+}
+
+impl Default for FileViewerApp {
+    fn default() -> Self {
+        Self {
+            content: None,
+            current_path: None,
+            error_message: None,
+            error_path: None,
+            link_target: None,
+            archive_path: None,
+            follow_symlinks: false,
+            shared_read: default_shared_read(),
+            show_hidden_files: false,
+            unknown_files: UnknownFiles::Text,
+            resume_session: ResumeSession::Ask,
+            last_session: None,
+            show_file_times: true,
+            pending_unknown: None,
+            relative_paths: false,
+            path_base: None,
+            dark_mode: true,
+            theme: Theme::Dark,
+            last_dark_theme: Theme::Dark,
+            last_light_theme: Theme::Light,
+            follow_system_theme: true,
+            system_dark: None,
+            theme_checked: None,
+            window_focused: true,
+            low_power: false,
+            slideshow_secs: 4.0,
+            slideshow_fullscreen: false,
+            recent_files: Vec::new(),
+            pinned_files: Vec::new(),
+            max_recent_files: default_max_recent_files(),
+            max_file_mb: DEFAULT_MAX_FILE_MB,
+            max_text_mb: DEFAULT_MAX_TEXT_MB,
+            private_mode: false,
+            bookmarks: BTreeMap::new(),
+            show_line_numbers: true,
+            show_minimap: false,
+            show_outline: false,
+            proportional_exts: BTreeSet::new(),
+            word_wrap: true,
+            wrap_column: None,
+            wrap_long_lines: false,
+            wrap_line_threshold: 500,
+            auto_format: false,
+            format_alt: None,
+            format_shown: false,
+            format_warning: None,
+            expand_tabs: true,
+            tab_width: 4,
+            text_zoom: 1.0,
+            preserve_zoom: true,
+            default_text_zoom: 1.0,
+            text_zoom_step: default_text_zoom_step(),
+            image_zoom_step: default_image_zoom_step(),
+            scroll_speed: default_scroll_speed(),
+            invert_scroll: false,
+            default_image_zoom: 1.0,
+            default_image_fit: false,
+            line_height_scale: 1.0,
+            image_zoom: 1.0,
+            show_about: false,
+            image_fit: false,
+            image_nearest: false,
+            accent_rgb: [93, 156, 255],
+            spacing_scale: 1.0,
+            theme_rounding: 6,
+            show_theme_editor: false,
+            show_settings: false,
+            settings_page: SettingsPage::Appearance,
+            key_bindings: crate::keys::KeyBindings::default(),
+            key_capture: None,
+            remember_window_positions: true,
+            window_positions: std::collections::HashMap::new(),
+            show_perf_overlay: false,
+            perf_frame_dt: 0.0,
+            split_view: false,
+            side_pane: None,
+            side_pane_load: None,
+            split_direction: crate::pane::SplitDirection::Vertical,
+            sync_scroll: false,
+            text_is_big: false,
+            big_text_threshold: BIG_TEXT_CHAR_THRESHOLD,
+            highlight_threshold: HIGHLIGHT_CHAR_THRESHOLD,
+            text_line_count: 0,
+            text_is_lossy: false,
+            text_invalid_offsets: Vec::new(),
+            text_line_offsets: Vec::new(),
+            show_byte_offsets: false,
+            text_encoding: "UTF-8",
+            encoding_override: None,
+            show_control_chars: false,
+            hex_row_bytes: 16,
+            hex_decimal_offsets: false,
+            hex_show_ascii: true,
+            hex_goto_input: String::new(),
+            hex_marked: None,
+            hex_scroll_to: None,
+            hex_selection: None,
+            outline: Vec::new(),
+            fold_ranges: BTreeMap::new(),
+            folded: BTreeSet::new(),
+            image_pixels: None,
+            image_histogram: None,
+            show_histogram: false,
+            show_thumbnails: false,
+            thumbnails: Default::default(),
+            image_tool: ImageTool::None,
+            measure_points: Vec::new(),
+            crop_rect: None,
+            crop_anchor: None,
+            image_adjust: Default::default(),
+            image_orientations: std::collections::HashMap::new(),
+            image_adjust_changed: None,
+            show_adjust: false,
+            compare: None,
+            compare_job: None,
+            pdf: None,
+            animation: None,
+            archive_entry_job: None,
+            slideshow: None,
+            table: None,
+            table_error: None,
+            table_view: true,
+            json_tree: None,
+            json_view: true,
+            compare_split: 0.5,
+            compare_diff: false,
+            text_diff: None,
+            text_diff_job: None,
+            diff_unified: false,
+            search_query: String::new(),
+            remember_search: false,
+            persistent_highlights: true,
+            search_regex: false,
+            search_match_case: false,
+            search_whole_word: false,
+            search_pattern: None,
+            search_error: None,
+            search_focused: false,
+            show_replace: false,
+            replace_with: String::new(),
+            replace_preview: None,
+            siblings: None,
+            text_line_starts: Vec::new(),
+            syntax_spans: Vec::new(),
+            syntax_key: None,
+            syntax_job: None,
+            language_override: None,
+            loading: None,
+            text_reload: None,
+            sibling_scan: None,
+            pending_neighbor: None,
+            tail_mode: false,
+            auto_reload: false,
+            file_watch: None,
+            disk_stamp: None,
+            line_flash: None,
+            search_active: false,
+            search_count: 0,
+            search_current: 0,
+            large_matches: Vec::new(),
+            large_search: None,
+            scroll_to_line: None,
+            show_folder_search: false,
+            folder_search_dir: None,
+            folder_search_query: String::new(),
+            folder_search: None,
+            folder_search_error: None,
+            print_open: false,
+            print_range: (1, 1),
+            print_dir: None,
+            print_line_numbers: true,
+            print_colors: true,
+            goto_line_open: false,
+            goto_line_input: String::new(),
+            bookmark_cursor: None,
+            cursor_line: None,
+            first_visible_line: None,
+            text_scroll_offset: egui::Vec2::ZERO,
+            pending_scroll_offset: None,
+            recently_closed: Vec::new(),
+            tabs: vec![Tab::Empty],
+            active_tab: 0,
+            session_name_input: String::new(),
+            recent_filter: String::new(),
+            recent_previews: Default::default(),
+            recent_preview_job: None,
+            toast: None,
+            clipboard: None,
+            extra_windows: Vec::new(),
+            windows_opened: 0,
+            secondary: false,
+            new_window_requested: false,
+            settings_dirty: std::cell::Cell::new(false),
+            settings_changed: std::cell::Cell::new(false),
+            settings_written: None,
+        }
+    }
+}
+
+impl eframe::App for FileViewerApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.remember_session();
+        if let Ok(s) = serde_json::to_string(self) {
+            storage.set_string(eframe::APP_KEY, s);
+        }
+        crate::settings::mark_settings_dirty(self);
+        crate::settings::flush_settings(self, true);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.hand_over_main_window(ctx);
+        self.show_window(ctx, frame.info().cpu_usage);
+        self.show_extra_windows(ctx);
+        self.share_settings();
+        if let Some(wait) = crate::settings::flush_settings(self, false) {
+            ctx.request_repaint_after(wait);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.remember_session();
+        crate::settings::mark_settings_dirty(self);
+        crate::settings::flush_settings(self, true);
+    }
+}
+
+impl FileViewerApp {
+    /// One viewer window's frame; the main window and every extra window run this on their own state.
+    fn show_window(&mut self, ctx: &egui::Context, cpu_usage: Option<f32>) {
+        // Follow system theme if enabled; asking the OS is a system call, so only every few seconds
+        // and when the window comes back into focus, as that's when the setting was likely changed
+        let focused = ctx.input(|i| i.viewport().focused.unwrap_or(true));
+        let refocused = focused && !self.window_focused;
+        self.window_focused = focused;
+        if !self.follow_system_theme {
+            self.system_dark = None;
+        } else {
+            let interval = self.poll_interval(THEME_POLL_INTERVAL);
+            if refocused || self.theme_checked.is_none_or(|at| at.elapsed() >= interval) {
+                self.theme_checked = Some(std::time::Instant::now());
+                let sys_dark = matches!(dark_light::detect(), Ok(dark_light::Mode::Dark));
+                // Only act when the system setting flips, not on every repeat of the same answer
+                if self.system_dark != Some(sys_dark) {
+                    self.system_dark = Some(sys_dark);
+                    if sys_dark != self.dark_mode {
+                        self.set_dark_mode(sys_dark);
+                    }
+                }
+            }
+            ctx.request_repaint_after(interval);
+        }
+        // Apply visuals each frame
+        self.remember_theme();
+        self.apply_theme(ctx);
+        self.poll_loading(ctx);
+        self.poll_text_reload();
+        self.poll_pdf(ctx);
+        self.poll_animation(ctx);
+        self.poll_folder_search(ctx);
+        self.poll_large_search(ctx);
+        self.poll_text_diff(ctx);
+        self.poll_archive_entry(ctx);
+        self.poll_compare_image(ctx);
+        self.poll_recent_preview(ctx);
+        self.thumbnails.poll(ctx);
+        self.poll_syntax(ctx);
+        self.poll_side_pane(ctx);
+
+        let mut file_to_load: Option<PathBuf> = None;
+
+        // Drag & Drop: preview and open files
         let hovered = ctx.input(|i| i.raw.hovered_files.clone());
         if !hovered.is_empty() {
             egui::Area::new("drop_hint".into())
@@ -290,131 +3241,212 @@ impl eframe::App for FileViewerApp {
                         });
                 });
         }
-        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
-        if let Some(df) = dropped.first() {
-            if let Some(path) = df.path.clone() {
-                file_to_load = Some(path);
-            }
+        // Several dropped files open one tab each
+        let mut dropped = ctx.input(|i| i.raw.dropped_files.clone()).into_iter().filter_map(|f| f.path);
+        if let Some(path) = dropped.next() {
+            self.tab_for_new_file();
+            self.add_background_tabs(dropped);
+            file_to_load = Some(path);
         }
 
         // Keyboard shortcuts
         let mut toggle_dark = false;
+        let mut reopen_closed = false;
+        let mut tab_action: Option<crate::ui::TabAction> = None;
+        // Ctrl+V reaches us as a paste event carrying the clipboard text (none for image-only clipboards)
+        let mut paste: Option<String> = None;
+        // Zoom in (true) / out (false) requests from the wheel and keyboard
+        let mut zoom_steps: Vec<bool> = Vec::new();
+        // Scroll preferences apply to every scroll area, so adjust the wheel input before anything reads it
+        if self.invert_scroll || self.scroll_speed != 1.0 {
+            let sign = if self.invert_scroll { -1.0 } else { 1.0 };
+            ctx.input_mut(|i| {
+                i.smooth_scroll_delta *= sign * self.scroll_speed;
+                i.raw_scroll_delta *= sign;
+            });
+        }
+        // Prev/Next request from the keyboard (true = next)
+        let mut navigate: Option<bool> = None;
+        let mut pdf_step: Option<bool> = None;
+        let mut toggle_mark = false;
+        let mut image_transform = None;
+        let mut print = false;
+        let (mut slideshow_pause, mut slideshow_stop) = (false, false);
+        let typing = ctx.wants_keyboard_input();
+        // Arrow keys move keyboard focus between toolbar controls while one is focused
+        let widget_focused = ctx.memory(|m| m.focused().is_some());
+        // A shortcut being picked is given up once its page is no longer shown
+        if !self.show_settings || self.settings_page != SettingsPage::Keyboard {
+            self.key_capture = None;
+        }
+        // While Settings waits for a new shortcut, keys go to it instead
+        let capturing = self.key_capture.is_some();
+        let keys = self.key_bindings.clone();
         ctx.input(|i| {
-            if i.modifiers.command && i.key_pressed(egui::Key::O) {
-                if let Some(path) = FileDialog::new()
-                    .add_filter("All Supported", &["txt","rs","py","toml","md","json","js","html","css","png","jpg","jpeg","gif","bmp","webp"])
-                    .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
-                    .add_filter("Text/Source", &["txt","rs","py","toml","md","json","js","html","css"])
-                    .pick_file()
-                {
-                    file_to_load = Some(path);
+            let pressed = |action| !capturing && keys.pressed(i, action, typing);
+            let is_text = matches!(self.content, Some(Content::Text(_) | Content::LargeText(_)));
+            let is_image = matches!(self.content, Some(Content::Image(_)));
+            if pressed(Action::Open)
+                && let Some(path) = crate::io::open_dialog().pick_file()
+            {
+                self.tab_for_new_file();
+                file_to_load = Some(path);
+            }
+            // Tab shortcuts would otherwise fire while typing in the find box
+            if !typing {
+                if pressed(Action::ReopenClosedTab) {
+                    reopen_closed = true;
+                }
+                if pressed(Action::NewTab) {
+                    tab_action = Some(crate::ui::TabAction::New);
+                }
+                if pressed(Action::CloseTab) {
+                    tab_action = Some(crate::ui::TabAction::Close(self.active_tab));
+                }
+                if pressed(Action::NextTab) {
+                    tab_action = Some(crate::ui::TabAction::Cycle(true));
+                }
+                if pressed(Action::PreviousTab) {
+                    tab_action = Some(crate::ui::TabAction::Cycle(false));
                 }
             }
-            if i.modifiers.command && i.key_pressed(egui::Key::D) {
+            if pressed(Action::NewWindow) {
+                self.new_window_requested = true;
+            }
+            if pressed(Action::ToggleTheme) {
                 toggle_dark = true;
             }
-            if i.modifiers.command && i.key_pressed(egui::Key::F) {
+            if pressed(Action::FolderSearch) {
+                self.show_folder_search = true;
+            }
+            if pressed(Action::Find) {
                 self.search_active = true;
             }
-            if i.modifiers.command && i.key_pressed(egui::Key::L) {
+            if pressed(Action::GoToLine) && is_text {
+                self.goto_line_open = true;
+                self.goto_line_input.clear();
+            }
+            if !typing && pressed(Action::Print) {
+                print = true;
+            }
+            if pressed(Action::ToggleLineNumbers) {
                 self.show_line_numbers = !self.show_line_numbers;
-                crate::settings::save_settings_to_disk(self);
+                crate::settings::mark_settings_dirty(self);
             }
-            if i.modifiers.command && i.key_pressed(egui::Key::W) {
+            if pressed(Action::ToggleWordWrap) {
                 self.word_wrap = !self.word_wrap;
-                crate::settings::save_settings_to_disk(self);
+                crate::settings::mark_settings_dirty(self);
             }
-
-            // Ctrl + Mouse wheel zoom for content
-            if i.modifiers.command && i.raw_scroll_delta.y != 0.0 {
-                let dir = i.raw_scroll_delta.y.signum();
-                match &self.content {
-                    Some(Content::Text(_)) => {
-                        let factor = if dir > 0.0 { 1.05 } else { 1.0 / 1.05 };
-                        self.text_zoom = (self.text_zoom * factor).clamp(0.6, 3.0);
-                    }
-                    Some(Content::Image(_)) => {
+            // Number keys jump images to fixed scales
+            if !typing && i.modifiers.is_none() && is_image {
+                let keys = [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4, egui::Key::Num5];
+                for (key, zoom) in keys.into_iter().zip(IMAGE_ZOOM_PRESETS) {
+                    if i.key_pressed(key) {
                         self.image_fit = false;
-                        let factor = if dir > 0.0 { 1.10 } else { 1.0 / 1.10 };
-                        self.image_zoom = (self.image_zoom * factor).clamp(0.1, 6.0);
+                        self.image_zoom = zoom;
                     }
-                    _ => {}
                 }
             }
-
-            // Reset and keyboard zoom shortcuts
-            if i.modifiers.command && i.key_pressed(egui::Key::Num0) {
-                match &self.content {
-                    Some(Content::Text(_)) => self.text_zoom = 1.0,
-                    Some(Content::Image(_)) => { self.image_fit = false; self.image_zoom = 1.0; },
-                    _ => {}
-                }
+            if !typing && is_image {
+                if pressed(Action::RotateClockwise) { image_transform = Some(crate::imaging::Transform::RotateClockwise); }
+                if pressed(Action::RotateCounterClockwise) { image_transform = Some(crate::imaging::Transform::RotateCounterClockwise); }
             }
-            if i.modifiers.command && i.key_pressed(egui::Key::Equals) {
-                match &self.content {
-                    Some(Content::Text(_)) => self.text_zoom = (self.text_zoom * 1.05).clamp(0.6, 3.0),
-                    Some(Content::Image(_)) => { self.image_fit = false; self.image_zoom = (self.image_zoom * 1.10).clamp(0.1, 6.0); },
-                    _ => {}
-                }
+            if !typing && self.pdf.is_some() {
+                if i.key_pressed(egui::Key::PageDown) { pdf_step = Some(true); }
+                if i.key_pressed(egui::Key::PageUp) { pdf_step = Some(false); }
+            }
+            if !capturing && i.key_pressed(egui::Key::Escape) {
+                self.measure_points.clear();
+                self.crop_rect = None;
+                // Escape in a text field leaves the field; it only ends the slideshow otherwise
+                slideshow_stop = !typing;
+            }
+            if !typing && i.key_pressed(egui::Key::Space) && self.slideshow.is_some() {
+                slideshow_pause = true;
+            }
+            if !typing && pressed(Action::ToggleBookmark) && is_text {
+                toggle_mark = true;
+            }
+            if is_text {
+                if pressed(Action::NextBookmark) { self.cycle_bookmark(true); }
+                if pressed(Action::PreviousBookmark) { self.cycle_bookmark(false); }
+            }
+
+            // Ctrl + Mouse wheel zoom for content
+            if i.modifiers.command && i.raw_scroll_delta.y != 0.0 {
+                zoom_steps.push(i.raw_scroll_delta.y > 0.0);
             }
-            if i.modifiers.command && i.key_pressed(egui::Key::Minus) {
+
+            // Reset and keyboard zoom shortcuts
+            if pressed(Action::ResetZoom) {
                 match &self.content {
-                    Some(Content::Text(_)) => self.text_zoom = (self.text_zoom / 1.05).clamp(0.6, 3.0),
-                    Some(Content::Image(_)) => { self.image_fit = false; self.image_zoom = (self.image_zoom / 1.10).clamp(0.1, 6.0); },
+                    Some(Content::Text(_) | Content::LargeText(_) | Content::Hex(_)) => self.text_zoom = 1.0,
+                    Some(Content::Image(_)) => { self.image_fit = false; self.image_zoom = 1.0; },
                     _ => {}
                 }
             }
+            if pressed(Action::ZoomIn) { zoom_steps.push(true); }
+            if pressed(Action::ZoomOut) { zoom_steps.push(false); }
 
             // Navigation with arrow keys for current content type
-            if i.key_pressed(egui::Key::ArrowRight) {
-                if let Some(cur) = self.current_path.clone() {
-                    match self.content {
-                        Some(Content::Image(_)) => {
-                            if let Some(next) = crate::io::neighbor_image(&cur, true) { file_to_load = Some(next); }
-                        }
-                        Some(Content::Text(_)) => {
-                            if let Some(next) = crate::io::neighbor_text(&cur, true) { file_to_load = Some(next); }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            if i.key_pressed(egui::Key::ArrowLeft) {
-                if let Some(cur) = self.current_path.clone() {
-                    match self.content {
-                        Some(Content::Image(_)) => {
-                            if let Some(prev) = crate::io::neighbor_image(&cur, false) { file_to_load = Some(prev); }
-                        }
-                        Some(Content::Text(_)) => {
-                            if let Some(prev) = crate::io::neighbor_text(&cur, false) { file_to_load = Some(prev); }
-                        }
-                        _ => {}
-                    }
-                }
-            }
+            if !widget_focused && pressed(Action::NextFile) { navigate = Some(true); }
+            if !widget_focused && pressed(Action::PreviousFile) { navigate = Some(false); }
             // Support '<' and '>' typed keys for both images and text
             for ev in &i.events {
-                if let egui::Event::Text(t) = ev {
+                if !typing && let egui::Event::Paste(t) = ev {
+                    paste = Some(t.clone());
+                }
+                if !capturing && let egui::Event::Text(t) = ev {
                     if t == ">" {
-                        if let Some(cur) = self.current_path.clone() {
-                            match self.content {
-                                Some(Content::Image(_)) => { if let Some(next) = crate::io::neighbor_image(&cur, true) { file_to_load = Some(next); } }
-                                Some(Content::Text(_)) => { if let Some(next) = crate::io::neighbor_text(&cur, true) { file_to_load = Some(next); } }
-                                _ => {}
-                            }
-                        }
+                        navigate = Some(true);
                     } else if t == "<" {
-                        if let Some(cur) = self.current_path.clone() {
-                            match self.content {
-                                Some(Content::Image(_)) => { if let Some(prev) = crate::io::neighbor_image(&cur, false) { file_to_load = Some(prev); } }
-                                Some(Content::Text(_)) => { if let Some(prev) = crate::io::neighbor_text(&cur, false) { file_to_load = Some(prev); } }
-                                _ => {}
-                            }
-                        }
+                        navigate = Some(false);
                     }
                 }
             }
         });
+        for zoom_in in zoom_steps {
+            self.step_zoom(zoom_in);
+        }
+        if toggle_mark
+            && let Some(line) = self.cursor_line.or(self.first_visible_line)
+            && self.toggle_bookmark(line)
+        {
+            let marked = self.current_bookmarks().binary_search(&line).is_ok();
+            self.show_toast(if marked { format!("Bookmarked line {}", line + 1) } else { format!("Removed bookmark on line {}", line + 1) });
+        }
+        if let Some(t) = image_transform {
+            self.transform_image(t);
+        }
+        if print {
+            self.start_print();
+        }
+        if slideshow_stop {
+            self.stop_slideshow(ctx);
+        }
+        if slideshow_pause {
+            self.toggle_slideshow_pause();
+        }
+        // Next/previous file keys turn the pages of a PDF
+        if self.pdf.is_some() && navigate.is_some() {
+            pdf_step = navigate.take();
+        }
+        if let Some(forward) = pdf_step
+            && let Some(page) = self.pdf.as_ref().map(PdfView::target)
+        {
+            self.go_to_pdf_page(if forward { page + 1 } else { page.saturating_sub(1) });
+        }
+        if let Some(forward) = navigate
+            && let Some(next) = self.neighbor(forward)
+        {
+            file_to_load = Some(next);
+        }
+        if let Some(next) = self.poll_sibling_scan(ctx) {
+            file_to_load = Some(next);
+        }
+        if let Some(next) = self.poll_slideshow(ctx) {
+            file_to_load = Some(next);
+        }
 
         // About dialog
         if self.show_about {
@@ -423,56 +3455,81 @@ impl eframe::App for FileViewerApp {
                 .resizable(false)
                 .open(&mut self.show_about)
                 .show(ctx, |ui| {
-                    ui.label(RichText::new("Gemini File Viewer 2.1").strong());
+                    ui.label(RichText::new(APP_TITLE).strong());
                     ui.label(format!("Version {}", env!("CARGO_PKG_VERSION")));
                     ui.separator();
                     ui.label("Shortcuts:");
-                    ui.monospace("Ctrl+O — Open file");
-                    ui.monospace("Ctrl+D — Toggle dark mode");
-                    ui.monospace("Ctrl+L — Toggle line numbers");
-                    ui.monospace("Ctrl+W — Toggle word wrap");
+                    for action in Action::ALL {
+                        ui.monospace(format!("{} — {}", self.key_bindings.label(action), action.name()));
+                    }
                     ui.monospace("Ctrl+Wheel — Zoom text/image");
-                    ui.monospace("Ctrl+= / Ctrl+- — Zoom in/out");
-                    ui.monospace("Ctrl+0 — Reset zoom");
-                    ui.monospace("Ctrl+F — Find in text");
+                    ui.monospace("Ctrl+V — View clipboard text");
+                    ui.monospace("1-5 — Image zoom 25/50/100/200/400%");
+                    ui.monospace("Space / Esc — Pause/stop slideshow");
+                    ui.monospace("PgUp / PgDn — Previous/next PDF page");
+                    ui.monospace("Drag, Ctrl+C — Copy selected text");
+                    ui.monospace("Click — Copy path:line reference");
+                    ui.monospace("Shift+Click — Copy path:line with text");
+                    ui.monospace("Ctrl+Click — Toggle line bookmark");
+                    ui.monospace("Click line number — Toggle line bookmark");
+                    ui.label("Shortcuts can be changed in Settings.");
                 });
         }
         if toggle_dark {
-            self.dark_mode = !self.dark_mode;
-            self.theme = if self.dark_mode { Theme::Dark } else { Theme::Light };
+            self.set_dark_mode(!self.dark_mode);
             self.follow_system_theme = false; // manual override
             self.apply_theme(ctx);
-            crate::settings::save_settings_to_disk(self);
+            crate::settings::mark_settings_dirty(self);
         }
 
+        // A full screen slideshow shows nothing but the image
+        let chrome = !(self.slideshow.is_some() && self.slideshow_fullscreen);
+
         // Top Toolbar
-        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
-            ui.horizontal_wrapped(|ui| {
-                crate::ui::toolbar(ui, self, ctx, &mut file_to_load);
+        if chrome {
+            egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    crate::ui::toolbar(ui, self, ctx, &mut file_to_load);
+                });
             });
-        });
+
+            egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+                if let Some(action) = crate::ui::tab_bar(ui, self) { tab_action = Some(action); }
+            });
+        }
+        match tab_action {
+            Some(crate::ui::TabAction::Switch(index)) => self.switch_tab(index, ctx),
+            Some(crate::ui::TabAction::Cycle(forward)) => self.cycle_tab(forward, ctx),
+            Some(crate::ui::TabAction::Close(index)) => self.close_tab(index, ctx),
+            Some(crate::ui::TabAction::New) => self.new_tab(),
+            None => {}
+        }
 
         // Search Bar (only when viewing text)
-        if matches!(self.content, Some(Content::Text(_))) {
+        if matches!(self.content, Some(Content::Text(_) | Content::LargeText(_))) {
             egui::TopBottomPanel::top("searchbar").show(ctx, |ui| {
                 crate::ui::search_bar(ui, self);
             });
         }
 
-        // Status Bar
-        egui::TopBottomPanel::bottom("statusbar").show(ctx, |ui| {
-            crate::ui::status_bar(ui, self);
-        });
+        if chrome {
+            // Status Bar
+            egui::TopBottomPanel::bottom("statusbar").show(ctx, |ui| {
+                crate::ui::status_bar(ui, self, &mut file_to_load);
+            });
 
-        // Extra status information
-        egui::TopBottomPanel::bottom("status-extra").show(ctx, |ui| {
-            crate::ui::status_extra(ui, self);
-        });
+            // Extra status information
+            egui::TopBottomPanel::bottom("status-extra").show(ctx, |ui| {
+                crate::ui::status_extra(ui, self);
+            });
+        }
 
         // Theme Editor window
         if self.show_theme_editor {
             let mut open = self.show_theme_editor;
-            egui::Window::new("Theme Editor").open(&mut open).resizable(false).show(ctx, |ui| {
+            let mut window = egui::Window::new("Theme Editor").open(&mut open).resizable(false);
+            if let Some(pos) = self.remembered_window_pos(ctx, "Theme Editor") { window = window.default_pos(pos); }
+            let shown = window.show(ctx, |ui| {
                 ui.heading("Theme & Layout");
                 ui.separator();
                 ui.checkbox(&mut self.follow_system_theme, "Follow system light/dark");
@@ -483,68 +3540,648 @@ impl eframe::App for FileViewerApp {
                         self.accent_rgb = [srgba.r(), srgba.g(), srgba.b()];
                     }
                 });
-                ui.add(egui::Slider::new(&mut self.spacing_scale, 0.6..=1.6).text("Spacing scale"));
-                ui.add(egui::Slider::new(&mut self.theme_rounding, 0..=12).text("Corner radius"));
+                let mut changed = ui.add(egui::Slider::new(&mut self.spacing_scale, 0.6..=1.6).text("Spacing scale")).changed();
+                changed |= ui.add(egui::Slider::new(&mut self.theme_rounding, 0..=12).text("Corner radius")).changed();
+                if changed { crate::settings::mark_settings_dirty(self); }
                 ui.label("Close this window using the × in the title bar.");
             });
+            self.track_window_pos(ctx, "Theme Editor", shown.map(|r| r.response.rect));
             self.show_theme_editor = open;
         }
 
+        // Settings window
+        if self.show_settings {
+            let mut open = self.show_settings;
+            let mut window = egui::Window::new("Settings").open(&mut open).resizable(false);
+            if let Some(pos) = self.remembered_window_pos(ctx, "Settings") { window = window.default_pos(pos); }
+            let shown = window.show(ctx, |ui| {
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        for page in SettingsPage::ALL {
+                            ui.selectable_value(&mut self.settings_page, page, page.name());
+                        }
+                    });
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(ctx.screen_rect().height() * 0.7).show(ui, |ui| {
+                        ui.set_width(440.0);
+                        match self.settings_page {
+                            SettingsPage::Appearance => {
+                                ui.heading("Appearance");
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    ui.label("Density:");
+                                    for (name, scale) in DENSITY_PRESETS {
+                                        let selected = (self.spacing_scale - scale).abs() < 0.01;
+                                        if ui.selectable_label(selected, name).clicked() && !selected {
+                                            self.spacing_scale = scale;
+                                            self.apply_theme(ctx);
+                                            crate::settings::mark_settings_dirty(self);
+                                        }
+                                    }
+                                });
+                                if ui.checkbox(&mut self.remember_window_positions, "Remember window positions").on_hover_text("Reopen Settings and the Theme Editor where they were last placed").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.add_space(8.0);
+                                ui.heading("Zoom");
+                                ui.separator();
+                                if ui.checkbox(&mut self.preserve_zoom, "Preserve zoom across files").on_hover_text("When off, each opened file starts at the default zoom below").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.add_enabled_ui(!self.preserve_zoom, |ui| {
+                                    let mut changed = false;
+                                    ui.horizontal(|ui| {
+                                        ui.label("Default text zoom:");
+                                        changed |= ui.add(egui::Slider::new(&mut self.default_text_zoom, 0.6..=3.0).custom_formatter(|v, _| format!("{:.0}%", v * 100.0))).changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Default image zoom:");
+                                        changed |= ui.checkbox(&mut self.default_image_fit, "Fit to window").changed();
+                                        ui.add_enabled_ui(!self.default_image_fit, |ui| {
+                                            changed |= ui.add(egui::Slider::new(&mut self.default_image_zoom, 0.1..=6.0).custom_formatter(|v, _| format!("{:.0}%", v * 100.0))).changed();
+                                        });
+                                    });
+                                    if changed { crate::settings::mark_settings_dirty(self); }
+                                });
+                                let mut wheel_changed = false;
+                                ui.horizontal(|ui| {
+                                    ui.label("Zoom step:");
+                                    wheel_changed |= ui.add(egui::DragValue::new(&mut self.text_zoom_step).range(1.0..=50.0).speed(0.5).prefix("text ").suffix("%")).changed();
+                                    wheel_changed |= ui.add(egui::DragValue::new(&mut self.image_zoom_step).range(1.0..=50.0).speed(0.5).prefix("image ").suffix("%")).changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Scroll speed:");
+                                    wheel_changed |= ui.add(egui::Slider::new(&mut self.scroll_speed, 0.25..=4.0).custom_formatter(|v, _| format!("{:.2}×", v))).changed();
+                                });
+                                wheel_changed |= ui.checkbox(&mut self.invert_scroll, "Invert scroll direction").on_hover_text("Flips the mouse wheel for scrolling and Ctrl+wheel zoom").changed();
+                                if wheel_changed { crate::settings::mark_settings_dirty(self); }
+                            }
+                            SettingsPage::Text => {
+                                ui.heading("Text");
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    let mut changed = ui.checkbox(&mut self.word_wrap, "Word wrap").changed();
+                                    changed |= ui.checkbox(&mut self.show_line_numbers, "Line numbers").changed();
+                                    changed |= ui.checkbox(&mut self.show_minimap, "Minimap").changed();
+                                    if changed { crate::settings::mark_settings_dirty(self); }
+                                });
+                                ui.horizontal(|ui| {
+                                    let mut on = self.wrap_column.is_some();
+                                    let mut column = self.wrap_column.unwrap_or(80);
+                                    let mut changed = ui.checkbox(&mut on, "Ruler at column")
+                                        .on_hover_text("Draws a line at this column; with word wrap on, lines wrap there instead of at the window edge")
+                                        .changed();
+                                    ui.add_enabled_ui(on, |ui| {
+                                        for preset in [80, 100, 120] {
+                                            changed |= ui.selectable_value(&mut column, preset, preset.to_string()).changed();
+                                        }
+                                        changed |= ui.add(egui::DragValue::new(&mut column).range(20..=1000)).changed();
+                                    });
+                                    if changed {
+                                        self.wrap_column = on.then_some(column);
+                                        crate::settings::mark_settings_dirty(self);
+                                    }
+                                });
+                                if ui.add(egui::Slider::new(&mut self.line_height_scale, 1.0..=2.5).text("Line spacing")).changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.label("Performance limits (characters):");
+                                let mut limits_changed = false;
+                                ui.horizontal(|ui| {
+                                    limits_changed |= ui.add(egui::DragValue::new(&mut self.big_text_threshold).range(50_000..=50_000_000).speed(10_000)).changed();
+                                    ui.label("Large file (no line numbers/highlighting)");
+                                });
+                                ui.horizontal(|ui| {
+                                    limits_changed |= ui.add(egui::DragValue::new(&mut self.highlight_threshold).range(10_000..=50_000_000).speed(10_000)).changed();
+                                    ui.label("Syntax and search highlighting");
+                                });
+                                ui.label(RichText::new("Higher limits render more features on big files but can make scrolling sluggish on slower machines.").weak().small());
+                                if limits_changed {
+                                    if let Some(Content::Text(text)) = &self.content {
+                                        self.text_is_big = self.is_big_text(text.len(), self.text_line_count);
+                                    }
+                                    self.refresh_search();
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.horizontal(|ui| {
+                                    let mut changed = ui.checkbox(&mut self.wrap_long_lines, "When word wrap is off, wrap lines longer than")
+                                        .on_hover_text("Keeps normal code unwrapped while very long (e.g. minified) lines wrap")
+                                        .changed();
+                                    changed |= ui.add_enabled(self.wrap_long_lines, egui::DragValue::new(&mut self.wrap_line_threshold).range(40..=100_000).suffix(" chars")).changed();
+                                    if changed { crate::settings::mark_settings_dirty(self); }
+                                });
+                                if ui.checkbox(&mut self.show_byte_offsets, "Show byte offsets in the gutter")
+                                    .on_hover_text("Each line's starting byte offset in the file, next to or instead of the line number; uses the hex view's offset base")
+                                    .changed()
+                                {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                if ui.checkbox(&mut self.auto_format, "Auto-format JSON, TOML and XML on open")
+                                    .on_hover_text("Shows minified files indented; the Formatted toggle brings back the original. TOML comments are not kept in the formatted view.")
+                                    .changed()
+                                {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.horizontal(|ui| {
+                                    let mut changed = ui.checkbox(&mut self.expand_tabs, "Expand tabs to stops every")
+                                        .on_hover_text("Soft tabs line up on tab stops; off draws each tab as a fixed-width gap")
+                                        .changed();
+                                    changed |= ui.add_enabled(self.expand_tabs, egui::DragValue::new(&mut self.tab_width).range(1..=16).suffix(" columns")).changed();
+                                    if changed { crate::settings::mark_settings_dirty(self); }
+                                });
+                                if ui.checkbox(&mut self.show_control_chars, "Show control characters and invalid UTF-8")
+                                    .on_hover_text("Render control characters as ^X and mark bytes that failed to decode")
+                                    .changed()
+                                {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.add_space(8.0);
+                                ui.heading("Search");
+                                ui.separator();
+                                if ui.checkbox(&mut self.remember_search, "Remember last search across restarts").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                            }
+                            SettingsPage::Files => {
+                                ui.heading("Files");
+                                ui.separator();
+                                if ui.checkbox(&mut self.follow_symlinks, "Follow symbolic links").on_hover_text("Open the link target instead of showing the link path").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                if ui.checkbox(&mut self.show_hidden_files, "Include hidden files when browsing")
+                                    .on_hover_text("Prev/Next also step through dotfiles and files marked hidden")
+                                    .changed()
+                                {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                if ui.checkbox(&mut self.shared_read, "Let other programs write files while they're being read")
+                                    .on_hover_text("When off, writers are refused until a read finishes. Sharing modes only apply on Windows.")
+                                    .changed()
+                                {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                if ui.checkbox(&mut self.relative_paths, "Show relative paths").on_hover_text("Status bar and Recent menu show paths relative to the base below; Copy Path stays absolute").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.add_enabled_ui(self.relative_paths, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Relative to:");
+                                        match &self.path_base {
+                                            Some(base) => ui.monospace(base.to_string_lossy()),
+                                            None => ui.label(RichText::new("working directory").italics()),
+                                        };
+                                        if ui.small_button("Choose…").clicked()
+                                            && let Some(dir) = FileDialog::new().pick_folder()
+                                        {
+                                            self.path_base = Some(dir);
+                                            crate::settings::mark_settings_dirty(self);
+                                        }
+                                        if self.path_base.is_some() && ui.small_button("Use working directory").clicked() {
+                                            self.path_base = None;
+                                            crate::settings::mark_settings_dirty(self);
+                                        }
+                                    });
+                                });
+                                if ui.checkbox(&mut self.show_file_times, "Show modification and creation times").on_hover_text("Shown in the status bar; hover for the exact time").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.label("Unknown file types:");
+                                    let before = self.unknown_files;
+                                    egui::ComboBox::from_id_salt("unknown_files")
+                                        .selected_text(match self.unknown_files {
+                                            UnknownFiles::Text => "Open as text",
+                                            UnknownFiles::Hex => "Open as hex",
+                                            UnknownFiles::Ask => "Ask each time",
+                                        })
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(&mut self.unknown_files, UnknownFiles::Text, "Open as text");
+                                            ui.selectable_value(&mut self.unknown_files, UnknownFiles::Hex, "Open as hex");
+                                            ui.selectable_value(&mut self.unknown_files, UnknownFiles::Ask, "Ask each time");
+                                        });
+                                    if self.unknown_files != before { crate::settings::mark_settings_dirty(self); }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("On startup:");
+                                    let before = self.resume_session;
+                                    egui::ComboBox::from_id_salt("resume_session")
+                                        .selected_text(match self.resume_session {
+                                            ResumeSession::Never => "Start empty",
+                                            ResumeSession::Ask => "Offer to resume last session",
+                                            ResumeSession::Always => "Resume last session",
+                                        })
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(&mut self.resume_session, ResumeSession::Never, "Start empty");
+                                            ui.selectable_value(&mut self.resume_session, ResumeSession::Ask, "Offer to resume last session");
+                                            ui.selectable_value(&mut self.resume_session, ResumeSession::Always, "Resume last session");
+                                        });
+                                    if self.resume_session != before { crate::settings::mark_settings_dirty(self); }
+                                });
+                                ui.add_space(8.0);
+                                ui.heading("Size limits");
+                                ui.separator();
+                                let mut sizes_changed = false;
+                                ui.horizontal(|ui| {
+                                    ui.label("Open images, hex and archives up to");
+                                    sizes_changed |= ui.add(egui::DragValue::new(&mut self.max_file_mb).range(1..=10_000).suffix(" MB")).changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Load text whole up to");
+                                    sizes_changed |= ui.add(egui::DragValue::new(&mut self.max_text_mb).range(1..=MAX_TEXT_MB).suffix(" MB")).changed();
+                                });
+                                ui.label(RichText::new("Bigger text is indexed and read a screenful at a time instead, whatever its size.").weak().small());
+                                if sizes_changed { crate::settings::mark_settings_dirty(self); }
+                            }
+                            SettingsPage::Privacy => {
+                                ui.heading("Privacy");
+                                ui.separator();
+                                if ui.checkbox(&mut self.private_mode, "Don't record recent files").on_hover_text("Opened files are not added to the Recent menu, which is hidden").changed() {
+                                    if self.private_mode { self.recent_files.clear(); }
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                ui.add_enabled_ui(!self.private_mode, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Remember up to");
+                                        if ui.add(egui::DragValue::new(&mut self.max_recent_files).range(1..=MAX_RECENT_FILES_CAP)).changed() {
+                                            self.trim_recent();
+                                            crate::settings::mark_settings_dirty(self);
+                                        }
+                                        ui.label("recent files");
+                                    });
+                                });
+                                if ui.button("🧹 Clear all history").on_hover_text("Forget recent files, bookmarks, reopen history, the last session and the saved search").clicked() {
+                                    self.clear_history();
+                                    self.show_toast("History cleared");
+                                }
+                            }
+                            SettingsPage::Images => {
+                                ui.heading("Slideshow");
+                                ui.separator();
+                                let mut slideshow_changed = false;
+                                ui.horizontal(|ui| {
+                                    ui.label("Show each image for:");
+                                    slideshow_changed |= ui.add(egui::DragValue::new(&mut self.slideshow_secs).range(1.0..=60.0).speed(0.2).fixed_decimals(1).suffix(" s")).changed();
+                                });
+                                slideshow_changed |= ui.checkbox(&mut self.slideshow_fullscreen, "Full screen without toolbars").changed();
+                                if slideshow_changed { crate::settings::mark_settings_dirty(self); }
+                            }
+                            SettingsPage::Hex => {
+                                ui.heading("Hex");
+                                ui.separator();
+                                let mut hex_changed = false;
+                                ui.horizontal(|ui| {
+                                    ui.label("Bytes per row:");
+                                    for n in HEX_ROW_SIZES {
+                                        hex_changed |= ui.selectable_value(&mut self.hex_row_bytes, n, n.to_string()).changed();
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Offsets:");
+                                    hex_changed |= ui.selectable_value(&mut self.hex_decimal_offsets, false, "Hexadecimal").changed();
+                                    hex_changed |= ui.selectable_value(&mut self.hex_decimal_offsets, true, "Decimal").changed();
+                                });
+                                hex_changed |= ui.checkbox(&mut self.hex_show_ascii, "Show ASCII column").changed();
+                                if hex_changed { crate::settings::mark_settings_dirty(self); }
+                            }
+                            SettingsPage::Keyboard => {
+                                ui.heading("Keyboard");
+                                ui.separator();
+                                if let Some(action) = self.key_capture {
+                                    // The next key pressed, with the modifiers held, becomes the shortcut; Esc cancels
+                                    let pressed = ctx.input(|i| {
+                                        i.events.iter().find_map(|e| match e {
+                                            egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                                            _ => None,
+                                        })
+                                    });
+                                    if let Some((key, modifiers)) = pressed {
+                                        if key != egui::Key::Escape {
+                                            self.key_bindings.set(action, Some(crate::keys::Binding::new(key, modifiers)));
+                                            crate::settings::mark_settings_dirty(self);
+                                        }
+                                        self.key_capture = None;
+                                    }
+                                }
+                                ui.collapsing("Shortcuts", |ui| {
+                                    egui::Grid::new("key_bindings").num_columns(3).striped(true).show(ui, |ui| {
+                                        for action in Action::ALL {
+                                            ui.label(action.name());
+                                            let conflicts = self.key_bindings.conflicts(action);
+                                            let mut text = RichText::new(if self.key_capture == Some(action) { "Press a key…".to_string() } else { self.key_bindings.label(action) });
+                                            if !conflicts.is_empty() {
+                                                text = text.color(egui::Color32::from_rgb(224, 108, 117));
+                                            }
+                                            let mut resp = ui.add(egui::Button::new(text).min_size(egui::vec2(140.0, 0.0)));
+                                            resp = if conflicts.is_empty() {
+                                                resp.on_hover_text("Click, then press the new key with its modifiers (Esc cancels)")
+                                            } else {
+                                                let names: Vec<&str> = conflicts.iter().map(|a| a.name()).collect();
+                                                resp.on_hover_text(format!("Also the shortcut for: {}", names.join(", ")))
+                                            };
+                                            if resp.clicked() {
+                                                self.key_capture = Some(action);
+                                            }
+                                            if ui.add_enabled(self.key_bindings.get(action).is_some(), egui::Button::new("×").small()).on_hover_text("Remove the shortcut").clicked() {
+                                                self.key_bindings.set(action, None);
+                                                crate::settings::mark_settings_dirty(self);
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                                    if ui.add_enabled(!self.key_bindings.is_default(), egui::Button::new("Reset to defaults")).clicked() {
+                                        self.key_bindings.reset();
+                                        self.key_capture = None;
+                                        crate::settings::mark_settings_dirty(self);
+                                    }
+                                });
+                            }
+                            SettingsPage::Diagnostics => {
+                                ui.heading("Diagnostics");
+                                ui.separator();
+                                if ui.checkbox(&mut self.low_power, "Low power mode").on_hover_text("Check the system theme and tailed files less often and skip highlight animations").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                                if ui.checkbox(&mut self.show_perf_overlay, "Show performance overlay").on_hover_text("FPS, frame time and approximate memory used by the loaded content").changed() {
+                                    crate::settings::mark_settings_dirty(self);
+                                }
+                            }
+                        }
+                    });
+                });
+            });
+            self.track_window_pos(ctx, "Settings", shown.map(|r| r.response.rect));
+            self.show_settings = open;
+        }
+
+        // Chooser for files of unknown type
+        if let Some(path) = self.pending_unknown.clone() {
+            let mut choice = None;
+            let mut cancel = false;
+            egui::Window::new("Open As")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    ui.label(format!("\"{}\" has an unrecognized file type. Open it as:", name));
+                    ui.horizontal(|ui| {
+                        if ui.button("📄 Text").clicked() { choice = Some(OpenAs::Text); }
+                        if ui.button("🔢 Hex").clicked() { choice = Some(OpenAs::Hex); }
+                        if ui.button("🖼 Image").clicked() { choice = Some(OpenAs::Image); }
+                        if ui.button("Cancel").clicked() { cancel = true; }
+                    });
+                });
+            if let Some(open_as) = choice {
+                self.pending_unknown = None;
+                self.load_file_as(path, open_as, ctx);
+            } else if cancel {
+                self.pending_unknown = None;
+            }
+        }
+
+        if self.goto_line_open && matches!(self.content, Some(Content::Text(_) | Content::LargeText(_))) {
+            let mut open = true;
+            let mut go = false;
+            egui::Window::new("Go to Line")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_TOP, [0.0, 80.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Line (1–{}):", self.text_line_count.max(1)));
+                        let input = ui.add(egui::TextEdit::singleline(&mut self.goto_line_input).desired_width(90.0));
+                        if ctx.memory(|m| m.focused().is_none()) {
+                            input.request_focus();
+                        }
+                        go = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        go |= ui.button("Go").clicked();
+                    });
+                });
+            if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                open = false;
+            }
+            if go {
+                self.goto_line();
+            }
+            self.goto_line_open &= open;
+        } else {
+            self.goto_line_open = false;
+        }
+
+        if self.print_open && matches!(self.content, Some(Content::Text(_) | Content::LargeText(_))) {
+            let mut open = true;
+            let mut print = false;
+            let last = self.text_line_count.max(1);
+            egui::Window::new("Print")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_TOP, [0.0, 80.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Lines");
+                        ui.add(egui::DragValue::new(&mut self.print_range.0).range(1..=last));
+                        ui.label("to");
+                        ui.add(egui::DragValue::new(&mut self.print_range.1).range(1..=last));
+                        if ui.small_button("All").clicked() {
+                            self.print_range = (1, last);
+                        }
+                    });
+                    let mut changed = ui.checkbox(&mut self.print_line_numbers, "Line numbers").changed();
+                    ui.add_enabled_ui(matches!(self.content, Some(Content::Text(_))), |ui| {
+                        changed |= ui.checkbox(&mut self.print_colors, "Syntax colors").changed();
+                    });
+                    if changed {
+                        crate::settings::mark_settings_dirty(self);
+                    }
+                    ui.label(egui::RichText::new("Opens in the browser, whose print dialog can also save a PDF.").weak().small());
+                    print = ui.button("🖨 Print…").clicked();
+                });
+            if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                open = false;
+            }
+            if print {
+                self.print_text();
+            }
+            self.print_open &= open;
+        } else {
+            self.print_open = false;
+        }
+
+        // Replace preview window
+        if self.replace_preview.is_some() {
+            let mut open = true;
+            let mut save = false;
+            egui::Window::new("Replace Preview").open(&mut open).default_size([640.0, 420.0]).show(ctx, |ui| {
+                let Some((text, count)) = &self.replace_preview else { return };
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} replacement(s) of \"{}\" with \"{}\"", count, self.search_query, self.replace_with));
+                    if ui.button("💾 Save As…").on_hover_text("Write the result to a new file").clicked() { save = true; }
+                });
+                ui.separator();
+                egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                    ui.label(RichText::new(crate::io::trim_final_newline(text)).monospace());
+                });
+            });
+            if save { self.export_replaced(); }
+            if !open { self.replace_preview = None; }
+        }
+
+        // Histogram window
+        if self.show_histogram && matches!(self.content, Some(Content::Image(_))) {
+            let mut open = self.show_histogram;
+            egui::Window::new("Histogram").open(&mut open).collapsible(true).resizable(false).show(ctx, |ui| {
+                match &self.image_histogram {
+                    Some(hist) => crate::ui::histogram_plot(ui, hist),
+                    None => { ui.label("No histogram available."); }
+                }
+            });
+            if open != self.show_histogram {
+                self.show_histogram = open;
+                crate::settings::mark_settings_dirty(self);
+            }
+        }
+
+        // Image adjustments window (preview only)
+        if self.show_adjust && matches!(self.content, Some(Content::Image(_))) {
+            let mut open = self.show_adjust;
+            egui::Window::new("Adjustments").open(&mut open).resizable(false).show(ctx, |ui| {
+                let adj = &mut self.image_adjust;
+                let mut changed = false;
+                changed |= ui.add(egui::Slider::new(&mut adj.brightness, -1.0..=1.0).text("Brightness")).changed();
+                changed |= ui.add(egui::Slider::new(&mut adj.contrast, 0.0..=3.0).text("Contrast")).changed();
+                changed |= ui.add(egui::Slider::new(&mut adj.gamma, 0.2..=3.0).text("Gamma")).changed();
+                if ui.button("Reset").clicked() {
+                    *adj = Default::default();
+                    changed = true;
+                }
+                ui.label(RichText::new("Preview only — the file and exports use the original pixels.").weak().small());
+                if changed { self.image_adjust_changed = Some(std::time::Instant::now()); }
+            });
+            self.show_adjust = open;
+        }
+        if let Some(at) = self.image_adjust_changed {
+            let settle = std::time::Duration::from_millis(120);
+            if at.elapsed() >= settle {
+                self.image_adjust_changed = None;
+                self.rebuild_texture();
+            } else {
+                ctx.request_repaint_after(settle);
+            }
+        }
+
+        self.poll_file_changes(ctx);
+        self.expire_print_files(ctx);
+
+        // Search in folder panel
+        if self.show_folder_search {
+            if self.folder_search_dir.is_none() {
+                self.folder_search_dir = self.current_path.as_ref().and_then(|p| p.parent()).map(Path::to_path_buf);
+            }
+            let mut open_hit = None;
+            egui::SidePanel::right("folder_search").resizable(true).default_width(320.0).show(ctx, |ui| {
+                open_hit = crate::ui::folder_search_panel(ui, self);
+            });
+            if let Some((path, line)) = open_hit {
+                self.open_at_line(path, line, ctx);
+            }
+        }
+
+        // Outline side panel
+        if self.show_outline && matches!(self.content, Some(Content::Text(_))) {
+            egui::SidePanel::left("outline").resizable(true).default_width(220.0).show(ctx, |ui| {
+                ui.heading("Outline");
+                ui.separator();
+                egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                    if self.outline.is_empty() { ui.label(RichText::new("(no entries)").weak()); }
+                    let mut jump: Option<usize> = None;
+                    for entry in &self.outline {
+                        ui.horizontal(|ui| {
+                            ui.add_space(entry.depth.min(6) as f32 * 12.0);
+                            if ui.selectable_label(false, &entry.label).on_hover_text(format!("Line {}", entry.line + 1)).clicked() {
+                                jump = Some(entry.line);
+                            }
+                        });
+                    }
+                    if jump.is_some() { self.scroll_to_line = jump; }
+                });
+            });
+        }
+
+        // Thumbnails of the folder's images, once it has been listed
+        if chrome
+            && self.show_thumbnails
+            && self.pdf.is_none()
+            && matches!(self.content, Some(Content::Image(_)))
+            && let Some(siblings) = &self.siblings
+            && self.current_path.as_deref().and_then(Path::parent) == Some(siblings.dir.as_path())
+        {
+            egui::TopBottomPanel::bottom("thumbnails").show(ctx, |ui| {
+                if let Some(path) = crate::ui::thumbnail_strip(ui, self) {
+                    file_to_load = Some(path);
+                }
+            });
+        }
+
         // Main Content
+        let mut text_actions = crate::ui::TextViewActions::default();
+        let mut hex_actions = crate::ui::HexViewActions::default();
+        let mut archive_entry_to_open: Option<String> = None;
+        let mut cancel_load = false;
+        let mut resume = false;
+        let mut sort_column: Option<usize> = None;
+        let mut pane_actions = crate::ui::SidePaneActions::default();
         egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(pane) = &self.side_pane {
+                pane_actions = match self.split_direction {
+                    crate::pane::SplitDirection::Vertical => egui::SidePanel::right("side_pane")
+                        .resizable(true)
+                        .default_width(ui.available_width() * 0.5)
+                        .show_inside(ui, |ui| crate::ui::side_pane_view(ui, self, pane))
+                        .inner,
+                    crate::pane::SplitDirection::Horizontal => egui::TopBottomPanel::bottom("side_pane")
+                        .resizable(true)
+                        .default_height(ui.available_height() * 0.5)
+                        .show_inside(ui, |ui| crate::ui::side_pane_view(ui, self, pane))
+                        .inner,
+                };
+            }
             if let Some(err) = &self.error_message {
                 ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
+                crate::ui::error_details(ui, err, self.error_path.as_deref());
             }
 
             if let Some(content) = &self.content {
                 match content {
                     Content::Text(text) => {
-                        let mut frame = egui::Frame::group(ui.style());
-                        frame.fill = if self.dark_mode { egui::Color32::from_rgb(28, 28, 30) } else { egui::Color32::from_rgb(255, 255, 255) };
-                        frame.inner_margin = egui::Margin::symmetric(12, 10);
-                        frame = frame.corner_radius(egui::CornerRadius::same(8));
-                        frame.show(ui, |ui| {
-                            // Wrap preference
-                            ui.style_mut().wrap_mode = Some(if self.word_wrap { egui::TextWrapMode::Wrap } else { egui::TextWrapMode::Extend });
-                            egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
-                                let text_style = egui::TextStyle::Monospace;
-                                let mut font_id = text_style.resolve(ui.style());
-                                font_id.size = (font_id.size * self.text_zoom).clamp(8.0, 48.0);
-                                let text_color = ui.visuals().text_color();
-
-                                let do_line_numbers = self.show_line_numbers && !self.text_is_big;
-                                let do_highlight = !self.text_is_big && text.len() <= HIGHLIGHT_CHAR_THRESHOLD;
-                                if do_line_numbers || do_highlight || !self.search_query.is_empty() {
-                                    let mut bracket_depth: i32 = 0;
-                                    let mut in_block_comment = false;
-                                    let ext = self
-                                        .current_path
-                                        .as_ref()
-                                        .and_then(|p| p.extension().and_then(|s| s.to_str()))
-                                        .unwrap_or("")
-                                        .to_lowercase();
-                                    // Determine target line for current match
-                                    let target_line = if !self.search_query.is_empty() && self.search_count > 0 {
-                                        search::find_target_line(text, &self.search_query, self.search_current)
-                                    } else { None };
-                                    // Render per line and capture rect
-                                    let mut counter: usize = 0;
-                                    let mut target_rect: Option<egui::Rect> = None;
-                                    for (i, line) in text.lines().enumerate() {
-                                        let mut line_job = LayoutJob::default();
-                                        if do_line_numbers {
-                                            line_job.append(&format!("{:>4} ", i + 1), 0.0, egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::GRAY, ..Default::default() });
-                                        }
-                                        highlight::append_highlighted(&mut line_job, line, &ext, &self.search_query, font_id.clone(), text_color, do_highlight, &mut bracket_depth, self.search_current, &mut counter, &mut in_block_comment);
-                                        let resp = ui.label(line_job);
-                                        if target_line == Some(i) { target_rect = Some(resp.rect); }
-                                    }
-                                    if let Some(rect) = target_rect { ui.scroll_to_rect(rect, Some(egui::Align::Center)); }
-                                } else {
-                                    ui.label(RichText::new(text).monospace().size(font_id.size));
-                                }
-                            });
-                        });
+                        if let Some(diff) = &self.text_diff {
+                            crate::ui::diff_view(ui, diff, self.diff_unified, self.text_zoom);
+                        } else if self.table_view && let Some(table) = &self.table {
+                            sort_column = crate::ui::table_view(ui, table, self.text_zoom);
+                        } else if self.json_view && let Some(tree) = &mut self.json_tree {
+                            crate::ui::json_tree_view(ui, tree, &crate::highlight::Palette::for_theme(self.theme), self.text_zoom);
+                        } else {
+                            if self.split_view {
+                                // Secondary pane on top; the draggable panel edge acts as the splitter
+                                egui::TopBottomPanel::top("split_top")
+                                    .resizable(true)
+                                    .default_height(ui.available_height() * 0.5)
+                                    .show_inside(ui, |ui| {
+                                        crate::ui::text_view(ui, self, text, "text_split", false, &mut text_actions);
+                                    });
+                            }
+                            crate::ui::text_view(ui, self, text, "text_main", true, &mut text_actions);
+                        }
+                    }
+                    Content::LargeText(index) => {
+                        crate::ui::large_text_view(ui, self, index, &mut text_actions);
+                    }
+                    Content::Archive(listing) => {
+                        crate::ui::archive_view(ui, listing, &mut archive_entry_to_open);
+                    }
+                    Content::Hex(bytes) => {
+                        crate::ui::hex_view(ui, self, bytes, &mut hex_actions);
                     }
                     Content::Image(texture) => {
                         let viewport = ui.available_size();
@@ -572,23 +4209,112 @@ impl eframe::App for FileViewerApp {
                         egui::ScrollArea::both().show(ui, |ui| {
                             ui.centered_and_justified(|ui| {
                                 let size = texture.size();
+                                // Compared images share the top-left corner; make room for the larger of each side
+                                let shown = self.compare.as_ref().map_or(size, |c| {
+                                    let c = c.texture.size();
+                                    [size[0].max(c[0]), size[1].max(c[1])]
+                                });
                                 let mut effective_zoom = self.image_zoom;
                                 if self.image_fit {
-                                    let sx = if size[0] > 0 { viewport.x / size[0] as f32 } else { 1.0 };
-                                    let sy = if size[1] > 0 { viewport.y / size[1] as f32 } else { 1.0 };
+                                    let sx = if shown[0] > 0 { viewport.x / shown[0] as f32 } else { 1.0 };
+                                    let sy = if shown[1] > 0 { viewport.y / shown[1] as f32 } else { 1.0 };
                                     let fit = sx.min(sy);
                                     if fit.is_finite() && fit > 0.0 {
                                         effective_zoom = fit.clamp(0.1, 6.0);
                                     }
                                 }
-                                let desired = egui::vec2(size[0] as f32 * effective_zoom, size[1] as f32 * effective_zoom);
-                                let image = egui::Image::new(texture).fit_to_exact_size(desired);
-                                let resp = ui.add(image);
+                                let desired = egui::vec2(shown[0] as f32 * effective_zoom, shown[1] as f32 * effective_zoom);
+                                // Only grab drags while cropping so the scroll area can still be dragged otherwise
+                                let sense = if self.image_tool == ImageTool::Crop { egui::Sense::click_and_drag() } else { egui::Sense::click() };
+                                let resp = if self.compare.is_some() {
+                                    // Both images are painted below, each on its side of the divider
+                                    ui.allocate_response(desired, sense)
+                                } else {
+                                    ui.add(egui::Image::new(texture).fit_to_exact_size(desired).sense(sense))
+                                };
+                                // The image is centered inside whatever space the layout handed out
+                                let shown_rect = egui::Rect::from_center_size(resp.rect.center(), desired);
+                                let image_rect = egui::Rect::from_min_size(shown_rect.min, egui::vec2(size[0] as f32, size[1] as f32) * effective_zoom);
+                                if let Some(compare) = &self.compare {
+                                    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                                    let split_x = shown_rect.left() + shown_rect.width() * self.compare_split;
+                                    let painter = ui.painter();
+                                    let c = compare.texture.size();
+                                    let compare_rect = egui::Rect::from_min_size(shown_rect.min, egui::vec2(c[0] as f32, c[1] as f32) * effective_zoom);
+                                    painter.with_clip_rect(shown_rect.with_max_x(split_x)).image(texture.id(), image_rect, uv, egui::Color32::WHITE);
+                                    painter.with_clip_rect(shown_rect.with_min_x(split_x)).image(compare.texture.id(), compare_rect, uv, egui::Color32::WHITE);
+                                    if self.compare_diff {
+                                        let d = compare.diff.size();
+                                        let diff_rect = egui::Rect::from_min_size(shown_rect.min, egui::vec2(d[0] as f32, d[1] as f32) * effective_zoom);
+                                        painter.image(compare.diff.id(), diff_rect, uv, egui::Color32::WHITE);
+                                    }
+                                    let handle_rect = egui::Rect::from_center_size(egui::pos2(split_x, shown_rect.center().y), egui::vec2(10.0, shown_rect.height()));
+                                    let handle = ui.interact(handle_rect, ui.id().with("compare_divider"), egui::Sense::drag());
+                                    if handle.dragged()
+                                        && let Some(pos) = handle.interact_pointer_pos()
+                                    {
+                                        self.compare_split = ((pos.x - shown_rect.left()) / shown_rect.width()).clamp(0.0, 1.0);
+                                    }
+                                    if handle.hovered() || handle.dragged() {
+                                        ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+                                    }
+                                    let painter = ui.painter();
+                                    let stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
+                                    painter.line_segment([egui::pos2(split_x, shown_rect.top()), egui::pos2(split_x, shown_rect.bottom())], stroke);
+                                    painter.circle(egui::pos2(split_x, shown_rect.center().y), 7.0, egui::Color32::from_gray(40), stroke);
+                                }
+                                let to_screen = |p: egui::Pos2| image_rect.min + p.to_vec2() * effective_zoom;
+                                let image_bounds = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(size[0] as f32, size[1] as f32));
+                                let to_pixel = |pos: egui::Pos2| ((pos - image_rect.min) / effective_zoom).to_pos2().clamp(image_bounds.min, image_bounds.max);
+                                if self.image_tool == ImageTool::Crop {
+                                    if resp.drag_started()
+                                        && let Some(pos) = resp.interact_pointer_pos()
+                                    {
+                                        self.crop_anchor = Some(to_pixel(pos));
+                                    }
+                                    if resp.dragged()
+                                        && let (Some(anchor), Some(pos)) = (self.crop_anchor, resp.interact_pointer_pos())
+                                    {
+                                        let sel = egui::Rect::from_two_pos(anchor.round(), to_pixel(pos).round());
+                                        self.crop_rect = (sel.width() >= 1.0 && sel.height() >= 1.0).then_some(sel);
+                                    }
+                                    if resp.drag_stopped() { self.crop_anchor = None; }
+                                    if let Some(sel) = self.crop_rect {
+                                        let screen = egui::Rect::from_min_max(to_screen(sel.min), to_screen(sel.max));
+                                        let painter = ui.painter();
+                                        painter.rect_filled(screen, 0.0, egui::Color32::from_rgba_unmultiplied(93, 156, 255, 40));
+                                        painter.rect_stroke(screen, 0.0, egui::Stroke::new(1.5, egui::Color32::from_rgb(93, 156, 255)), egui::StrokeKind::Middle);
+                                    }
+                                }
+                                if self.image_tool == ImageTool::Measure {
+                                    if resp.clicked()
+                                        && let Some(pos) = resp.interact_pointer_pos()
+                                    {
+                                        if self.measure_points.len() != 1 { self.measure_points.clear(); }
+                                        self.measure_points.push(to_pixel(pos));
+                                    }
+                                    let painter = ui.painter();
+                                    let stroke = egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 200, 0));
+                                    for p in &self.measure_points {
+                                        painter.circle_stroke(to_screen(*p), 4.0, stroke);
+                                    }
+                                    if let [a, b] = self.measure_points[..] {
+                                        painter.line_segment([to_screen(a), to_screen(b)], stroke);
+                                        painter.text(
+                                            to_screen(b) + egui::vec2(8.0, -8.0),
+                                            egui::Align2::LEFT_BOTTOM,
+                                            format!("{:.1} px", a.distance(b)),
+                                            egui::FontId::monospace(13.0),
+                                            stroke.color,
+                                        );
+                                    }
+                                }
                                 if resp.hovered() {
                                     let scroll = ui.input(|i| i.raw_scroll_delta.y);
                                     if scroll != 0.0 {
                                         self.image_fit = false;
-                                        let factor = if scroll > 0.0 { 1.10 } else { 1.0 / 1.10 };
+                                        let factor = 1.0 + self.image_zoom_step / 100.0;
+                                        let factor = if scroll > 0.0 { factor } else { 1.0 / factor };
                                         self.image_zoom = (self.image_zoom * factor).clamp(0.1, 6.0);
                                     }
                                 }
@@ -596,6 +4322,21 @@ impl eframe::App for FileViewerApp {
                         });
                     }
                 }
+            } else if let Some(loading) = &self.loading {
+                // Quick loads finish before a spinner would be worth showing
+                if loading.started.elapsed() > std::time::Duration::from_millis(150) {
+                    let name = loading.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    let size = fs::metadata(&loading.path).map(|m| format!(" ({:.1} MB)", m.len() as f64 / (1024.0 * 1024.0))).unwrap_or_default();
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(ui.available_height() * 0.30);
+                        ui.add(egui::Spinner::new().size(32.0));
+                        ui.add_space(8.0);
+                        ui.label(format!("Loading {}{}…", name, size)).on_hover_text(loading.path.to_string_lossy());
+                        if ui.button("Cancel").clicked() { cancel_load = true; }
+                    });
+                } else {
+                    ctx.request_repaint_after(std::time::Duration::from_millis(150));
+                }
             } else if self.error_message.is_none() {
                 ui.vertical_centered(|ui| {
                     use egui::RichText as RT;
@@ -606,37 +4347,452 @@ impl eframe::App for FileViewerApp {
                     ui.add_space(4.0);
                     ui.label("Open a file to get started.");
                     ui.add_space(12.0);
-                    if ui.add(egui::Button::new("📂 Open a file (Ctrl+O)").min_size(egui::vec2(220.0, 36.0))).clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("All Supported", &["txt","rs","py","toml","md","json","js","html","css","png","jpg","jpeg","gif","bmp","webp"])
-                            .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
-                            .add_filter("Text/Source", &["txt","rs","py","toml","md","json","js","html","css"])
-                            .pick_file()
-                        {
-                            file_to_load = Some(path);
+                    if self.resume_session == ResumeSession::Ask
+                        && let Some(session) = &self.last_session
+                    {
+                        let label = match session.files.len() {
+                            1 => "↩ Resume where you left off (1 file)".to_string(),
+                            n => format!("↩ Resume where you left off ({} files)", n),
+                        };
+                        let names: Vec<String> = session.files.iter().map(|f| f.path.to_string_lossy().into_owned()).collect();
+                        if ui.add(egui::Button::new(label).min_size(egui::vec2(220.0, 36.0))).on_hover_text(names.join("\n")).clicked() {
+                            resume = true;
                         }
+                        ui.add_space(6.0);
+                    }
+                    if ui.add(egui::Button::new("📂 Open a file (Ctrl+O)").min_size(egui::vec2(220.0, 36.0))).clicked()
+                        && let Some(path) = crate::io::open_dialog().pick_file()
+                    {
+                        file_to_load = Some(path);
                     }
                 });
             }
         });
 
+        if cancel_load { self.loading = None; }
+        if resume { self.resume_last_session(ctx); }
+        if let Some(column) = sort_column
+            && let Some(table) = &mut self.table
+        {
+            // A second click on the sorted column reverses it
+            let ascending = table.sort != Some((column, true));
+            table.sort_by(column, ascending);
+        }
+        if let Some(line) = text_actions.toggled_bookmark { self.toggle_bookmark(line); }
+        if text_actions.clicked_line.is_some() { self.cursor_line = text_actions.clicked_line; }
+        if text_actions.first_visible.is_some() { self.first_visible_line = text_actions.first_visible; }
+        if let Some(line) = text_actions.toggled_fold && !self.folded.remove(&line) {
+            self.folded.insert(line);
+        }
+        if let Some(reference) = text_actions.copied_line {
+            ctx.copy_text(reference.clone());
+            self.show_toast(format!("Copied {}", reference));
+        }
+
+        // Performance overlay
+        if self.show_perf_overlay {
+            // Keep frames coming so the figures stay live rather than freezing when idle
+            ctx.request_repaint();
+            let dt = ctx.input(|i| i.unstable_dt);
+            self.perf_frame_dt = if self.perf_frame_dt > 0.0 { self.perf_frame_dt * 0.9 + dt * 0.1 } else { dt };
+            let cpu_ms = cpu_usage.map(|s| s * 1000.0);
+            let content_mb = self.approx_content_bytes() as f64 / (1024.0 * 1024.0);
+            let fps = if self.perf_frame_dt > 0.0 { 1.0 / self.perf_frame_dt } else { 0.0 };
+            let frame_ms = self.perf_frame_dt * 1000.0;
+            egui::Area::new("perf_overlay".into())
+                .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-12.0, 64.0))
+                .order(egui::Order::Foreground)
+                .interactable(false)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.monospace(format!("FPS   {:>6.1}", fps));
+                        ui.monospace(format!("Frame {:>6.1} ms", frame_ms));
+                        if let Some(ms) = cpu_ms { ui.monospace(format!("CPU   {:>6.1} ms", ms)); }
+                        ui.monospace(format!("Data  {:>6.1} MB", content_mb));
+                    });
+                });
+        }
+
+        // Brief confirmation toast
+        if let Some((msg, at)) = &self.toast {
+            let shown_for = std::time::Duration::from_millis(1800);
+            if at.elapsed() < shown_for {
+                egui::Area::new("toast".into())
+                    .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -72.0))
+                    .order(egui::Order::Tooltip)
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.label(msg.as_str());
+                        });
+                    });
+                // Wake up once to take it down rather than repainting while it shows
+                ctx.request_repaint_after(shown_for.saturating_sub(at.elapsed()));
+            } else {
+                self.toast = None;
+            }
+        }
+        if text_actions.scrolled_to_line { self.cursor_line = self.scroll_to_line.take(); }
+        self.hex_scroll_to = None;
+        if text_actions.left_end { self.tail_mode = false; }
+        if let Some(selection) = hex_actions.selection { self.hex_selection = Some(selection); }
+        if let Some((copy_format, text)) = hex_actions.copied {
+            ctx.copy_text(text);
+            self.show_toast(format!("Copied selection as {}", copy_format.name()));
+        }
+        if let Some(offset) = text_actions.scroll_offset {
+            self.text_scroll_offset = offset;
+            self.pending_scroll_offset = None;
+        }
+        if let Some(y) = text_actions.minimap_scroll {
+            self.pending_scroll_offset = Some(egui::vec2(self.text_scroll_offset.x, y));
+        }
+        self.apply_side_pane_actions(pane_actions);
+
         // Deferred file loading to avoid borrow issues
         if let Some(path) = file_to_load {
             self.load_file(path, ctx);
+        } else if reopen_closed {
+            self.reopen_closed(ctx);
+        } else if let Some(name) = archive_entry_to_open {
+            self.open_archive_entry(&name, ctx);
+        } else if let Some(text) = paste {
+            self.paste_from_clipboard(Some(text), ctx);
+        }
+    }
+
+    /// A new viewer window starting from this one's settings, with nothing open.
+    fn open_new_window(&mut self) {
+        let Ok(json) = serde_json::to_string(self) else { return };
+        let Ok(window) = serde_json::from_str::<FileViewerApp>(&json) else { return };
+        let mut window = window.restored();
+        window.secondary = true;
+        window.clipboard = arboard::Clipboard::new().ok();
+        self.windows_opened += 1;
+        let id = egui::ViewportId::from_hash_of(("viewer_window", self.windows_opened));
+        self.extra_windows.push((id, window));
+    }
+
+    fn show_extra_windows(&mut self, ctx: &egui::Context) {
+        let mut new_window = std::mem::take(&mut self.new_window_requested);
+        let mut unsupported = false;
+        self.extra_windows.retain_mut(|(id, window)| {
+            let builder = egui::ViewportBuilder::default()
+                .with_inner_size([1000.0, 700.0])
+                .with_title(APP_TITLE);
+            ctx.show_viewport_immediate(*id, builder, |ctx, class| {
+                if class == egui::ViewportClass::Embedded {
+                    unsupported = true;
+                    return false;
+                }
+                window.show_window(ctx, None);
+                new_window |= std::mem::take(&mut window.new_window_requested);
+                !ctx.input(|i| i.viewport().close_requested())
+            })
+        });
+        if unsupported {
+            self.show_toast("This system can't open more windows");
+        } else if new_window {
+            self.open_new_window();
+        }
+    }
+
+    /// Closing the main window while others are open closes the newest of those instead, moving
+    /// its file into the main window at its place. The app exits once the last window closes.
+    fn hand_over_main_window(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|i| i.viewport().close_requested()) {
+            return;
+        }
+        let Some((id, window)) = self.extra_windows.pop() else { return };
+        ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        let (outer, inner) = ctx.input_for(id, |i| (i.viewport().outer_rect, i.viewport().inner_rect));
+        if let Some(outer) = outer { ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(outer.min)); }
+        if let Some(inner) = inner { ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(inner.size())); }
+        // The window keeps its files and view but takes the main window's settings, which are newer
+        // than the ones it was opened with, and its place as the one writing them to disk
+        let mut window = window;
+        window.copy_settings_from(self);
+        window.last_session = self.last_session.take();
+        window.secondary = false;
+        window.extra_windows = std::mem::take(&mut self.extra_windows);
+        window.windows_opened = self.windows_opened;
+        window.settings_dirty.set(self.settings_dirty.get());
+        window.settings_written = self.settings_written;
+        *self = window;
+    }
+
+    /// Copy settings changed in one window to the others, so every window shows them and the main
+    /// window saves them. A change in an extra window goes to the main window first.
+    fn share_settings(&mut self) {
+        let mut windows = std::mem::take(&mut self.extra_windows);
+        for (_, window) in &windows {
+            if window.settings_changed.take() {
+                self.copy_settings_from(window);
+                crate::settings::mark_settings_dirty(self);
+            }
+        }
+        if self.settings_changed.take() {
+            for (_, window) in &mut windows {
+                window.copy_settings_from(self);
+            }
+        }
+        self.extra_windows = windows;
+    }
+
+    /// The settings every window shares, leaving out what each window keeps for itself: its zoom,
+    /// image fit and find query, and the session only the main window records.
+    fn copy_settings_from(&mut self, other: &FileViewerApp) {
+        self.follow_symlinks = other.follow_symlinks;
+        self.show_hidden_files = other.show_hidden_files;
+        self.shared_read = other.shared_read;
+        self.unknown_files = other.unknown_files;
+        self.resume_session = other.resume_session;
+        self.show_file_times = other.show_file_times;
+        self.relative_paths = other.relative_paths;
+        self.path_base = other.path_base.clone();
+        self.dark_mode = other.dark_mode;
+        self.theme = other.theme;
+        self.last_dark_theme = other.last_dark_theme;
+        self.last_light_theme = other.last_light_theme;
+        self.follow_system_theme = other.follow_system_theme;
+        self.low_power = other.low_power;
+        self.slideshow_secs = other.slideshow_secs;
+        self.slideshow_fullscreen = other.slideshow_fullscreen;
+        self.max_file_mb = other.max_file_mb;
+        self.max_text_mb = other.max_text_mb;
+        self.recent_files = other.recent_files.clone();
+        self.pinned_files = other.pinned_files.clone();
+        self.max_recent_files = other.max_recent_files;
+        self.private_mode = other.private_mode;
+        self.bookmarks = other.bookmarks.clone();
+        self.show_line_numbers = other.show_line_numbers;
+        self.show_minimap = other.show_minimap;
+        self.show_outline = other.show_outline;
+        self.proportional_exts = other.proportional_exts.clone();
+        self.word_wrap = other.word_wrap;
+        self.wrap_column = other.wrap_column;
+        self.wrap_long_lines = other.wrap_long_lines;
+        self.wrap_line_threshold = other.wrap_line_threshold;
+        self.auto_format = other.auto_format;
+        self.expand_tabs = other.expand_tabs;
+        self.tab_width = other.tab_width;
+        self.preserve_zoom = other.preserve_zoom;
+        self.default_text_zoom = other.default_text_zoom;
+        self.default_image_zoom = other.default_image_zoom;
+        self.default_image_fit = other.default_image_fit;
+        self.text_zoom_step = other.text_zoom_step;
+        self.image_zoom_step = other.image_zoom_step;
+        self.scroll_speed = other.scroll_speed;
+        self.invert_scroll = other.invert_scroll;
+        self.line_height_scale = other.line_height_scale;
+        self.image_nearest = other.image_nearest;
+        self.accent_rgb = other.accent_rgb;
+        self.spacing_scale = other.spacing_scale;
+        self.theme_rounding = other.theme_rounding;
+        self.key_bindings = other.key_bindings.clone();
+        self.remember_window_positions = other.remember_window_positions;
+        self.window_positions = other.window_positions.clone();
+        self.show_perf_overlay = other.show_perf_overlay;
+        self.split_direction = other.split_direction;
+        self.sync_scroll = other.sync_scroll;
+        self.big_text_threshold = other.big_text_threshold;
+        self.highlight_threshold = other.highlight_threshold;
+        self.show_byte_offsets = other.show_byte_offsets;
+        self.show_control_chars = other.show_control_chars;
+        self.hex_row_bytes = other.hex_row_bytes;
+        self.hex_decimal_offsets = other.hex_decimal_offsets;
+        self.hex_show_ascii = other.hex_show_ascii;
+        self.show_histogram = other.show_histogram;
+        self.show_thumbnails = other.show_thumbnails;
+        self.table_view = other.table_view;
+        self.json_view = other.json_view;
+        self.diff_unified = other.diff_unified;
+        self.remember_search = other.remember_search;
+        self.persistent_highlights = other.persistent_highlights;
+        self.search_regex = other.search_regex;
+        self.search_match_case = other.search_match_case;
+        self.search_whole_word = other.search_whole_word;
+        self.print_line_numbers = other.print_line_numbers;
+        self.print_colors = other.print_colors;
+        self.auto_reload = other.auto_reload;
+    }
+}
+
+/// A Recent entry's preview and the modification time it was made from. Runs off the UI thread.
+fn make_recent_preview(path: &Path, max_bytes: u64, shared: bool) -> (Option<std::time::SystemTime>, MadePreview) {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    let preview = if modified.is_none() {
+        MadePreview::Unavailable("File not found".to_string())
+    } else if crate::io::is_supported_image(path) {
+        match crate::thumbs::make(path, 128, max_bytes, shared) {
+            Ok(image) => MadePreview::Image(image),
+            Err(e) => MadePreview::Unavailable(e),
+        }
+    } else if fs::metadata(path).is_ok_and(|m| m.len() > max_bytes) {
+        MadePreview::Unavailable("Too large to preview".to_string())
+    } else if crate::io::is_supported_text(path) {
+        match crate::io::text_preview(path, shared, 8) {
+            Ok(text) if text.is_empty() => MadePreview::Unavailable("(empty file)".to_string()),
+            Ok(text) => MadePreview::Text(text),
+            Err(e) => MadePreview::Unavailable(e),
+        }
+    } else {
+        MadePreview::Unavailable("No preview for this file type".to_string())
+    };
+    (modified, preview)
+}
+
+type DiffJob = std::sync::mpsc::Receiver<Result<crate::diff::TextDiff, String>>;
+// Where the entry would be on disk, and its decoded contents
+type EntryJob = std::sync::mpsc::Receiver<Result<(PathBuf, FileData), String>>;
+// The second image's path and pixels, with the marked differences and how many pixels differ
+type CompareJob = std::sync::mpsc::Receiver<Result<(PathBuf, image::RgbaImage, egui::ColorImage, usize), String>>;
+
+/// The shown text being read again on a worker thread.
+pub(crate) struct TextReload {
+    rx: std::sync::mpsc::Receiver<Result<Reloaded, String>>,
+    // Re-decoding in another encoding rather than following a change on disk
+    reencode: bool,
+}
+
+enum Reloaded {
+    Whole(crate::io::LoadedText),
+    // Text added to the end of the file since it was read
+    Appended(String),
+    // An indexed file brought up to date, or None when it hadn't changed
+    Index(Option<crate::io::LineIndex>),
+}
+
+/// The end of the shown text, to check that a tailed file only grew.
+struct ShownText {
+    len: usize,
+    tail: Vec<u8>,
+}
+
+// How much of the end of the shown text must still match the file to read only what was appended
+const APPEND_CHECK_BYTES: usize = 4096;
+
+fn reload_text(path: &Path, shared: bool, encoding: Option<&'static encoding_rs::Encoding>, shown: Option<ShownText>) -> Result<Reloaded, String> {
+    if let Some(shown) = shown
+        && let Some(appended) = crate::io::read_appended(path, shared, shown.len, &shown.tail)
+    {
+        return Ok(Reloaded::Appended(appended));
+    }
+    crate::io::load_text(path, shared, encoding).map(Reloaded::Whole)
+}
+
+/// A file being read on a worker thread, plus the view state to apply once it's shown.
+pub(crate) struct Loading {
+    pub(crate) path: PathBuf,
+    pub(crate) started: std::time::Instant,
+    rx: std::sync::mpsc::Receiver<Result<ReadFile, String>>,
+    restore: Option<FileViewState>,
+    // 0-based line to scroll to once shown
+    line: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+struct ReadOptions {
+    open_as: OpenAs,
+    follow_symlinks: bool,
+    unknown_files: UnknownFiles,
+    shared: bool,
+    max_file_bytes: u64,
+    max_text_bytes: u64,
+}
+
+struct ReadFile {
+    // Where the file was read from, i.e. the link target when following symlinks
+    path: PathBuf,
+    link_target: Option<PathBuf>,
+    data: FileData,
+}
+
+enum FileData {
+    Hex(Vec<u8>),
+    Archive(crate::archive::Listing),
+    Image(image::RgbaImage),
+    Animation(Vec<crate::io::Frame>),
+    Pdf(crate::pdf::Page),
+    Text(crate::io::LoadedText),
+    LargeText(crate::io::LineIndex),
+}
+
+/// Everything slow about opening a file: resolving links, reading and decoding. Runs off the UI thread.
+fn read_for_view(path: PathBuf, options: ReadOptions) -> Result<ReadFile, String> {
+    let mut link_target = None;
+    let is_link = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+    let path = if is_link {
+        let target = fs::read_link(&path).unwrap_or_default();
+        match fs::canonicalize(&path) {
+            Ok(resolved) if options.follow_symlinks => resolved,
+            Ok(resolved) => {
+                link_target = Some(resolved);
+                path
+            }
+            Err(e) => {
+                return Err(format!("Broken symbolic link: {} → {} ({})", path.display(), target.display(), e));
+            }
         }
+    } else {
+        path
+    };
+
+    let open_as = options.open_as;
+    let auto = open_as == OpenAs::Auto;
+    let unknown_as_hex = auto && options.unknown_files == UnknownFiles::Hex && !crate::io::is_known_type(&path);
+    let hex = open_as == OpenAs::Hex || unknown_as_hex;
+    let archive = !hex && auto && crate::archive::is_archive(&path);
+    let image = !hex && !archive && (open_as == OpenAs::Image || (auto && crate::io::is_supported_image(&path)));
+    let pdf = !hex && !archive && !image && auto && crate::pdf::is_pdf(&path);
+    let len = fs::metadata(&path).map_or(0, |m| m.len());
+    // Text over the limit is indexed rather than loaded, so text has no limit at all
+    let large_text = !hex && !archive && !image && !pdf && len > options.max_text_bytes.min(crate::io::LARGE_TEXT_BYTES);
+    // PDFium reads only the parts of a PDF it needs, so like text those have no limit
+    let limit = if hex || archive || image { options.max_file_bytes } else { u64::MAX };
+    if len > limit {
+        return Err(format!("File is too large (> {:.1}MB)", limit as f64 / 1_000_000.0));
     }
+
+    let data = if hex {
+        crate::io::read_file(&path, options.shared).map(FileData::Hex).map_err(|e| format!("Failed to read file: {}", e))?
+    } else if archive {
+        crate::archive::list(&path, options.shared).map(FileData::Archive)?
+    } else if image {
+        match crate::io::load_animation(&path, options.shared) {
+            // A GIF of one frame is a still image, already decoded
+            Some(mut frames) if frames.len() == 1 => FileData::Image(frames.remove(0).0),
+            Some(frames) => FileData::Animation(frames),
+            None => crate::io::load_image(&path, options.shared).map(FileData::Image)?,
+        }
+    } else if pdf {
+        crate::pdf::render_page(&path, 0).map(FileData::Pdf)?
+    } else if large_text {
+        crate::io::LineIndex::open(&path, options.shared).map(FileData::LargeText)?
+    } else {
+        crate::io::load_text(&path, options.shared, None).map(FileData::Text)?
+    };
+    Ok(ReadFile { path, link_target, data })
 }
 
 fn try_read(path: &Path) -> Option<Vec<u8>> { std::fs::read(path).ok() }
 
+/// Modification time and length, enough to notice appends and rewrites.
+fn file_stamp(path: &Path) -> Option<(std::time::SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
 fn load_custom_fonts(ctx: &egui::Context) {
     use egui::{FontData, FontDefinitions, FontFamily};
     let mut fonts = FontDefinitions::default();
 
     // Candidate roots: CWD and executable dir
     let mut roots: Vec<std::path::PathBuf> = vec![std::path::PathBuf::from(".")];
-    if let Ok(exe) = std::env::current_exe() {
-        if let Some(dir) = exe.parent() { roots.push(dir.to_path_buf()); }
+    if let Ok(exe) = std::env::current_exe()
+        && let Some(dir) = exe.parent()
+    {
+        roots.push(dir.to_path_buf());
     }
 
     let font_paths = [
@@ -682,3 +4838,8 @@ fn load_custom_fonts(ctx: &egui::Context) {
 fn default_follow_system_true() -> bool { true }
 fn default_spacing_scale() -> f32 { 1.0 }
 fn default_rounding() -> u8 { 6 }
+fn default_max_recent_files() -> usize { 10 }
+fn default_shared_read() -> bool { true }
+fn default_text_zoom_step() -> f32 { 5.0 }
+fn default_image_zoom_step() -> f32 { 10.0 }
+fn default_scroll_speed() -> f32 { 1.0 }
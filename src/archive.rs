@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub(crate) struct ArchiveEntry {
+    /// Full path inside the archive, `/`-separated
+    pub(crate) name: String,
+    pub(crate) size: u64,
+    pub(crate) is_dir: bool,
+    /// Why the entry can't be opened, if it can't
+    pub(crate) note: Option<&'static str>,
+}
+
+/// Entries of an archive plus a directory tree over them for display.
+pub(crate) struct Listing {
+    pub(crate) entries: Vec<ArchiveEntry>,
+    pub(crate) root: Node,
+}
+
+#[derive(Default)]
+pub(crate) struct Node {
+    pub(crate) children: BTreeMap<String, Node>,
+    /// Index into `Listing::entries` when the archive has an explicit entry for this node
+    pub(crate) entry: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn kind(path: &Path) -> Option<Kind> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        Some(Kind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(Kind::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Kind::TarGz)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn is_archive(path: &Path) -> bool {
+    kind(path).is_some()
+}
+
+pub(crate) fn list(path: &Path, shared: bool) -> Result<Listing, String> {
+    let entries = match kind(path) {
+        Some(Kind::Zip) => zip_entries(path, shared)?,
+        Some(Kind::Tar | Kind::TarGz) => tar_entries(path, shared)?,
+        None => return Err("Not a supported archive".to_string()),
+    };
+    let mut root = Node::default();
+    for (i, entry) in entries.iter().enumerate() {
+        let mut node = &mut root;
+        for part in entry.name.split('/').filter(|p| !p.is_empty()) {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        node.entry = Some(i);
+    }
+    Ok(Listing { entries, root })
+}
+
+/// Read one entry into memory, refusing entries larger than `limit` bytes.
+pub(crate) fn read_entry(path: &Path, name: &str, limit: u64, shared: bool) -> Result<Vec<u8>, String> {
+    match kind(path) {
+        Some(Kind::Zip) => read_zip_entry(path, name, limit, shared),
+        Some(Kind::Tar | Kind::TarGz) => read_tar_entry(path, name, limit, shared),
+        None => Err("Not a supported archive".to_string()),
+    }
+}
+
+/// Copy at most `limit` bytes from `reader`; the size in a header can lie.
+fn read_limited(reader: impl Read, name: &str, size_hint: u64, limit: u64) -> Result<Vec<u8>, String> {
+    let too_large = || format!("Entry is too large (> {:.1}MB)", limit as f64 / 1_000_000.0);
+    if size_hint > limit {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::with_capacity(size_hint as usize);
+    reader.take(limit + 1).read_to_end(&mut bytes).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    if bytes.len() as u64 > limit {
+        return Err(too_large());
+    }
+    Ok(bytes)
+}
+
+fn open_zip(path: &Path, shared: bool) -> Result<zip::ZipArchive<File>, String> {
+    let file = crate::io::open_file(path, shared).map_err(|e| format!("Failed to open archive: {}", e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("Failed to read archive: {}", e))
+}
+
+fn zip_entries(path: &Path, shared: bool) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = open_zip(path, shared)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        // Raw access reads the header only, so encrypted entries can still be listed
+        let file = archive.by_index_raw(i).map_err(|e| format!("Failed to read archive: {}", e))?;
+        let note = if file.encrypted() {
+            Some("encrypted")
+        } else if file.is_symlink() {
+            Some("symbolic link")
+        } else if !matches!(file.compression(), zip::CompressionMethod::Stored | zip::CompressionMethod::Deflated) {
+            Some("unsupported compression")
+        } else {
+            None
+        };
+        entries.push(ArchiveEntry { name: file.name().to_string(), size: file.size(), is_dir: file.is_dir(), note });
+    }
+    Ok(entries)
+}
+
+fn read_zip_entry(path: &Path, name: &str, limit: u64, shared: bool) -> Result<Vec<u8>, String> {
+    let mut archive = open_zip(path, shared)?;
+    let file = archive.by_name(name).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let size = file.size();
+    read_limited(file, name, size, limit)
+}
+
+/// Tar archives have no index, so listing and extracting both read through from the start.
+fn open_tar(path: &Path, shared: bool) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let file = crate::io::open_file(path, shared).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let reader: Box<dyn Read> = if kind(path) == Some(Kind::TarGz) {
+        Box::new(flate2::read::GzDecoder::new(std::io::BufReader::new(file)))
+    } else {
+        Box::new(std::io::BufReader::new(file))
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+fn tar_entries(path: &Path, shared: bool) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = open_tar(path, shared)?;
+    let mut entries = Vec::new();
+    for entry in archive.entries().map_err(|e| format!("Failed to read archive: {}", e))? {
+        let entry = entry.map_err(|e| format!("Failed to read archive: {}", e))?;
+        let header = entry.header();
+        let kind = header.entry_type();
+        let note = if kind.is_symlink() || kind.is_hard_link() {
+            Some("link")
+        } else if !(kind.is_file() || kind.is_dir()) {
+            Some("special file")
+        } else {
+            None
+        };
+        let name = entry.path().map_err(|e| format!("Failed to read archive: {}", e))?.to_string_lossy().replace('\\', "/");
+        entries.push(ArchiveEntry { name, size: entry.size(), is_dir: kind.is_dir(), note });
+    }
+    Ok(entries)
+}
+
+fn read_tar_entry(path: &Path, name: &str, limit: u64, shared: bool) -> Result<Vec<u8>, String> {
+    let mut archive = open_tar(path, shared)?;
+    for entry in archive.entries().map_err(|e| format!("Failed to read archive: {}", e))? {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        if entry.path().is_ok_and(|p| p.to_string_lossy().replace('\\', "/") == name) {
+            let size = entry.size();
+            return read_limited(entry, name, size, limit);
+        }
+    }
+    Err(format!("Failed to read {}: not in the archive", name))
+}
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Change {
+    Same,
+    Removed,
+    Added,
+    // A line replaced by another at the same place
+    Changed,
+}
+
+/// One row of the diff: a line of the current file (left), of the compared file (right), or both.
+#[derive(Clone, Copy)]
+pub(crate) struct Row {
+    pub(crate) change: Change,
+    pub(crate) left: Option<usize>,
+    pub(crate) right: Option<usize>,
+}
+
+/// The current text compared line by line with a second file.
+pub(crate) struct TextDiff {
+    pub(crate) path: PathBuf,
+    pub(crate) left: Vec<String>,
+    pub(crate) right: Vec<String>,
+    /// Side-by-side rows; changed lines share a row
+    pub(crate) rows: Vec<Row>,
+    /// Unified rows; a changed line becomes a removed row followed by an added one
+    pub(crate) unified: Vec<Row>,
+    pub(crate) added: usize,
+    pub(crate) removed: usize,
+    pub(crate) changed: usize,
+    /// Longest line of either side, in characters
+    pub(crate) widest: usize,
+    /// The compared file's text, to diff again when the current text is reloaded
+    pub(crate) right_text: String,
+}
+
+impl TextDiff {
+    pub(crate) fn new(path: PathBuf, left_text: &str, right_text: String) -> TextDiff {
+        let left: Vec<String> = left_text.lines().map(str::to_string).collect();
+        let right: Vec<String> = right_text.lines().map(str::to_string).collect();
+        let mut rows = Vec::new();
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        for op in similar::capture_diff_slices(similar::Algorithm::Myers, &left, &right) {
+            let (tag, old, new) = op.as_tag_tuple();
+            match tag {
+                similar::DiffTag::Equal => {
+                    rows.extend(old.zip(new).map(|(l, r)| Row { change: Change::Same, left: Some(l), right: Some(r) }));
+                }
+                similar::DiffTag::Delete => {
+                    removed += old.len();
+                    rows.extend(old.map(|l| Row { change: Change::Removed, left: Some(l), right: None }));
+                }
+                similar::DiffTag::Insert => {
+                    added += new.len();
+                    rows.extend(new.map(|r| Row { change: Change::Added, left: None, right: Some(r) }));
+                }
+                similar::DiffTag::Replace => {
+                    // Pair lines up as changed; whatever is left over on one side was added or removed
+                    let pairs = old.len().min(new.len());
+                    changed += pairs;
+                    removed += old.len() - pairs;
+                    added += new.len() - pairs;
+                    let mut new = new;
+                    for l in old {
+                        let r = new.next();
+                        let change = if r.is_some() { Change::Changed } else { Change::Removed };
+                        rows.push(Row { change, left: Some(l), right: r });
+                    }
+                    rows.extend(new.map(|r| Row { change: Change::Added, left: None, right: Some(r) }));
+                }
+            }
+        }
+        let mut unified = Vec::with_capacity(rows.len());
+        for row in &rows {
+            if row.change == Change::Changed {
+                unified.push(Row { change: Change::Removed, left: row.left, right: None });
+                unified.push(Row { change: Change::Added, left: None, right: row.right });
+            } else {
+                unified.push(*row);
+            }
+        }
+        let widest = left.iter().chain(&right).map(|l| l.chars().count()).max().unwrap_or(0);
+        TextDiff { path, left, right, rows, unified, added, removed, changed, widest, right_text }
+    }
+}
@@ -0,0 +1,205 @@
+/// Pretty-printed `text` for structured formats the viewer can reformat, or None for anything else.
+/// Invalid content yields an error describing the first problem.
+pub(crate) fn pretty(text: &str, ext: &str) -> Option<Result<String, String>> {
+    match ext {
+        "json" => Some(json(text)),
+        "toml" => Some(toml(text)),
+        "xml" => Some(xml(text)),
+        _ => None,
+    }
+}
+
+/// Re-indents two spaces per level, the way `serde_json` pretty-prints. Strings, numbers and key
+/// order are kept exactly as written rather than going through a parsed value.
+fn json(text: &str) -> Result<String, String> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text).map_err(|e| format!("Not valid JSON: {}", e))?;
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut depth = 0usize;
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+    let mut chars = text.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                // Empty objects and arrays stay on one line
+                if let Some(close) = chars.next_if(|&next| next == if c == '{' { '}' } else { ']' }) {
+                    out.push(close);
+                } else {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+fn toml(text: &str) -> Result<String, String> {
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| format!("Not valid TOML: {}", e.message()))?;
+    toml::to_string_pretty(&table).map_err(|e| e.to_string())
+}
+
+enum XmlToken<'a> {
+    Open(&'a str, &'a str),
+    Close(&'a str, &'a str),
+    // Self-closing tags, comments, CDATA, declarations and processing instructions
+    Other(&'a str),
+    Text(&'a str),
+}
+
+/// Re-indents elements two spaces per level. Elements holding only text stay on one line;
+/// text is trimmed but otherwise kept verbatim.
+fn xml(text: &str) -> Result<String, String> {
+    let tokens = xml_tokens(text)?;
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut stack: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let indent = "  ".repeat(stack.len());
+        match tokens[i] {
+            XmlToken::Open(tag, name) => {
+                // `<a>text</a>` stays together
+                if let (Some(XmlToken::Text(body)), Some(XmlToken::Close(close, close_name))) = (tokens.get(i + 1), tokens.get(i + 2))
+                    && *close_name == name
+                {
+                    out.push_str(&format!("{}{}{}{}\n", indent, tag, body, close));
+                    i += 3;
+                    continue;
+                }
+                out.push_str(&format!("{}{}\n", indent, tag));
+                stack.push(name);
+            }
+            XmlToken::Close(tag, name) => {
+                match stack.pop() {
+                    Some(open) if open == name => {}
+                    Some(open) => return Err(format!("Not valid XML: </{}> closes <{}>", name, open)),
+                    None => return Err(format!("Not valid XML: unexpected </{}>", name)),
+                }
+                out.push_str(&format!("{}{}\n", "  ".repeat(stack.len()), tag));
+            }
+            XmlToken::Other(tag) | XmlToken::Text(tag) => out.push_str(&format!("{}{}\n", indent, tag)),
+        }
+        i += 1;
+    }
+    if let Some(open) = stack.pop() {
+        return Err(format!("Not valid XML: <{}> is never closed", open));
+    }
+    Ok(out)
+}
+
+/// Foldable elements as start line -> end line, for elements whose open and close tags are on
+/// different lines. Unclosed tags (as in HTML's `<br>` or `<p>`) are skipped rather than rejected.
+pub(crate) fn xml_folds(text: &str) -> std::collections::BTreeMap<usize, usize> {
+    let mut folds = std::collections::BTreeMap::new();
+    let Ok(tokens) = xml_tokens(text) else { return folds };
+    let line_starts: Vec<usize> = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let line_of = |s: &str| {
+        let offset = s.as_ptr() as usize - text.as_ptr() as usize;
+        line_starts.partition_point(|&start| start <= offset) - 1
+    };
+    let mut stack: Vec<(&str, usize)> = Vec::new();
+    for token in &tokens {
+        match *token {
+            XmlToken::Open(tag, name) => stack.push((name, line_of(tag))),
+            XmlToken::Close(tag, name) => {
+                let Some(pos) = stack.iter().rposition(|(open, _)| open.eq_ignore_ascii_case(name)) else { continue };
+                let (_, start) = stack[pos];
+                stack.truncate(pos);
+                let end = line_of(tag);
+                // Several elements may open on one line; the outermost decides the fold
+                if end > start {
+                    let fold_end = folds.entry(start).or_insert(end);
+                    *fold_end = (*fold_end).max(end);
+                }
+            }
+            _ => {}
+        }
+    }
+    folds
+}
+
+fn xml_tokens(text: &str) -> Result<Vec<XmlToken<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            push_text(&mut tokens, rest);
+            break;
+        };
+        push_text(&mut tokens, &rest[..start]);
+        rest = &rest[start..];
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|e| e + 3)
+        } else if rest.starts_with("<![CDATA[") {
+            rest.find("]]>").map(|e| e + 3)
+        } else {
+            tag_end(rest)
+        };
+        let Some(end) = end else {
+            return Err("Not valid XML: unterminated tag".to_string());
+        };
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        tokens.push(if tag.starts_with("<!") || tag.starts_with("<?") || tag.ends_with("/>") {
+            XmlToken::Other(tag)
+        } else if let Some(name) = tag.strip_prefix("</") {
+            XmlToken::Close(tag, tag_name(name))
+        } else {
+            XmlToken::Open(tag, tag_name(&tag[1..]))
+        });
+    }
+    Ok(tokens)
+}
+
+fn tag_name(s: &str) -> &str {
+    let end = s.find(|c: char| c.is_whitespace() || c == '/' || c == '>').unwrap_or(s.len());
+    &s[..end]
+}
+
+/// End of a tag starting at `s[0] == '<'`, skipping `>` inside quoted attribute values.
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn push_text<'a>(tokens: &mut Vec<XmlToken<'a>>, text: &'a str) {
+    let trimmed = text.trim();
+    if !trimmed.is_empty() {
+        tokens.push(XmlToken::Text(trimmed));
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt::Write;
+
+/// Formats for copying a range of bytes out of the hex view.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CopyFormat {
+    Hex,
+    CArray,
+    Base64,
+}
+
+impl CopyFormat {
+    pub(crate) const ALL: [CopyFormat; 3] = [CopyFormat::Hex, CopyFormat::CArray, CopyFormat::Base64];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            CopyFormat::Hex => "Hex",
+            CopyFormat::CArray => "C array",
+            CopyFormat::Base64 => "Base64",
+        }
+    }
+
+    pub(crate) fn format(self, bytes: &[u8]) -> String {
+        match self {
+            CopyFormat::Hex => to_hex(bytes),
+            CopyFormat::CArray => to_c_array(bytes),
+            CopyFormat::Base64 => to_base64(bytes),
+        }
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(out, "{:02x}", b);
+    }
+    out
+}
+
+/// `{ 0x.., ... }` with twelve bytes per line, ready to paste into C or Rust.
+pub(crate) fn to_c_array(bytes: &[u8]) -> String {
+    let mut out = String::from("{");
+    for (i, chunk) in bytes.chunks(12).enumerate() {
+        out.push_str(if i == 0 { "\n    " } else { ",\n    " });
+        let items: Vec<String> = chunk.iter().map(|b| format!("0x{:02x}", b)).collect();
+        out.push_str(&items.join(", "));
+    }
+    out.push_str("\n}");
+    out
+}
+
+/// Standard padded base64 (RFC 4648).
+pub(crate) fn to_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
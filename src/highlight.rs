@@ -1,54 +1,135 @@
 use eframe::egui::{self, text::LayoutJob, Color32, FontId};
 
+use crate::app::Theme;
+use crate::search::Pattern;
+use crate::syntax::LineSpans;
+
+/// Token colors used by the syntax highlighter, chosen per theme.
+pub(crate) struct Palette {
+    pub(crate) keyword: Color32,
+    pub(crate) string: Color32,
+    pub(crate) comment: Color32,
+    pub(crate) number: Color32,
+    /// true/false/null/none
+    pub(crate) literal: Color32,
+    /// Cycled by nesting depth
+    pub(crate) brackets: [Color32; 5],
+}
+
+impl Palette {
+    pub(crate) fn for_theme(theme: Theme) -> Self {
+        let rgb = Color32::from_rgb;
+        match theme {
+            Theme::Dark | Theme::Allison => Palette {
+                keyword: rgb(97, 175, 239),
+                string: rgb(152, 195, 121),
+                comment: Color32::GRAY,
+                number: rgb(209, 154, 102),
+                literal: rgb(198, 120, 221),
+                brackets: [rgb(152, 195, 121), rgb(224, 108, 117), rgb(97, 175, 239), rgb(229, 192, 123), rgb(86, 182, 194)],
+            },
+            Theme::Light => Palette {
+                keyword: rgb(0, 92, 197),
+                string: rgb(3, 106, 7),
+                comment: rgb(106, 115, 125),
+                number: rgb(152, 104, 1),
+                literal: rgb(111, 66, 193),
+                brackets: [rgb(3, 106, 7), rgb(215, 58, 73), rgb(0, 92, 197), rgb(176, 136, 0), rgb(0, 134, 148)],
+            },
+            Theme::SolarizedLight | Theme::SolarizedDark => Palette {
+                keyword: rgb(38, 139, 210),
+                string: rgb(42, 161, 152),
+                comment: if theme == Theme::SolarizedDark { rgb(88, 110, 117) } else { rgb(147, 161, 161) },
+                number: rgb(211, 54, 130),
+                literal: rgb(108, 113, 196),
+                brackets: [rgb(181, 137, 0), rgb(203, 75, 22), rgb(220, 50, 47), rgb(211, 54, 130), rgb(38, 139, 210)],
+            },
+            Theme::Dracula => Palette {
+                keyword: rgb(255, 121, 198),
+                string: rgb(241, 250, 140),
+                comment: rgb(98, 114, 164),
+                number: rgb(189, 147, 249),
+                literal: rgb(189, 147, 249),
+                brackets: [rgb(139, 233, 253), rgb(80, 250, 123), rgb(255, 184, 108), rgb(255, 121, 198), rgb(189, 147, 249)],
+            },
+            Theme::GruvboxDark => Palette {
+                keyword: rgb(251, 73, 52),
+                string: rgb(184, 187, 38),
+                comment: rgb(146, 131, 116),
+                number: rgb(211, 134, 155),
+                literal: rgb(211, 134, 155),
+                brackets: [rgb(250, 189, 47), rgb(131, 165, 152), rgb(142, 192, 124), rgb(254, 128, 25), rgb(211, 134, 155)],
+            },
+            Theme::Sepia => Palette {
+                keyword: rgb(136, 57, 40),
+                string: rgb(80, 110, 40),
+                comment: rgb(150, 130, 110),
+                number: rgb(160, 90, 20),
+                literal: rgb(120, 70, 140),
+                brackets: [rgb(136, 57, 40), rgb(80, 110, 40), rgb(160, 90, 20), rgb(120, 70, 140), rgb(50, 100, 120)],
+            },
+        }
+    }
+
+    /// A syntax color from this palette as the same kind of token is colored in `other`.
+    pub(crate) fn translate(&self, color: Color32, other: &Palette) -> Color32 {
+        [
+            (self.keyword, other.keyword),
+            (self.string, other.string),
+            (self.comment, other.comment),
+            (self.number, other.number),
+            (self.literal, other.literal),
+        ]
+        .into_iter()
+        .find(|(from, _)| *from == color)
+        .map_or(color, |(_, to)| to)
+    }
+}
+
 pub(crate) fn append_with_search(
     job: &mut LayoutJob,
     text: &str,
     font_id: FontId,
     color: Color32,
-    query: &str,
+    query: Option<&Pattern>,
     current_idx: usize,
     counter: &mut usize,
 ) {
-    if query.is_empty() {
+    let Some(pattern) = query else {
         job.append(text, 0.0, egui::TextFormat { font_id, color, ..Default::default() });
         return;
-    }
-    let lc_query = query.to_ascii_lowercase();
-    let mut rest = text;
-    loop {
-        let lc_rest = rest.to_ascii_lowercase();
-        if let Some(found_rel) = lc_rest.find(&lc_query) {
-            let prefix = &rest[..found_rel];
-            if !prefix.is_empty() {
-                job.append(prefix, 0.0, egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() });
-            }
-            let matched = &rest[found_rel..found_rel + lc_query.len()];
-            let mut fmt = egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() };
-            if *counter == current_idx {
-                fmt.background = Color32::from_rgba_premultiplied(224, 108, 117, 96);
-            } else {
-                fmt.background = Color32::from_rgba_premultiplied(255, 255, 0, 64);
-            }
-            job.append(matched, 0.0, fmt);
-            *counter += 1;
-            rest = &rest[found_rel + lc_query.len()..];
-            if rest.is_empty() { break; }
+    };
+    let mut last = 0;
+    for found in pattern.matches(text) {
+        if found.start > last {
+            job.append(&text[last..found.start], 0.0, egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() });
+        }
+        let mut fmt = egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() };
+        if *counter == current_idx {
+            // Active match: stronger fill plus underline so it stands out among the others
+            fmt.background = Color32::from_rgba_premultiplied(224, 108, 117, 160);
+            fmt.underline = egui::Stroke::new(1.5, Color32::from_rgb(224, 108, 117));
         } else {
-            if !rest.is_empty() {
-                job.append(rest, 0.0, egui::TextFormat { font_id, color, ..Default::default() });
-            }
-            break;
+            fmt.background = Color32::from_rgba_premultiplied(255, 255, 0, 64);
         }
+        job.append(&text[found.clone()], 0.0, fmt);
+        *counter += 1;
+        last = found.end;
+    }
+    if last < text.len() {
+        job.append(&text[last..], 0.0, egui::TextFormat { font_id, color, ..Default::default() });
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn token_highlight(
     job: &mut LayoutJob,
     text: &str,
     ext: &str,
     font_id: FontId,
     base_color: Color32,
-    query: &str,
+    palette: &Palette,
+    query: Option<&Pattern>,
     do_syntax: bool,
     depth: &mut i32,
     current_idx: usize,
@@ -58,16 +139,10 @@ pub(crate) fn token_highlight(
         append_with_search(job, text, font_id, base_color, query, current_idx, counter);
         return;
     }
-    let kw_color = Color32::from_rgb(97, 175, 239);
-    let num_color = Color32::from_rgb(209, 154, 102);
-    let bool_color = Color32::from_rgb(198, 120, 221);
-    let bracket_colors = [
-        Color32::from_rgb(152, 195, 121),
-        Color32::from_rgb(224, 108, 117),
-        Color32::from_rgb(97, 175, 239),
-        Color32::from_rgb(229, 192, 123),
-        Color32::from_rgb(86, 182, 194),
-    ];
+    let kw_color = palette.keyword;
+    let num_color = palette.number;
+    let bool_color = palette.literal;
+    let bracket_colors = palette.brackets;
 
     let keywords_rs: &[&str] = &[
         "as","async","await","break","const","continue","crate","dyn","else","enum","extern","false","fn","for","if","impl","in","let","loop","match","mod","move","mut","pub","ref","return","self","Self","static","struct","super","trait","true","type","unsafe","use","where","while",
@@ -84,9 +159,9 @@ pub(crate) fn token_highlight(
         } else {
             if !buf.is_empty() {
                 let lc = buf.to_ascii_lowercase();
-                let (color, _) = if ext == "rs" && keywords_rs.contains(&buf.as_str()) {
-                    (kw_color, true)
-                } else if ext == "py" && keywords_py.contains(&buf.as_str()) {
+                let (color, _) = if (ext == "rs" && keywords_rs.contains(&buf.as_str()))
+                    || (ext == "py" && keywords_py.contains(&buf.as_str()))
+                {
                     (kw_color, true)
                 } else if lc == "true" || lc == "false" || lc == "null" || lc == "none" {
                     (bool_color, true)
@@ -117,9 +192,9 @@ pub(crate) fn token_highlight(
     }
     if !buf.is_empty() {
         let lc = buf.to_ascii_lowercase();
-        let (color, _) = if ext == "rs" && keywords_rs.contains(&buf.as_str()) {
-            (kw_color, true)
-        } else if ext == "py" && keywords_py.contains(&buf.as_str()) {
+        let (color, _) = if (ext == "rs" && keywords_rs.contains(&buf.as_str()))
+            || (ext == "py" && keywords_py.contains(&buf.as_str()))
+        {
             (kw_color, true)
         } else if lc == "true" || lc == "false" || lc == "null" || lc == "none" {
             (bool_color, true)
@@ -132,13 +207,15 @@ pub(crate) fn token_highlight(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn append_highlighted(
     job: &mut LayoutJob,
     line: &str,
     ext: &str,
-    query: &str,
+    query: Option<&Pattern>,
     font_id: FontId,
     base_color: Color32,
+    palette: &Palette,
     do_syntax: bool,
     depth: &mut i32,
     current_idx: usize,
@@ -146,17 +223,21 @@ pub(crate) fn append_highlighted(
     in_block_comment: &mut bool,
 ) {
     if do_syntax {
+        if matches!(ext, "xml" | "html" | "htm") {
+            xml_highlight(job, line, query, font_id, base_color, palette, current_idx, counter, in_block_comment);
+            return;
+        }
         if ext == "rs" {
             let mut i = 0usize;
             if *in_block_comment {
                 if let Some(end) = line[i..].find("*/") {
                     let end_abs = i + end + 2;
-                    let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                    let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                     job.append(&line[i..end_abs], 0.0, fmt);
                     *in_block_comment = false;
                     i = end_abs;
                 } else {
-                    let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                    let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                     job.append(&line[i..], 0.0, fmt);
                     return;
                 }
@@ -168,57 +249,57 @@ pub(crate) fn append_highlighted(
                 match (pos_sl, pos_blk) {
                     (Some(psl), Some(pblk)) if psl < pblk => {
                         if psl > 0 {
-                            token_highlight(job, &rest[..psl], ext, font_id.clone(), base_color, query, do_syntax, depth, current_idx, counter);
+                            token_highlight(job, &rest[..psl], ext, font_id.clone(), base_color, palette, query, do_syntax, depth, current_idx, counter);
                         }
-                        let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                        let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                         job.append(&rest[psl..], 0.0, fmt);
                         return;
                     }
                     (Some(psl), None) => {
                         if psl > 0 {
-                            token_highlight(job, &rest[..psl], ext, font_id.clone(), base_color, query, do_syntax, depth, current_idx, counter);
+                            token_highlight(job, &rest[..psl], ext, font_id.clone(), base_color, palette, query, do_syntax, depth, current_idx, counter);
                         }
-                        let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                        let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                         job.append(&rest[psl..], 0.0, fmt);
                         return;
                     }
                     (None, Some(pblk)) => {
                         if pblk > 0 {
-                            token_highlight(job, &rest[..pblk], ext, font_id.clone(), base_color, query, do_syntax, depth, current_idx, counter);
+                            token_highlight(job, &rest[..pblk], ext, font_id.clone(), base_color, palette, query, do_syntax, depth, current_idx, counter);
                         }
                         let after = pblk + 2;
                         let tail = &rest[after..];
                         if let Some(end) = tail.find("*/") {
                             let end_abs = i + after + end + 2;
-                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                             job.append(&line[i + pblk..end_abs], 0.0, fmt);
                             i = end_abs;
                             continue;
                         } else {
-                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                             job.append(&rest[pblk..], 0.0, fmt);
                             *in_block_comment = true;
                             return;
                         }
                     }
                     (None, None) => {
-                        token_highlight(job, rest, ext, font_id.clone(), base_color, query, do_syntax, depth, current_idx, counter);
+                        token_highlight(job, rest, ext, font_id.clone(), base_color, palette, query, do_syntax, depth, current_idx, counter);
                         return;
                     }
                     (Some(_psl), Some(pblk)) => {
                         if pblk > 0 {
-                            token_highlight(job, &rest[..pblk], ext, font_id.clone(), base_color, query, do_syntax, depth, current_idx, counter);
+                            token_highlight(job, &rest[..pblk], ext, font_id.clone(), base_color, palette, query, do_syntax, depth, current_idx, counter);
                         }
                         let after = pblk + 2;
                         let tail = &rest[after..];
                         if let Some(end) = tail.find("*/") {
                             let end_abs = i + after + end + 2;
-                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                             job.append(&line[i + pblk..end_abs], 0.0, fmt);
                             i = end_abs;
                             continue;
                         } else {
-                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+                            let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
                             job.append(&rest[pblk..], 0.0, fmt);
                             *in_block_comment = true;
                             return;
@@ -230,13 +311,13 @@ pub(crate) fn append_highlighted(
         }
         let comment_prefix = if ext == "rs" || ext == "js" { "//" } else if ext == "toml" { "#" } else { "" };
         let comment_prefix = if ext == "py" { "#" } else { comment_prefix };
-        if !comment_prefix.is_empty() {
-            if let Some(pos) = line.find(comment_prefix) {
-                append_highlighted(job, &line[..pos], "", query, font_id.clone(), base_color, do_syntax, depth, current_idx, counter, in_block_comment);
-                let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
-                job.append(&line[pos..], 0.0, fmt);
-                return;
-            }
+        if !comment_prefix.is_empty()
+            && let Some(pos) = line.find(comment_prefix)
+        {
+            append_highlighted(job, &line[..pos], "", query, font_id.clone(), base_color, palette, do_syntax, depth, current_idx, counter, in_block_comment);
+            let fmt = egui::TextFormat { font_id: font_id.clone(), color: palette.comment, ..Default::default() };
+            job.append(&line[pos..], 0.0, fmt);
+            return;
         }
     }
 
@@ -246,14 +327,14 @@ pub(crate) fn append_highlighted(
         let mut chars = line.chars().peekable();
         while let Some(ch) = chars.next() {
             if ch == '"' {
-                if !buf.is_empty() { token_highlight(job, &buf, ext, font_id.clone(), base_color, query, do_syntax, depth, current_idx, counter); buf.clear(); }
+                if !buf.is_empty() { token_highlight(job, &buf, ext, font_id.clone(), base_color, palette, query, do_syntax, depth, current_idx, counter); buf.clear(); }
                 buf.clear();
                 let mut s = String::from('"');
-                while let Some(c2) = chars.next() {
+                for c2 in chars.by_ref() {
                     s.push(c2);
                     if c2 == '"' { break; }
                 }
-                append_with_search(job, &s, font_id.clone(), Color32::from_rgb(152, 195, 121), query, current_idx, counter);
+                append_with_search(job, &s, font_id.clone(), palette.string, query, current_idx, counter);
             } else {
                 buf.push(ch);
             }
@@ -263,6 +344,181 @@ pub(crate) fn append_highlighted(
     }
 
     if !buf.is_empty() {
-        token_highlight(job, &buf, ext, font_id, base_color, query, do_syntax, depth, current_idx, counter);
+        token_highlight(job, &buf, ext, font_id, base_color, palette, query, do_syntax, depth, current_idx, counter);
+    }
+}
+
+/// The colors `append_highlighted` gives `line` on top of the output's `text_color`, as spans over
+/// its bytes, for output other than the screen. Plain text has no span.
+pub(crate) fn line_spans(line: &str, ext: &str, palette: &Palette, text_color: Color32, depth: &mut i32, in_block_comment: &mut bool) -> LineSpans {
+    let mut job = LayoutJob::default();
+    append_highlighted(&mut job, line, ext, None, FontId::default(), text_color, palette, true, depth, usize::MAX, &mut 0, in_block_comment);
+    job.sections
+        .into_iter()
+        .filter(|section| section.format.color != text_color)
+        .map(|section| (section.byte_range, section.format.color))
+        .collect()
+}
+
+/// Markup coloring: tag names, attribute names and values, and `<!-- -->` comments, which may span lines.
+/// A line that opens with `name=` is treated as continuing a tag's attribute list.
+#[allow(clippy::too_many_arguments)]
+fn xml_highlight(
+    job: &mut LayoutJob,
+    line: &str,
+    query: Option<&Pattern>,
+    font_id: FontId,
+    base_color: Color32,
+    palette: &Palette,
+    current_idx: usize,
+    counter: &mut usize,
+    in_comment: &mut bool,
+) {
+    #[derive(PartialEq)]
+    enum State { Text, TagName, Attrs }
+    let mut push = |job: &mut LayoutJob, s: &str, color: Color32| {
+        if !s.is_empty() {
+            append_with_search(job, s, font_id.clone(), color, query, current_idx, counter);
+        }
+    };
+    let trimmed = line.trim_start();
+    let continues_tag = trimmed
+        .split_once('=')
+        .is_some_and(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.')));
+    let mut state = if continues_tag { State::Attrs } else { State::Text };
+    let mut rest = line;
+    while !rest.is_empty() {
+        if *in_comment {
+            let end = rest.find("-->").map_or(rest.len(), |e| e + 3);
+            push(job, &rest[..end], palette.comment);
+            *in_comment = end == rest.len() && !rest.ends_with("-->");
+            rest = &rest[end..];
+            continue;
+        }
+        match state {
+            State::Text => {
+                if rest.starts_with("<!--") {
+                    *in_comment = true;
+                    continue;
+                }
+                let end = rest.find('<').unwrap_or(rest.len());
+                if end > 0 {
+                    push(job, &rest[..end], base_color);
+                    rest = &rest[end..];
+                    continue;
+                }
+                // `<`, `</`, `<?` or `<!` opens a tag
+                let open = if rest[1..].starts_with(['/', '?', '!']) { 2 } else { 1 };
+                let open = open.min(rest.len());
+                push(job, &rest[..open], palette.keyword);
+                rest = &rest[open..];
+                state = State::TagName;
+            }
+            State::TagName => {
+                let end = rest.find(|c: char| c.is_whitespace() || matches!(c, '>' | '/')).unwrap_or(rest.len());
+                push(job, &rest[..end], palette.keyword);
+                rest = &rest[end..];
+                state = State::Attrs;
+            }
+            State::Attrs => {
+                let c = rest.chars().next().unwrap_or(' ');
+                if c == '"' || c == '\'' {
+                    let end = rest[1..].find(c).map_or(rest.len(), |e| e + 2);
+                    push(job, &rest[..end], palette.string);
+                    rest = &rest[end..];
+                } else if rest.starts_with("/>") || rest.starts_with("?>") {
+                    push(job, &rest[..2], palette.keyword);
+                    rest = &rest[2..];
+                    state = State::Text;
+                } else if c == '>' {
+                    push(job, ">", palette.keyword);
+                    rest = &rest[1..];
+                    state = State::Text;
+                } else if c.is_whitespace() || c == '=' {
+                    let end = rest.find(|c: char| !(c.is_whitespace() || c == '=')).unwrap_or(rest.len());
+                    push(job, &rest[..end], base_color);
+                    rest = &rest[end..];
+                } else {
+                    let end = rest
+                        .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '"' | '\'') || (c == '/' || c == '?'))
+                        .unwrap_or(rest.len())
+                        .max(c.len_utf8());
+                    push(job, &rest[..end], palette.literal);
+                    rest = &rest[end..];
+                }
+            }
+        }
+    }
+}
+
+fn is_invalid_marker(ch: char, offset: usize, invalid_offsets: &[usize]) -> bool {
+    ch == '\u{FFFD}' && invalid_offsets.binary_search(&offset).is_ok()
+}
+
+/// Replace tabs with spaces up to the next multiple of `width` columns, counted from the start of
+/// the line's content so the gutter never shifts the stops.
+pub(crate) fn expand_tabs(line: &str, width: usize) -> std::borrow::Cow<'_, str> {
+    if !line.contains('\t') {
+        return std::borrow::Cow::Borrowed(line);
+    }
+    let mut out = String::with_capacity(line.len() + width * 4);
+    let mut col = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let pad = width - col % width;
+            out.extend(std::iter::repeat_n(' ', pad));
+            col += pad;
+        } else {
+            out.push(c);
+            col += 1;
+        }
+    }
+    std::borrow::Cow::Owned(out)
+}
+
+pub(crate) fn needs_control_markers(line: &str, line_start: usize, invalid_offsets: &[usize]) -> bool {
+    line.char_indices().any(|(i, ch)| ch.is_ascii_control() || is_invalid_marker(ch, line_start + i, invalid_offsets))
+}
+
+/// Appends `line` with ASCII control characters in caret notation (`^I`, `^M`) and
+/// replacement characters that stand for undecodable input marked in red.
+pub(crate) fn append_control_visible(
+    job: &mut LayoutJob,
+    line: &str,
+    line_start: usize,
+    invalid_offsets: &[usize],
+    crlf: bool,
+    font_id: FontId,
+    base_color: Color32,
+) {
+    let plain = egui::TextFormat { font_id: font_id.clone(), color: base_color, ..Default::default() };
+    let caret = egui::TextFormat {
+        font_id: font_id.clone(),
+        color: Color32::GRAY,
+        background: Color32::from_rgba_unmultiplied(128, 128, 128, 48),
+        ..Default::default()
+    };
+    let invalid = egui::TextFormat {
+        font_id,
+        color: Color32::WHITE,
+        background: Color32::from_rgb(200, 40, 40),
+        ..Default::default()
+    };
+    let mut run_start = 0usize;
+    for (i, ch) in line.char_indices() {
+        let marker = if ch.is_ascii_control() {
+            Some((format!("^{}", ((ch as u8) ^ 0x40) as char), &caret))
+        } else if is_invalid_marker(ch, line_start + i, invalid_offsets) {
+            Some(("\u{FFFD}".to_string(), &invalid))
+        } else {
+            None
+        };
+        if let Some((shown, fmt)) = marker {
+            if run_start < i { job.append(&line[run_start..i], 0.0, plain.clone()); }
+            job.append(&shown, 0.0, fmt.clone());
+            run_start = i + ch.len_utf8();
+        }
     }
+    if run_start < line.len() { job.append(&line[run_start..], 0.0, plain); }
+    if crlf { job.append("^M", 0.0, caret); }
 }
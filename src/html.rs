@@ -0,0 +1,114 @@
+use std::fmt::Write;
+
+use eframe::egui::Color32;
+
+use crate::syntax::LineSpans;
+
+const PRINT_STYLE: &str = "
+@page { margin: 15mm; }
+body { margin: 0; color: #000; background: #fff; }
+h1 { font: bold 10pt sans-serif; margin: 0 0 6pt; padding-bottom: 3pt; border-bottom: 1px solid #999; }
+table { border-collapse: collapse; font: 9pt monospace; }
+tr { break-inside: avoid; }
+td { padding: 0; vertical-align: top; white-space: pre-wrap; overflow-wrap: anywhere; }
+td.n { color: #888; text-align: right; padding-right: 1em; white-space: pre; user-select: none; }
+img { display: block; max-width: 100%; max-height: 95vh; margin: auto; }
+";
+
+/// A page that opens the print dialog as soon as a browser shows it. Lines are numbered from
+/// `first_line` (1-based) when `line_numbers`, and colored by `spans` (one per line) when given.
+pub(crate) fn print_text(title: &str, lines: &[&str], first_line: usize, line_numbers: bool, spans: Option<&[LineSpans]>) -> String {
+    let mut out = page_start(title);
+    out.push_str("<table>\n");
+    for (i, line) in lines.iter().enumerate() {
+        out.push_str("<tr>");
+        if line_numbers {
+            let _ = write!(out, "<td class=\"n\">{}</td>", first_line + i);
+        }
+        out.push_str("<td>");
+        match spans.and_then(|s| s.get(i)) {
+            Some(spans) => push_colored(&mut out, line, spans),
+            None => push_escaped(&mut out, line),
+        }
+        // An empty cell would collapse the row
+        if line.is_empty() {
+            out.push(' ');
+        }
+        out.push_str("</td></tr>\n");
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+/// A print page showing the image file `src`, relative to the page.
+pub(crate) fn print_image(title: &str, src: &str) -> String {
+    let mut out = page_start(title);
+    out.push_str("<img src=\"");
+    push_escaped(&mut out, src);
+    out.push_str("\" alt=\"\">\n</body>\n</html>\n");
+    out
+}
+
+/// A standalone page with the text in the theme's `background` and `foreground`, colored by `spans`
+/// (one per line). All styling is inline, so a snippet copied from it keeps its colors when pasted.
+pub(crate) fn export_text(title: &str, lines: &[&str], spans: &[LineSpans], background: Color32, foreground: Color32) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+    push_escaped(&mut out, title);
+    let _ = write!(
+        out,
+        "</title>\n</head>\n<body style=\"margin:0;background:{0}\">\n<pre style=\"margin:0;padding:1em;background:{0};color:{1};font:10pt monospace\">",
+        css_color(background),
+        css_color(foreground)
+    );
+    for (i, line) in lines.iter().enumerate() {
+        match spans.get(i) {
+            Some(spans) => push_colored(&mut out, line, spans),
+            None => push_escaped(&mut out, line),
+        }
+        out.push('\n');
+    }
+    out.push_str("</pre>\n</body>\n</html>\n");
+    out
+}
+
+fn page_start(title: &str) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+    push_escaped(&mut out, title);
+    let _ = write!(out, "</title>\n<style>{}</style>\n</head>\n<body onload=\"window.print()\">\n<h1>", PRINT_STYLE);
+    push_escaped(&mut out, title);
+    out.push_str("</h1>\n");
+    out
+}
+
+fn push_colored(out: &mut String, line: &str, spans: &LineSpans) {
+    let mut pos = 0;
+    for (range, color) in spans {
+        // Spans also cover the line ending, which isn't part of `line`
+        let (start, end) = (range.start.min(line.len()), range.end.min(line.len()));
+        let (Some(before), Some(part)) = (line.get(pos..start), line.get(start..end)) else { continue };
+        push_escaped(out, before);
+        if !part.is_empty() {
+            let _ = write!(out, "<span style=\"color:{}\">", css_color(*color));
+            push_escaped(out, part);
+            out.push_str("</span>");
+        }
+        pos = end;
+    }
+    push_escaped(out, line.get(pos..).unwrap_or(""));
+}
+
+fn css_color(c: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", c.r(), c.g(), c.b())
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use eframe::egui::{Color32, ColorImage, Rect};
+use image::RgbaImage;
+
+pub(crate) fn to_color_image(img: &RgbaImage) -> ColorImage {
+    ColorImage::from_rgba_unmultiplied([img.width() as _, img.height() as _], img.as_raw())
+}
+
+/// Per-channel value counts: red, green, blue and luminance.
+pub(crate) struct Histogram {
+    pub(crate) channels: [[u32; 256]; 4],
+}
+
+impl Histogram {
+    pub(crate) fn peak(&self) -> u32 {
+        self.channels.iter().flat_map(|c| c.iter()).copied().max().unwrap_or(0)
+    }
+}
+
+/// Downscaled copy fitting in `max` x `max`, keeping the aspect ratio.
+pub(crate) fn thumbnail(img: &RgbaImage, max: u32) -> RgbaImage {
+    let scale = (max as f32 / img.width().max(img.height()) as f32).min(1.0);
+    let w = ((img.width() as f32 * scale).round() as u32).max(1);
+    let h = ((img.height() as f32 * scale).round() as u32).max(1);
+    image::imageops::thumbnail(img, w, h)
+}
+
+pub(crate) fn histogram(img: &RgbaImage) -> Histogram {
+    let mut channels = [[0u32; 256]; 4];
+    for px in img.pixels() {
+        let [r, g, b, _] = px.0;
+        channels[0][r as usize] += 1;
+        channels[1][g as usize] += 1;
+        channels[2][b as usize] += 1;
+        // Rec. 709 luma weights in 8-bit fixed point
+        let luma = (54 * r as u32 + 183 * g as u32 + 19 * b as u32) >> 8;
+        channels[3][luma as usize] += 1;
+    }
+    Histogram { channels }
+}
+
+/// Copy out the pixels under `sel` (image coordinates), clamped to the image bounds.
+pub(crate) fn crop(img: &RgbaImage, sel: Rect) -> RgbaImage {
+    let x = (sel.min.x.max(0.0) as u32).min(img.width());
+    let y = (sel.min.y.max(0.0) as u32).min(img.height());
+    let w = (sel.width().max(0.0) as u32).min(img.width() - x);
+    let h = (sel.height().max(0.0) as u32).min(img.height() - y);
+    image::imageops::crop_imm(img, x, y, w, h).to_image()
+}
+
+/// One turn or mirror of the shown image.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Transform {
+    RotateClockwise,
+    RotateCounterClockwise,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+/// How an image is shown relative to its file: mirrored left to right first when `mirrored`,
+/// then turned clockwise by `quarter_turns` × 90°.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) struct Orientation {
+    mirrored: bool,
+    quarter_turns: u8,
+}
+
+impl Orientation {
+    pub(crate) fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// This orientation followed by `t`.
+    pub(crate) fn then(self, t: Transform) -> Orientation {
+        let turns = self.quarter_turns;
+        match t {
+            Transform::RotateClockwise => Orientation { quarter_turns: (turns + 1) % 4, ..self },
+            Transform::RotateCounterClockwise => Orientation { quarter_turns: (turns + 3) % 4, ..self },
+            // Mirroring after a turn equals mirroring first and turning the other way
+            Transform::FlipHorizontal => Orientation { mirrored: !self.mirrored, quarter_turns: (4 - turns) % 4 },
+            // A vertical flip is a horizontal one plus a half turn
+            Transform::FlipVertical => Orientation { mirrored: !self.mirrored, quarter_turns: (6 - turns) % 4 },
+        }
+    }
+
+    pub(crate) fn apply(self, img: RgbaImage) -> RgbaImage {
+        let img = if self.mirrored { image::imageops::flip_horizontal(&img) } else { img };
+        match self.quarter_turns {
+            1 => image::imageops::rotate90(&img),
+            2 => image::imageops::rotate180(&img),
+            3 => image::imageops::rotate270(&img),
+            _ => img,
+        }
+    }
+}
+
+pub(crate) fn transformed(img: &RgbaImage, t: Transform) -> RgbaImage {
+    match t {
+        Transform::RotateClockwise => image::imageops::rotate90(img),
+        Transform::RotateCounterClockwise => image::imageops::rotate270(img),
+        Transform::FlipHorizontal => image::imageops::flip_horizontal(img),
+        Transform::FlipVertical => image::imageops::flip_vertical(img),
+    }
+}
+
+/// Preview-only tone adjustments applied to the displayed texture.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct Adjustments {
+    pub(crate) brightness: f32,
+    pub(crate) contrast: f32,
+    pub(crate) gamma: f32,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self { brightness: 0.0, contrast: 1.0, gamma: 1.0 }
+    }
+}
+
+impl Adjustments {
+    pub(crate) fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+pub(crate) fn adjusted_color_image(img: &RgbaImage, adj: Adjustments) -> ColorImage {
+    if adj.is_identity() {
+        return to_color_image(img);
+    }
+    let inv_gamma = 1.0 / adj.gamma.max(0.01);
+    let lut: Vec<u8> = (0..256)
+        .map(|x| {
+            let v = ((x as f32 / 255.0 - 0.5) * adj.contrast + 0.5 + adj.brightness).clamp(0.0, 1.0);
+            (v.powf(inv_gamma) * 255.0).round() as u8
+        })
+        .collect();
+    let mut out = img.clone();
+    for px in out.pixels_mut() {
+        for c in &mut px.0[..3] {
+            *c = lut[*c as usize];
+        }
+    }
+    to_color_image(&out)
+}
+
+/// Overlay marking pixels that differ between `a` and `b` in red, stronger for bigger differences.
+/// Both are aligned top-left; only the overlapping area is compared. Also returns the differing pixel count.
+pub(crate) fn difference(a: &RgbaImage, b: &RgbaImage) -> (ColorImage, usize) {
+    let (w, h) = (a.width().min(b.width()), a.height().min(b.height()));
+    let mut changed = 0;
+    let mut pixels = Vec::with_capacity((w * h) as usize);
+    for y in 0..h {
+        for x in 0..w {
+            let (pa, pb) = (a.get_pixel(x, y).0, b.get_pixel(x, y).0);
+            let delta = pa.iter().zip(pb).map(|(&ca, cb)| ca.abs_diff(cb)).max().unwrap_or(0);
+            pixels.push(if delta == 0 {
+                Color32::TRANSPARENT
+            } else {
+                changed += 1;
+                // Even one-level changes must stay visible
+                Color32::from_rgba_unmultiplied(255, 0, 0, 128 + delta / 2)
+            });
+        }
+    }
+    (ColorImage { size: [w as usize, h as usize], pixels }, changed)
+}
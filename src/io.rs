@@ -1,9 +1,9 @@
-use eframe::egui::ColorImage;
-use image::GenericImageView;
+use image::RgbaImage;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 const MAX_IMAGE_TEXTURE_BYTES: usize = 128 * 1024 * 1024; // ~128 MB RGBA texture limit
+const MAX_ANIMATION_BYTES: usize = 256 * 1024 * 1024; // all decoded frames of an animation together
 
 pub(crate) fn is_supported_image(path: &Path) -> bool {
     let ext = path
@@ -14,39 +14,545 @@ pub(crate) fn is_supported_image(path: &Path) -> bool {
     matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp")
 }
 
-pub(crate) fn load_text(path: &Path) -> Result<(String, bool, usize), String> {
-    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
-    let text = String::from_utf8_lossy(&bytes).into_owned();
-    let lossy = text.contains('\u{FFFD}');
-    let lines = text.lines().count();
-    Ok((text, lossy, lines))
+pub(crate) struct LoadedText {
+    pub(crate) text: String,
+    /// Byte offsets in `text` where undecodable input was replaced with U+FFFD
+    pub(crate) invalid_offsets: Vec<usize>,
+    pub(crate) lines: usize,
+    /// Encoding the bytes were decoded as, e.g. "UTF-8" or "UTF-16LE"
+    pub(crate) encoding: &'static str,
+    /// Byte offset in `text` of each line's start
+    pub(crate) line_starts: Vec<usize>,
+    /// Byte offset in the source of each line's start, only where that differs from `line_starts`
+    /// (text that wasn't valid UTF-8); empty otherwise
+    pub(crate) line_offsets: Vec<usize>,
 }
 
-pub(crate) fn load_image(path: &Path) -> Result<ColorImage, String> {
-    // Pre-check dimensions to estimate texture memory before decoding
-    if let Ok((w, h)) = image::image_dimensions(path) {
-        let est_bytes: usize = (w as usize)
-            .saturating_mul(h as usize)
-            .saturating_mul(4);
-        if est_bytes > MAX_IMAGE_TEXTURE_BYTES {
-            return Err(format!(
-                "Image too large: {}x{} (~{:.1} MB RGBA). Limit ~{:.0} MB",
-                w,
-                h,
-                est_bytes as f64 / (1024.0 * 1024.0),
-                MAX_IMAGE_TEXTURE_BYTES as f64 / (1024.0 * 1024.0)
-            ));
+impl LoadedText {
+    /// Text that doesn't come straight from a file, e.g. a reformatted view or the clipboard.
+    pub(crate) fn plain(text: String, encoding: &'static str) -> LoadedText {
+        let line_starts = text_line_offsets(&text);
+        LoadedText { lines: line_count_of(&text, &line_starts), text, invalid_offsets: Vec::new(), encoding, line_starts, line_offsets: Vec::new() }
+    }
+}
+
+/// Open for reading. With `shared`, other programs may keep writing, renaming or deleting the file
+/// while it is open; otherwise writers are refused until it is closed. Only Windows enforces sharing
+/// modes; elsewhere both behave the same.
+pub(crate) fn open_file(path: &Path, shared: bool) -> std::io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_SHARE_READ: u32 = 0x1;
+        const FILE_SHARE_WRITE: u32 = 0x2;
+        const FILE_SHARE_DELETE: u32 = 0x4;
+        options.share_mode(if shared { FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE } else { FILE_SHARE_READ });
+    }
+    #[cfg(not(windows))]
+    let _ = shared;
+    options.open(path)
+}
+
+pub(crate) fn read_file(path: &Path, shared: bool) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    let mut file = open_file(path, shared)?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read and decode a text file as `encoding`, or as whatever it looks like when None.
+pub(crate) fn load_text(path: &Path, shared: bool, encoding: Option<&'static encoding_rs::Encoding>) -> Result<LoadedText, String> {
+    let bytes = read_file(path, shared).map_err(|e| format!("Failed to read file: {}", e))?;
+    let encoding = encoding.unwrap_or_else(|| detect_utf16(&bytes).unwrap_or_else(|| detect_encoding(&bytes)));
+    Ok(decode_as(bytes, encoding))
+}
+
+pub(crate) fn decode_text(bytes: Vec<u8>) -> LoadedText {
+    let encoding = detect_utf16(&bytes).unwrap_or_else(|| detect_encoding(&bytes));
+    decode_as(bytes, encoding)
+}
+
+// How much of a file that isn't UTF-8 is examined to guess its legacy encoding
+const DETECT_SAMPLE_BYTES: usize = 1024 * 1024;
+
+/// UTF-8 when the bytes are valid UTF-8 (allowing a sequence cut off at the end, as in a file's
+/// first few KB); otherwise the legacy encoding they most resemble, e.g. windows-1252 or Shift_JIS.
+fn detect_encoding(bytes: &[u8]) -> &'static encoding_rs::Encoding {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return encoding_rs::UTF_8,
+        Err(e) if e.error_len().is_none() => return encoding_rs::UTF_8,
+        Err(_) => {}
+    }
+    let sample = &bytes[..bytes.len().min(DETECT_SAMPLE_BYTES)];
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(sample, sample.len() == bytes.len());
+    detector.guess(None, true)
+}
+
+/// Encodings offered for reopening a file that was decoded wrongly.
+pub(crate) fn reopen_encodings() -> [&'static encoding_rs::Encoding; 13] {
+    use encoding_rs::*;
+    [UTF_8, UTF_16LE, UTF_16BE, WINDOWS_1252, ISO_8859_2, ISO_8859_15, WINDOWS_1251, KOI8_R, SHIFT_JIS, EUC_JP, GBK, BIG5, EUC_KR]
+}
+
+/// Decode `bytes` as `encoding`, skipping a matching byte order mark. Valid UTF-8 becomes the
+/// text without being copied.
+pub(crate) fn decode_as(bytes: Vec<u8>, encoding: &'static encoding_rs::Encoding) -> LoadedText {
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        let bom: &[u8] = if encoding == encoding_rs::UTF_16LE { b"\xFF\xFE" } else { b"\xFE\xFF" };
+        let body = bytes.strip_prefix(bom).unwrap_or(&bytes);
+        let (text, _) = encoding.decode_without_bom_handling(body);
+        let text = text.into_owned();
+        let invalid_offsets = text.match_indices('\u{FFFD}').map(|(i, _)| i).collect();
+        // A newline is one 16-bit unit, with the 0x0A byte first in little endian
+        let bom = bytes.len() - body.len();
+        let newline: &[u8] = if encoding == encoding_rs::UTF_16LE { b"\n\0" } else { b"\0\n" };
+        let line_offsets = std::iter::once(bom)
+            .chain(body.chunks_exact(2).enumerate().filter(|(_, unit)| *unit == newline).map(|(i, _)| bom + i * 2 + 2))
+            .collect();
+        let line_starts = text_line_offsets(&text);
+        return LoadedText { lines: line_count_of(&text, &line_starts), text, invalid_offsets, encoding: encoding.name(), line_starts, line_offsets };
+    }
+    let (text, invalid_offsets, bytes) = if encoding == encoding_rs::UTF_8 {
+        match String::from_utf8(bytes) {
+            Ok(text) => (text, Vec::new(), None),
+            Err(e) => {
+                // Lossy decode by hand so we know where each replacement character came from
+                let bytes = e.into_bytes();
+                let mut text = String::with_capacity(bytes.len());
+                let mut invalid_offsets = Vec::new();
+                for chunk in bytes.utf8_chunks() {
+                    text.push_str(chunk.valid());
+                    if !chunk.invalid().is_empty() {
+                        invalid_offsets.push(text.len());
+                        text.push('\u{FFFD}');
+                    }
+                }
+                (text, invalid_offsets, Some(bytes))
+            }
+        }
+    } else {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes);
+        let text = text.into_owned();
+        let invalid_offsets = text.match_indices('\u{FFFD}').map(|(i, _)| i).collect();
+        (text, invalid_offsets, Some(bytes))
+    };
+    let line_starts = text_line_offsets(&text);
+    // These encodings are ASCII-compatible and never use b'\n' inside a multi-byte sequence,
+    // so line starts in the file follow the raw newlines
+    let line_offsets = bytes.map_or_else(Vec::new, |bytes| text_line_offsets_in(&bytes));
+    LoadedText { lines: line_count_of(&text, &line_starts), text, invalid_offsets, encoding: encoding.name(), line_starts, line_offsets }
+}
+
+/// UTF-8 text appended to a file whose first `len` bytes end with `tail`. A character still being
+/// written is left for the next read. None when the file was rewritten or what follows isn't UTF-8.
+pub(crate) fn read_appended(path: &Path, shared: bool, len: usize, tail: &[u8]) -> Option<String> {
+    use std::io::{Read, Seek};
+    let mut file = open_file(path, shared).ok()?;
+    file.seek(std::io::SeekFrom::Start((len - tail.len()) as u64)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    if !bytes.starts_with(tail) {
+        return None;
+    }
+    let mut appended = bytes.split_off(tail.len());
+    if let Err(e) = std::str::from_utf8(&appended) {
+        // An error with no length is an incomplete sequence at the very end
+        if e.error_len().is_some() {
+            return None;
+        }
+        appended.truncate(e.valid_up_to());
+    }
+    String::from_utf8(appended).ok()
+}
+
+/// Byte offset of each line's start in `text`.
+pub(crate) fn text_line_offsets(text: &str) -> Vec<usize> {
+    text_line_offsets_in(text.as_bytes())
+}
+
+fn text_line_offsets_in(bytes: &[u8]) -> Vec<usize> {
+    std::iter::once(0).chain(memchr::memchr_iter(b'\n', bytes).map(|i| i + 1)).collect()
+}
+
+/// Lines as the viewer renders them, from the line starts of `text`: a final `\n` (or `\r\n`) ends
+/// the last line instead of starting an empty one, so `"a\nb"` and `"a\nb\n"` both have 2 lines and `""` has none.
+pub(crate) fn line_count_of(text: &str, line_starts: &[usize]) -> usize {
+    if text.is_empty() {
+        0
+    } else if text.ends_with('\n') {
+        line_starts.len() - 1
+    } else {
+        line_starts.len()
+    }
+}
+
+/// UTF-16 by BOM, or, for BOM-less files such as many Windows logs, by NUL bytes piling up in
+/// the high-byte positions of mostly-ASCII text.
+fn detect_utf16(bytes: &[u8]) -> Option<&'static encoding_rs::Encoding> {
+    if bytes.starts_with(b"\xFF\xFE") {
+        return Some(encoding_rs::UTF_16LE);
+    }
+    if bytes.starts_with(b"\xFE\xFF") {
+        return Some(encoding_rs::UTF_16BE);
+    }
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    // Mostly NULs on one side and almost none on the other
+    let (mostly, rarely) = (pairs * 2 / 5, pairs / 20);
+    if odd_nuls >= mostly && even_nuls <= rarely {
+        Some(encoding_rs::UTF_16LE)
+    } else if even_nuls >= mostly && odd_nuls <= rarely {
+        Some(encoding_rs::UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// The first `max_lines` lines of a text file, reading at most a few KB of it.
+pub(crate) fn text_preview(path: &Path, shared: bool, max_lines: usize) -> Result<String, String> {
+    use std::io::Read;
+    let mut head = Vec::with_capacity(4096);
+    open_file(path, shared)
+        .and_then(|f| f.take(4096).read_to_end(&mut head))
+        .map_err(|e| e.to_string())?;
+    let loaded = decode_text(head);
+    let lines: Vec<String> = loaded
+        .text
+        .lines()
+        .take(max_lines)
+        .map(|l| if l.chars().count() > 80 { format!("{}…", l.chars().take(80).collect::<String>()) } else { l.to_string() })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+
+/// `text` without the line ending that terminates its last line, so plain labels show no extra empty row.
+pub(crate) fn trim_final_newline(text: &str) -> &str {
+    text.strip_suffix('\n').map(|t| t.strip_suffix('\r').unwrap_or(t)).unwrap_or(text)
+}
+
+/// Text files from this size up are indexed and read a screenful at a time instead of loaded whole.
+pub(crate) const LARGE_TEXT_BYTES: u64 = 256 * 1024 * 1024;
+// The index keeps the start of every this many lines; lines in between are found by reading on from there
+const LINES_PER_CHECKPOINT: usize = 256;
+// Longer lines are cut short when shown or searched
+const MAX_SHOWN_LINE_BYTES: usize = 16 * 1024;
+const INDEX_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Where the lines of a text file too large to hold in memory start, so any of them can be read
+/// on demand. Only the bytes present when last indexed are read, which lets a growing log be
+/// followed by indexing just what was appended.
+#[derive(Clone)]
+pub(crate) struct LineIndex {
+    pub(crate) path: PathBuf,
+    pub(crate) encoding: &'static encoding_rs::Encoding,
+    shared: bool,
+    /// Bytes indexed
+    len: u64,
+    newlines: usize,
+    /// Start of every `LINES_PER_CHECKPOINT`th line
+    checkpoints: Vec<u64>,
+    // Start of the line after the last newline
+    last_start: u64,
+    // The lines shown most recently, so an unmoved view doesn't touch the file
+    cache: std::cell::RefCell<Option<(std::ops::Range<usize>, Vec<String>)>>,
+}
+
+impl LineIndex {
+    /// Index `path`, guessing its encoding from the start. UTF-16 files can't be split at newline
+    /// bytes, so they are refused.
+    pub(crate) fn open(path: &Path, shared: bool) -> Result<LineIndex, String> {
+        use std::io::Read;
+        let mut head = Vec::new();
+        open_file(path, shared)
+            .and_then(|f| f.take(DETECT_SAMPLE_BYTES as u64).read_to_end(&mut head))
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if detect_utf16(&head).is_some() {
+            return Err("UTF-16 text files this large are not supported".to_string());
+        }
+        let mut index = LineIndex {
+            path: path.to_path_buf(),
+            encoding: detect_encoding(&head),
+            shared,
+            len: 0,
+            newlines: 0,
+            checkpoints: vec![0],
+            last_start: 0,
+            cache: Default::default(),
+        };
+        index.index_appended()?;
+        Ok(index)
+    }
+
+    /// Lines as `line_count` counts them.
+    pub(crate) fn line_count(&self) -> usize {
+        self.newlines + (self.len > self.last_start) as usize
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Memory held by the index itself.
+    pub(crate) fn approx_bytes(&self) -> usize {
+        self.checkpoints.len() * std::mem::size_of::<u64>()
+    }
+
+    /// Catch up with the file: index what was appended, or start over if it shrank.
+    /// Returns whether anything changed.
+    pub(crate) fn refresh(&mut self) -> Result<bool, String> {
+        let len = fs::metadata(&self.path).map_err(|e| format!("Failed to read file: {}", e))?.len();
+        if len == self.len {
+            return Ok(false);
+        }
+        if len < self.len {
+            (self.len, self.newlines, self.last_start) = (0, 0, 0);
+            self.checkpoints = vec![0];
+        }
+        self.cache.take();
+        self.index_appended()?;
+        Ok(true)
+    }
+
+    fn index_appended(&mut self) -> Result<(), String> {
+        use std::io::{Read, Seek, SeekFrom};
+        let read_error = |e: std::io::Error| format!("Failed to read file: {}", e);
+        let mut file = open_file(&self.path, self.shared).map_err(read_error)?;
+        file.seek(SeekFrom::Start(self.len)).map_err(read_error)?;
+        let mut chunk = vec![0; INDEX_CHUNK_BYTES];
+        loop {
+            let read = file.read(&mut chunk).map_err(read_error)?;
+            if read == 0 {
+                return Ok(());
+            }
+            for i in memchr::memchr_iter(b'\n', &chunk[..read]) {
+                self.newlines += 1;
+                self.last_start = self.len + i as u64 + 1;
+                if self.newlines.is_multiple_of(LINES_PER_CHECKPOINT) {
+                    self.checkpoints.push(self.last_start);
+                }
+            }
+            self.len += read as u64;
         }
     }
 
-    let img = image::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
-    let (width, height) = img.dimensions();
-    let rgba = img.to_rgba8();
-    let pixels = rgba.into_flat_samples();
-    Ok(ColorImage::from_rgba_unmultiplied([
-        width as _,
-        height as _,
-    ], pixels.as_slice()))
+    /// The lines in `range` (clamped to the file), without line endings. Overlong lines end in "…".
+    pub(crate) fn lines(&self, range: std::ops::Range<usize>) -> Vec<String> {
+        let range = range.start.min(self.line_count())..range.end.min(self.line_count());
+        if range.is_empty() {
+            return Vec::new();
+        }
+        if let Some((cached, lines)) = &*self.cache.borrow()
+            && cached.start <= range.start
+            && range.end <= cached.end
+        {
+            return lines[range.start - cached.start..range.end - cached.start].to_vec();
+        }
+        let mut lines = Vec::with_capacity(range.len());
+        // A file that can't be read right now shows no text rather than failing the whole view
+        let _ = self.for_each_line(range.start, |_, line| {
+            lines.push(line);
+            lines.len() < range.len()
+        });
+        *self.cache.borrow_mut() = Some((range, lines.clone()));
+        lines
+    }
+
+    /// Call `f` with each line from `first` on, with its number, until it returns false or the indexed part ends.
+    pub(crate) fn for_each_line(&self, first: usize, mut f: impl FnMut(usize, String) -> bool) -> Result<(), String> {
+        use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+        let read_error = |e: std::io::Error| format!("Failed to read file: {}", e);
+        let Some(&start) = self.checkpoints.get(first / LINES_PER_CHECKPOINT) else { return Ok(()) };
+        let mut file = open_file(&self.path, self.shared).map_err(read_error)?;
+        file.seek(SeekFrom::Start(start)).map_err(read_error)?;
+        let mut reader = BufReader::with_capacity(64 * 1024, file.take(self.len - start));
+        let mut bytes = Vec::new();
+        let mut i = first / LINES_PER_CHECKPOINT * LINES_PER_CHECKPOINT;
+        loop {
+            // Lines before `first` are only skipped over
+            let max = if i < first { 0 } else { MAX_SHOWN_LINE_BYTES };
+            bytes.clear();
+            let mut length = 0;
+            let mut ended = false;
+            while !ended {
+                let buf = reader.fill_buf().map_err(read_error)?;
+                if buf.is_empty() {
+                    break;
+                }
+                let used = memchr::memchr(b'\n', buf).map_or(buf.len(), |n| n + 1);
+                ended = buf[used - 1] == b'\n';
+                bytes.extend_from_slice(&buf[..used.min(max.saturating_sub(bytes.len()))]);
+                length += used;
+                reader.consume(used);
+            }
+            if length == 0 {
+                return Ok(());
+            }
+            if i >= first {
+                let cut = length - ended as usize > MAX_SHOWN_LINE_BYTES;
+                let shown = bytes.strip_suffix(b"\n").unwrap_or(&bytes);
+                let shown = shown.strip_suffix(b"\r").unwrap_or(shown);
+                let line = if cut {
+                    // Decoding as if more were to come drops a character split by the cut rather than replacing it
+                    let mut decoder = self.encoding.new_decoder_without_bom_handling();
+                    let mut line = String::with_capacity(decoder.max_utf8_buffer_length(shown.len()).unwrap_or(shown.len()) + '…'.len_utf8());
+                    let _ = decoder.decode_to_string(shown, &mut line, false);
+                    line.push('…');
+                    line
+                } else {
+                    self.encoding.decode_without_bom_handling(shown).0.into_owned()
+                };
+                if !f(i, line) {
+                    return Ok(());
+                }
+            }
+            i += 1;
+        }
+    }
+}
+
+pub(crate) fn load_image(path: &Path, shared: bool) -> Result<RgbaImage, String> {
+    let reader = || {
+        let file = open_file(path, shared).map_err(|e| format!("Failed to open image: {}", e))?;
+        let mut reader = image::ImageReader::new(std::io::BufReader::new(file));
+        if let Ok(format) = image::ImageFormat::from_path(path) {
+            reader.set_format(format);
+        }
+        Ok::<_, String>(reader)
+    };
+    // Pre-check dimensions to estimate texture memory before decoding
+    if let Ok((w, h)) = reader()?.into_dimensions() {
+        check_texture_size(w, h)?;
+    }
+    let img = reader()?.decode().map_err(|e| format!("Failed to open image: {}", e))?;
+    finish_image(img)
+}
+
+/// One frame of an animated image and how long it stays up.
+pub(crate) type Frame = (RgbaImage, std::time::Duration);
+
+/// Every frame of a GIF or an APNG. `None` for other still images and anything that fails to
+/// decode or would use too much memory; those load through `load_image`.
+pub(crate) fn load_animation(path: &Path, shared: bool) -> Option<Vec<Frame>> {
+    use image::AnimationDecoder;
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    let reader = std::io::BufReader::new(open_file(path, shared).ok()?);
+    let frames = match ext.as_str() {
+        "gif" => image::codecs::gif::GifDecoder::new(reader).ok()?.into_frames(),
+        "png" => {
+            let decoder = image::codecs::png::PngDecoder::new(reader).ok()?;
+            if !decoder.is_apng().ok()? {
+                return None;
+            }
+            decoder.apng().ok()?.into_frames()
+        }
+        _ => return None,
+    };
+    let mut out = Vec::new();
+    let mut total = 0usize;
+    for frame in frames {
+        let frame = frame.ok()?;
+        let (num, den) = frame.delay().numer_denom_ms();
+        // Like browsers, treat delays of 10 ms or less as the customary 100 ms
+        let ms = num.checked_div(den).unwrap_or(0);
+        let delay = std::time::Duration::from_millis(if ms <= 10 { 100 } else { ms as u64 });
+        let buffer = frame.into_buffer();
+        check_texture_size(buffer.width(), buffer.height()).ok()?;
+        total = total.saturating_add(buffer.as_raw().len());
+        if total > MAX_ANIMATION_BYTES || buffer.width() == 0 || buffer.height() == 0 {
+            return None;
+        }
+        out.push((buffer, delay));
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+/// Same as `load_image` for an image already in memory, e.g. an archive entry.
+pub(crate) fn load_image_bytes(bytes: &[u8]) -> Result<RgbaImage, String> {
+    let reader = || {
+        image::ImageReader::new(std::io::Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| format!("Failed to open image: {}", e))
+    };
+    if let Ok((w, h)) = reader()?.into_dimensions() {
+        check_texture_size(w, h)?;
+    }
+    let img = reader()?.decode().map_err(|e| format!("Failed to open image: {}", e))?;
+    finish_image(img)
+}
+
+fn check_texture_size(w: u32, h: u32) -> Result<(), String> {
+    let est_bytes: usize = (w as usize)
+        .saturating_mul(h as usize)
+        .saturating_mul(4);
+    if est_bytes > MAX_IMAGE_TEXTURE_BYTES {
+        return Err(format!(
+            "Image too large: {}x{} (~{:.1} MB RGBA). Limit ~{:.0} MB",
+            w,
+            h,
+            est_bytes as f64 / (1024.0 * 1024.0),
+            MAX_IMAGE_TEXTURE_BYTES as f64 / (1024.0 * 1024.0)
+        ));
+    }
+    Ok(())
+}
+
+fn finish_image(img: image::DynamicImage) -> Result<RgbaImage, String> {
+    if img.width() == 0 || img.height() == 0 {
+        return Err(format!("Image has no pixels ({}x{})", img.width(), img.height()));
+    }
+    Ok(img.to_rgba8())
+}
+
+/// Best guess at what a file contains from its first bytes, independent of its extension.
+pub(crate) fn sniff_type(path: &Path) -> Result<String, String> {
+    use std::io::Read;
+    let mut head = Vec::with_capacity(512);
+    open_file(path, true)
+        .and_then(|f| f.take(512).read_to_end(&mut head))
+        .map_err(|e| e.to_string())?;
+    Ok(if head.is_empty() {
+        "empty".to_string()
+    } else if let Ok(format) = image::guess_format(&head) {
+        format!("{:?} image", format)
+    } else if head.starts_with(b"PK\x03\x04") {
+        "ZIP archive".to_string()
+    } else if head.contains(&0) {
+        "binary".to_string()
+    } else if std::str::from_utf8(&head).map_or_else(|e| e.error_len().is_none(), |_| true) {
+        // `error_len() == None` means only the cut-off tail of the sample is incomplete
+        "UTF-8 text".to_string()
+    } else {
+        "text (not valid UTF-8)".to_string()
+    })
+}
+
+/// The Open dialog, filtered to the file types the viewer knows.
+pub(crate) fn open_dialog() -> rfd::FileDialog {
+    const TEXT: &[&str] = &["txt","rs","py","toml","md","json","js","html","css","c","h","cpp","hpp","go","java","ts","yaml","yml","sh","sql","csv","tsv"];
+    const IMAGES: &[&str] = &["png","jpg","jpeg","gif","bmp","webp"];
+    const ARCHIVES: &[&str] = &["zip","tar","gz","tgz"];
+    let all: Vec<&str> = TEXT.iter().chain(IMAGES).chain(&["pdf"]).chain(ARCHIVES).copied().collect();
+    rfd::FileDialog::new()
+        .add_filter("All Supported", &all)
+        .add_filter("Images", IMAGES)
+        .add_filter("Text/Source", TEXT)
+        .add_filter("PDF", &["pdf"])
+        .add_filter("Archives", ARCHIVES)
+}
+
+/// Whether the extension maps to a viewer without asking: text, image or archive.
+pub(crate) fn is_known_type(path: &Path) -> bool {
+    is_supported_text(path) || is_supported_image(path) || crate::archive::is_archive(path) || crate::pdf::is_pdf(path)
 }
 
 pub(crate) fn is_supported_text(path: &Path) -> bool {
@@ -57,43 +563,80 @@ pub(crate) fn is_supported_text(path: &Path) -> bool {
         .to_lowercase();
     matches!(
         ext.as_str(),
-        "txt" | "rs" | "py" | "toml" | "md" | "json" | "js" | "html" | "css"
+        "txt" | "rs" | "py" | "toml" | "md" | "json" | "js" | "html" | "htm" | "xml" | "css"
+            | "c" | "h" | "cpp" | "hpp" | "cc" | "cs" | "go" | "java" | "ts" | "rb" | "php" | "lua"
+            | "yaml" | "yml" | "sh" | "bash" | "sql" | "ini" | "log" | "csv" | "tsv"
     )
 }
 
-pub(crate) fn neighbor_image(path: &Path, forward: bool) -> Option<PathBuf> {
-    let parent = path.parent()?;
-    let mut images: Vec<PathBuf> = std::fs::read_dir(parent).ok()?
-        .filter_map(|e| e.ok().map(|e| e.path()))
-        .filter(|p| p.is_file() && is_supported_image(p))
-        .collect();
-    if images.is_empty() { return None; }
-    images.sort();
-    let current_name = path.file_name()?;
-    let idx = images.iter().position(|p| p.file_name() == Some(current_name))?;
-    if images.len() <= 1 { return None; }
-    let next_idx = if forward {
-        (idx + 1) % images.len()
-    } else {
-        (idx + images.len() - 1) % images.len()
-    };
-    images.get(next_idx).cloned()
+/// Dotfiles everywhere, plus files with the hidden attribute on Windows.
+pub(crate) fn is_hidden(path: &Path) -> bool {
+    if path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')) {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if fs::metadata(path).is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0) {
+            return true;
+        }
+    }
+    false
 }
-pub(crate) fn neighbor_text(path: &Path, forward: bool) -> Option<PathBuf> {
-    let parent = path.parent()?;
-    let mut texts: Vec<PathBuf> = std::fs::read_dir(parent).ok()?
-        .filter_map(|e| e.ok().map(|e| e.path()))
-        .filter(|p| p.is_file() && is_supported_text(p))
-        .collect();
-    if texts.is_empty() { return None; }
-    texts.sort();
-    let current_name = path.file_name()?;
-    let idx = texts.iter().position(|p| p.file_name() == Some(current_name))?;
-    if texts.len() <= 1 { return None; }
-    let next_idx = if forward {
-        (idx + 1) % texts.len()
-    } else {
-        (idx + texts.len() - 1) % texts.len()
-    };
-    texts.get(next_idx).cloned()
+
+/// Sorted image and text files of one directory, cached so repeated Prev/Next presses don't rescan.
+pub(crate) struct Siblings {
+    pub(crate) dir: PathBuf,
+    modified: Option<std::time::SystemTime>,
+    include_hidden: bool,
+    images: Vec<PathBuf>,
+    texts: Vec<PathBuf>,
+}
+
+impl Siblings {
+    /// List `dir`; slow for folders with many thousands of entries, so run it off the UI thread.
+    pub(crate) fn scan(dir: &Path, include_hidden: bool) -> Siblings {
+        let modified = fs::metadata(dir).and_then(|m| m.modified()).ok();
+        let (mut images, mut texts) = (Vec::new(), Vec::new());
+        for path in fs::read_dir(dir).into_iter().flatten().filter_map(|e| e.ok().map(|e| e.path())) {
+            if !include_hidden && is_hidden(&path) {
+                continue;
+            }
+            if is_supported_image(&path) && path.is_file() {
+                images.push(path);
+            } else if is_supported_text(&path) && path.is_file() {
+                texts.push(path);
+            }
+        }
+        images.sort();
+        texts.sort();
+        Siblings { dir: dir.to_path_buf(), modified, include_hidden, images, texts }
+    }
+
+    /// The folder's images, sorted by name.
+    pub(crate) fn images(&self) -> &[PathBuf] {
+        &self.images
+    }
+
+    /// Still valid for `dir`: same directory and filter, and no files added or removed since the scan.
+    pub(crate) fn is_fresh(&self, dir: &Path, include_hidden: bool) -> bool {
+        self.dir == dir && self.include_hidden == include_hidden && fs::metadata(dir).and_then(|m| m.modified()).ok() == self.modified
+    }
+
+    /// The file after (or before) `path` among siblings of the same kind, wrapping around.
+    pub(crate) fn neighbor(&self, path: &Path, image: bool, forward: bool) -> Option<PathBuf> {
+        let files = if image { &self.images } else { &self.texts };
+        let name = path.file_name()?;
+        let next_idx = match files.binary_search_by(|p| p.file_name().cmp(&Some(name))) {
+            Ok(_) if files.len() <= 1 => return None,
+            Ok(idx) if forward => (idx + 1) % files.len(),
+            Ok(idx) => (idx + files.len() - 1) % files.len(),
+            // The current file isn't listed (e.g. a hidden file while hidden ones are skipped)
+            Err(_) if files.is_empty() => return None,
+            Err(pos) if forward => pos % files.len(),
+            Err(pos) => (pos + files.len() - 1) % files.len(),
+        };
+        files.get(next_idx).cloned()
+    }
 }
@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+/// A JSON document for the tree view, with the nodes left visible by the key filter.
+pub(crate) struct JsonTree {
+    pub(crate) root: Value,
+    pub(crate) filter: String,
+    /// JSON pointers of nodes whose key matches the filter, plus their ancestors; None when not filtering
+    pub(crate) visible: Option<HashSet<String>>,
+    /// Keys matching the filter
+    pub(crate) matches: usize,
+}
+
+impl JsonTree {
+    pub(crate) fn parse(text: &str) -> Result<JsonTree, String> {
+        let root = serde_json::from_str(text).map_err(|e| format!("Not valid JSON: {}", e))?;
+        Ok(JsonTree { root, filter: String::new(), visible: None, matches: 0 })
+    }
+
+    /// Show only keys containing `filter` (ignoring case) and the nodes leading to them.
+    pub(crate) fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+        let filter = filter.trim().to_lowercase();
+        self.visible = (!filter.is_empty()).then(|| {
+            let mut visible = HashSet::new();
+            mark_matches(&self.root, "", &filter, &mut visible);
+            visible
+        });
+        self.matches = if filter.is_empty() { 0 } else { count_matches(&self.root, &filter) };
+    }
+}
+
+fn count_matches(value: &Value, filter: &str) -> usize {
+    match value {
+        Value::Object(map) => map.iter().map(|(k, v)| k.to_lowercase().contains(filter) as usize + count_matches(v, filter)).sum(),
+        Value::Array(items) => items.iter().map(|v| count_matches(v, filter)).sum(),
+        _ => 0,
+    }
+}
+
+/// Adds the pointers of matching keys under `pointer` and of their ancestors; true if any matched.
+fn mark_matches(value: &Value, pointer: &str, filter: &str, visible: &mut HashSet<String>) -> bool {
+    let mut any = false;
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_pointer = child_pointer(pointer, key);
+                let hit = key.to_lowercase().contains(filter);
+                if mark_matches(child, &child_pointer, filter, visible) || hit {
+                    // A matching object or array shows all of its contents
+                    if hit {
+                        mark_all(child, &child_pointer, visible);
+                    }
+                    visible.insert(child_pointer);
+                    any = true;
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                let child_pointer = format!("{}/{}", pointer, i);
+                if mark_matches(child, &child_pointer, filter, visible) {
+                    visible.insert(child_pointer);
+                    any = true;
+                }
+            }
+        }
+        _ => {}
+    }
+    any
+}
+
+fn mark_all(value: &Value, pointer: &str, visible: &mut HashSet<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_pointer = child_pointer(pointer, key);
+                mark_all(child, &child_pointer, visible);
+                visible.insert(child_pointer);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                let child_pointer = format!("{}/{}", pointer, i);
+                mark_all(child, &child_pointer, visible);
+                visible.insert(child_pointer);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// JSON pointer (RFC 6901) of member `key` of the node at `pointer`.
+pub(crate) fn child_pointer(pointer: &str, key: &str) -> String {
+    format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"))
+}
+
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
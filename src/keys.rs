@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use eframe::egui::{self, Key};
+
+/// Commands that can be bound to a key in Settings.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Action {
+    Open,
+    NewTab,
+    CloseTab,
+    ReopenClosedTab,
+    NextTab,
+    PreviousTab,
+    NewWindow,
+    ToggleTheme,
+    Find,
+    FolderSearch,
+    GoToLine,
+    Print,
+    ToggleLineNumbers,
+    ToggleWordWrap,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    NextFile,
+    PreviousFile,
+    ToggleBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    RotateClockwise,
+    RotateCounterClockwise,
+}
+
+impl Action {
+    pub(crate) const ALL: [Action; 24] = [
+        Action::Open,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::ReopenClosedTab,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::NewWindow,
+        Action::ToggleTheme,
+        Action::Find,
+        Action::FolderSearch,
+        Action::GoToLine,
+        Action::Print,
+        Action::ToggleLineNumbers,
+        Action::ToggleWordWrap,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ResetZoom,
+        Action::NextFile,
+        Action::PreviousFile,
+        Action::ToggleBookmark,
+        Action::NextBookmark,
+        Action::PreviousBookmark,
+        Action::RotateClockwise,
+        Action::RotateCounterClockwise,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Action::Open => "Open file",
+            Action::NewTab => "New tab",
+            Action::CloseTab => "Close tab",
+            Action::ReopenClosedTab => "Reopen closed tab",
+            Action::NextTab => "Next tab",
+            Action::PreviousTab => "Previous tab",
+            Action::NewWindow => "New window",
+            Action::ToggleTheme => "Toggle light/dark",
+            Action::Find => "Find in text",
+            Action::FolderSearch => "Search in folder",
+            Action::GoToLine => "Go to line",
+            Action::Print => "Print",
+            Action::ToggleLineNumbers => "Toggle line numbers",
+            Action::ToggleWordWrap => "Toggle word wrap",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ResetZoom => "Reset zoom",
+            Action::NextFile => "Next file in folder",
+            Action::PreviousFile => "Previous file in folder",
+            Action::ToggleBookmark => "Toggle line bookmark",
+            Action::NextBookmark => "Next bookmark",
+            Action::PreviousBookmark => "Previous bookmark",
+            Action::RotateClockwise => "Rotate image right",
+            Action::RotateCounterClockwise => "Rotate image left",
+        }
+    }
+
+    fn default_binding(self) -> Binding {
+        let ctrl = |key| Binding { command: true, shift: false, alt: false, key };
+        let ctrl_shift = |key| Binding { command: true, shift: true, alt: false, key };
+        let plain = |key| Binding { command: false, shift: false, alt: false, key };
+        match self {
+            Action::Open => ctrl(Key::O),
+            Action::NewTab => ctrl(Key::T),
+            Action::CloseTab => ctrl(Key::F4),
+            Action::ReopenClosedTab => ctrl_shift(Key::T),
+            Action::NextTab => ctrl(Key::Tab),
+            Action::PreviousTab => ctrl_shift(Key::Tab),
+            Action::NewWindow => ctrl_shift(Key::N),
+            Action::ToggleTheme => ctrl(Key::D),
+            Action::Find => ctrl(Key::F),
+            Action::FolderSearch => ctrl_shift(Key::F),
+            Action::GoToLine => ctrl(Key::G),
+            Action::Print => ctrl(Key::P),
+            Action::ToggleLineNumbers => ctrl(Key::L),
+            Action::ToggleWordWrap => ctrl(Key::W),
+            Action::ZoomIn => ctrl(Key::Equals),
+            Action::ZoomOut => ctrl(Key::Minus),
+            Action::ResetZoom => ctrl(Key::Num0),
+            Action::NextFile => plain(Key::ArrowRight),
+            Action::PreviousFile => plain(Key::ArrowLeft),
+            Action::ToggleBookmark => ctrl(Key::B),
+            Action::NextBookmark => plain(Key::F2),
+            Action::PreviousBookmark => Binding { command: false, shift: true, alt: false, key: Key::F2 },
+            Action::RotateClockwise => plain(Key::R),
+            Action::RotateCounterClockwise => Binding { command: false, shift: true, alt: false, key: Key::R },
+        }
+    }
+}
+
+/// A key and the modifiers held with it, written like "Ctrl+Shift+T" in the settings file.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(into = "String", try_from = "String")]
+pub(crate) struct Binding {
+    /// Ctrl, or Cmd on macOS
+    pub(crate) command: bool,
+    pub(crate) shift: bool,
+    pub(crate) alt: bool,
+    pub(crate) key: Key,
+}
+
+impl Binding {
+    pub(crate) fn new(key: Key, modifiers: egui::Modifiers) -> Binding {
+        Binding { command: modifiers.command, shift: modifiers.shift, alt: modifiers.alt, key }
+    }
+
+    /// Pressed this frame with exactly these modifiers, so Ctrl+T doesn't also fire for Ctrl+Shift+T.
+    fn pressed(self, input: &egui::InputState) -> bool {
+        let m = input.modifiers;
+        input.key_pressed(self.key) && m.command == self.command && m.shift == self.shift && m.alt == self.alt
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.command { f.write_str("Ctrl+")?; }
+        if self.shift { f.write_str("Shift+")?; }
+        if self.alt { f.write_str("Alt+")?; }
+        f.write_str(self.key.name())
+    }
+}
+
+impl From<Binding> for String {
+    fn from(binding: Binding) -> String {
+        binding.to_string()
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Binding, String> {
+        let mut binding = Binding { command: false, shift: false, alt: false, key: Key::Escape };
+        let (modifiers, key) = text.rsplit_once('+').filter(|(_, key)| !key.is_empty()).unwrap_or(("", text.as_str()));
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier {
+                "Ctrl" | "Cmd" => binding.command = true,
+                "Shift" => binding.shift = true,
+                "Alt" => binding.alt = true,
+                _ => return Err(format!("Unknown modifier {} in shortcut {}", modifier, text)),
+            }
+        }
+        binding.key = Key::from_name(key).ok_or_else(|| format!("Unknown key in shortcut {}", text))?;
+        Ok(binding)
+    }
+}
+
+/// The user's shortcuts where they differ from the defaults; an action mapped to None has no key.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+#[serde(transparent)]
+pub(crate) struct KeyBindings(BTreeMap<Action, Option<Binding>>);
+
+impl KeyBindings {
+    pub(crate) fn get(&self, action: Action) -> Option<Binding> {
+        self.0.get(&action).copied().unwrap_or(Some(action.default_binding()))
+    }
+
+    pub(crate) fn set(&mut self, action: Action, binding: Option<Binding>) {
+        if binding == Some(action.default_binding()) {
+            self.0.remove(&action);
+        } else {
+            self.0.insert(action, binding);
+        }
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.0.clear();
+    }
+
+    /// While a text field has focus only shortcuts with Ctrl or Alt fire, so plain keys still type.
+    pub(crate) fn pressed(&self, input: &egui::InputState, action: Action, typing: bool) -> bool {
+        self.get(action).is_some_and(|binding| (!typing || binding.command || binding.alt) && binding.pressed(input))
+    }
+
+    /// The shortcut of `action` for menus and tooltips, or "unassigned".
+    pub(crate) fn label(&self, action: Action) -> String {
+        self.get(action).map_or_else(|| "unassigned".to_string(), |binding| binding.to_string())
+    }
+
+    /// Other actions on the same key as `action`.
+    pub(crate) fn conflicts(&self, action: Action) -> Vec<Action> {
+        let Some(binding) = self.get(action) else { return Vec::new() };
+        Action::ALL.into_iter().filter(|&other| other != action && self.get(other) == Some(binding)).collect()
+    }
+}
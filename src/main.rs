@@ -1,11 +1,26 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 mod app;
+mod archive;
+mod diff;
+mod format;
+mod hexfmt;
 mod highlight;
+mod html;
 mod search;
+mod imaging;
 mod io;
+mod jsontree;
+mod keys;
+mod outline;
+mod pane;
+mod pdf;
 mod settings;
+mod syntax;
+mod table;
+mod thumbs;
 mod ui;
+mod watch;
 
 use app::FileViewerApp;
 use eframe::egui;
@@ -15,7 +30,7 @@ fn make_options() -> eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 700.0])
             .with_resizable(true)
-            .with_title("Gemini File Viewer 2.1"),
+            .with_title(app::APP_TITLE),
         ..Default::default()
     }
 }
@@ -49,14 +64,62 @@ fn configure_backend(backend: Option<&str>) {
     }
 }
 
+/// Files to open at startup and the line to show in the first one.
+struct Args {
+    files: Vec<std::path::PathBuf>,
+    line: Option<usize>,
+}
+
+const USAGE: &str = "Usage: gemini-file-viewer [--line N] [FILE]...";
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args { files: Vec::new(), line: None };
+    let mut only_files = false;
+    while let Some(arg) = args.next() {
+        if only_files || !arg.starts_with('-') || arg == "-" {
+            parsed.files.push(arg.into());
+            continue;
+        }
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        match flag.as_str() {
+            "--" => only_files = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            "-l" | "--line" => {
+                let value = inline.or_else(|| args.next()).ok_or("--line needs a line number")?;
+                let line = value.parse::<usize>().ok().filter(|&n| n > 0).ok_or_else(|| format!("Invalid line number: {}", value))?;
+                parsed.line = Some(line);
+            }
+            _ => return Err(format!("Unknown option: {}\n{}", arg, USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
 fn main() -> Result<(), eframe::Error> {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
     let chosen = select_backend_from_env();
     configure_backend(chosen);
 
     let res = eframe::run_native(
-        "Gemini File Viewer 2.1",
+        app::APP_TITLE,
         make_options(),
-        Box::new(|cc| Ok(Box::new(FileViewerApp::new(cc))))
+        Box::new(move |cc| {
+            let mut app = FileViewerApp::new(cc);
+            app.open_startup_files(args.files, args.line, &cc.egui_ctx);
+            Ok(Box::new(app))
+        })
     );
 
     if let Err(ref e) = res {
@@ -0,0 +1,53 @@
+pub(crate) struct OutlineEntry {
+    pub(crate) line: usize,
+    pub(crate) depth: usize,
+    pub(crate) label: String,
+}
+
+/// Heuristic outline: Markdown headings, TOML tables, and top-level-ish definitions for a few languages.
+pub(crate) fn extract(text: &str, ext: &str) -> Vec<OutlineEntry> {
+    let mut entries = Vec::new();
+    let mut in_fence = false;
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let entry = match ext {
+            "md" => {
+                if trimmed.starts_with("```") { in_fence = !in_fence; }
+                if in_fence { None } else { markdown_heading(trimmed) }
+            }
+            "rs" => definition(trimmed, &["pub(crate) ", "pub(super) ", "pub ", "async ", "unsafe ", "const "], &["fn ", "struct ", "enum ", "trait ", "impl ", "impl<", "mod ", "macro_rules! "], &['{', '(', ';', '='])
+                .map(|label| (indent / 4, label)),
+            "py" => definition(trimmed, &["async "], &["def ", "class "], &['(', ':']).map(|label| (indent / 4, label)),
+            "js" => definition(trimmed, &["export ", "default ", "async "], &["function ", "class "], &['{', '(']).map(|label| (indent / 2, label)),
+            "toml" => (trimmed.starts_with('[') && trimmed.trim_end().ends_with(']'))
+                .then(|| (0, trimmed.trim_end().to_string())),
+            _ => None,
+        };
+        if let Some((depth, label)) = entry {
+            entries.push(OutlineEntry { line: i, depth, label });
+        }
+    }
+    entries
+}
+
+fn markdown_heading(trimmed: &str) -> Option<(usize, String)> {
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 { return None; }
+    let rest = &trimmed[level..];
+    if !rest.starts_with(' ') { return None; }
+    let title = rest.trim().trim_end_matches('#').trim();
+    (!title.is_empty()).then(|| (level - 1, title.to_string()))
+}
+
+/// Matches `[modifiers] keyword name...` and returns the declaration up to the first stop character.
+fn definition(trimmed: &str, modifiers: &[&str], keywords: &[&str], stops: &[char]) -> Option<String> {
+    let mut rest = trimmed;
+    while let Some(m) = modifiers.iter().find(|m| rest.starts_with(*m)) {
+        rest = &rest[m.len()..];
+    }
+    keywords.iter().find(|k| rest.starts_with(*k))?;
+    let end = rest.find(stops).unwrap_or(rest.len());
+    let label = rest[..end].trim();
+    (!label.is_empty()).then(|| label.to_string())
+}
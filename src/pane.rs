@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use eframe::egui;
+
+use crate::app::Theme;
+use crate::syntax::LineSpans;
+
+/// Which way the central panel is split for a second file.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum SplitDirection {
+    /// Side by side
+    #[default]
+    Vertical,
+    /// One above the other
+    Horizontal,
+}
+
+/// A second text file shown beside the current one, with its own zoom and scroll.
+pub(crate) struct SidePane {
+    pub(crate) path: PathBuf,
+    pub(crate) text: String,
+    /// Byte offset in `text` of each line's start
+    pub(crate) line_starts: Vec<usize>,
+    pub(crate) lines: usize,
+    /// Syntax colors per line; empty for plain text or text over the highlight threshold
+    pub(crate) spans: Vec<LineSpans>,
+    // Theme the spans were asked for, and the worker coloring them
+    theme: Option<Theme>,
+    syntax_job: Option<std::sync::mpsc::Receiver<Vec<LineSpans>>>,
+    pub(crate) zoom: f32,
+}
+
+impl SidePane {
+    pub(crate) fn new(path: PathBuf, loaded: crate::io::LoadedText, zoom: f32) -> SidePane {
+        SidePane {
+            path,
+            line_starts: loaded.line_starts,
+            lines: loaded.lines,
+            text: loaded.text,
+            spans: Vec::new(),
+            theme: None,
+            syntax_job: None,
+            zoom,
+        }
+    }
+
+    /// Color the text for `theme` on a worker thread unless it already is, leaving text over
+    /// `threshold` bytes plain. The old colors stay until the new ones arrive.
+    pub(crate) fn poll_highlight(&mut self, theme: Theme, threshold: usize, ctx: &egui::Context) {
+        if let Some(rx) = &self.syntax_job {
+            match rx.try_recv() {
+                Ok(spans) => self.spans = spans,
+                Err(std::sync::mpsc::TryRecvError::Empty) => return,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {}
+            }
+            self.syntax_job = None;
+        }
+        if self.theme == Some(theme) {
+            return;
+        }
+        self.theme = Some(theme);
+        let ext = self.path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let first_line = self.text.lines().next().unwrap_or("");
+        match crate::syntax::language_for(&ext, first_line, None).filter(|_| self.text.len() <= threshold) {
+            Some(language) => self.syntax_job = Some(crate::syntax::highlight_in_background(self.text.clone(), language, theme, ctx)),
+            None => self.spans.clear(),
+        }
+    }
+
+    /// Line `i` without its line ending.
+    pub(crate) fn line(&self, i: usize) -> &str {
+        let start = self.line_starts[i];
+        let line = &self.text[start..self.line_starts.get(i + 1).copied().unwrap_or(self.text.len())];
+        line.strip_suffix('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).unwrap_or(line)
+    }
+}
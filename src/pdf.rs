@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use eframe::egui;
+use pdfium_render::prelude::{PdfDocument, PdfRenderConfig, Pdfium};
+
+// Pages render at twice their nominal 72 dpi so they stay sharp when zoomed in a little
+const RENDER_SCALE: f32 = 2.0;
+
+/// One rendered page and how many the document has.
+pub(crate) struct Page {
+    pub(crate) image: image::RgbaImage,
+    pub(crate) count: usize,
+}
+
+pub(crate) fn is_pdf(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+/// The PDFium library, looked for next to the executable, then in the working directory, then among
+/// the system libraries. Loaded once; a failure is kept so every PDF reports the same reason.
+fn pdfium() -> Result<&'static Pdfium, String> {
+    static PDFIUM: OnceLock<Result<Pdfium, String>> = OnceLock::new();
+    PDFIUM
+        .get_or_init(|| {
+            let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+            exe_dir
+                .into_iter()
+                .chain(std::iter::once(std::path::PathBuf::from(".")))
+                .find_map(|dir| Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(&dir)).ok())
+                .or_else(|| Pdfium::bind_to_system_library().ok())
+                .map(Pdfium::new)
+                .ok_or_else(|| format!("PDF support needs the PDFium library ({}) next to the program or installed system-wide", Pdfium::pdfium_platform_library_name().to_string_lossy()))
+        })
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+fn open(path: &Path) -> Result<PdfDocument<'static>, String> {
+    pdfium()?.load_pdf_from_file(path, None).map_err(|e| format!("Failed to open PDF: {}", e))
+}
+
+/// Render page `index` (0-based) of an open document.
+fn render(document: &PdfDocument, index: usize) -> Result<Page, String> {
+    let pages = document.pages();
+    let count = pages.len() as usize;
+    if count == 0 {
+        return Err("PDF has no pages".to_string());
+    }
+    let number = u16::try_from(index.min(count - 1)).map_err(|_| format!("Page {} is out of range", index + 1))?;
+    let page = pages.get(number).map_err(|e| format!("Failed to read page {}: {}", index + 1, e))?;
+    let bitmap = page
+        .render_with_config(&PdfRenderConfig::new().scale_page_by_factor(RENDER_SCALE))
+        .map_err(|e| format!("Failed to render page {}: {}", index + 1, e))?;
+    Ok(Page { image: bitmap.as_image().into_rgba8(), count })
+}
+
+/// Render page `index` (0-based) of the PDF at `path`.
+pub(crate) fn render_page(path: &Path, index: usize) -> Result<Page, String> {
+    render(&open(path)?, index)
+}
+
+/// Renders pages of one PDF on a worker thread that keeps the document open between pages.
+/// The worker ends when this is dropped.
+pub(crate) struct Renderer {
+    requests: Sender<usize>,
+    pages: Receiver<Result<(usize, Page), String>>,
+}
+
+impl Renderer {
+    pub(crate) fn new(path: PathBuf, ctx: &egui::Context) -> Self {
+        let (requests, request_rx) = mpsc::channel::<usize>();
+        let (page_tx, pages) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            // Opened with the first request; the first page was rendered while loading
+            let mut document = None;
+            while let Ok(mut index) = request_rx.recv() {
+                // Only the newest of several quick requests is worth rendering
+                while let Ok(newer) = request_rx.try_recv() {
+                    index = newer;
+                }
+                let result = match document.get_or_insert_with(|| open(&path)) {
+                    Ok(document) => render(document, index).map(|page| (index, page)),
+                    Err(e) => Err(e.clone()),
+                };
+                if page_tx.send(result).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Renderer { requests, pages }
+    }
+
+    pub(crate) fn request(&self, index: usize) {
+        let _ = self.requests.send(index);
+    }
+
+    /// A finished page with its index, or why it failed.
+    pub(crate) fn poll(&self) -> Option<Result<(usize, Page), String>> {
+        match self.pages.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err("Rendering stopped unexpectedly".to_string())),
+        }
+    }
+}
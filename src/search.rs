@@ -1,7 +1,61 @@
-pub(crate) fn recompute_count(query: &str, text: &str) -> usize {
-    if query.is_empty() { return 0; }
-    let q = query.to_ascii_lowercase();
-    text.to_ascii_lowercase().matches(&q).count()
+use std::ops::Range;
+
+/// The find field compiled for matching: literal text or a regular expression, where `^` and `$`
+/// match at line ends. Case-insensitive unless `match_case`; `whole_word` requires word boundaries at both ends.
+/// Text is matched a line at a time, as the views mark it, so no match spans a line break.
+#[derive(Clone)]
+pub(crate) struct Pattern {
+    regex: regex::Regex,
+    literal: bool,
+}
+
+impl Pattern {
+    /// None for an empty query; an invalid regular expression gives a one-line reason.
+    pub(crate) fn new(query: &str, regex: bool, match_case: bool, whole_word: bool) -> Result<Option<Pattern>, String> {
+        if query.is_empty() { return Ok(None); }
+        let source = if regex { query.to_string() } else { regex::escape(query) };
+        // Unicode word boundaries in both modes, so letters like "é" count as part of a word
+        let source = if whole_word { format!(r"(?u:\b)(?:{})(?u:\b)", source) } else { source };
+        regex::RegexBuilder::new(&source)
+            .case_insensitive(!match_case)
+            .unicode(regex)
+            .multi_line(true)
+            .build()
+            .map(|r| Some(Pattern { regex: r, literal: !regex }))
+            // The full message repeats the pattern with a caret under the problem; the last line says what it is
+            .map_err(|e| e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string())
+    }
+
+    /// Byte ranges of the matches in `text`. Empty matches (as of `a*`) are skipped.
+    pub(crate) fn matches<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.regex.find_iter(text).map(|m| m.range()).filter(|r| !r.is_empty())
+    }
+}
+
+pub(crate) fn recompute_count(pattern: Option<&Pattern>, text: &str) -> usize {
+    pattern.map_or(0, |p| text.lines().map(|line| p.matches(line).count()).sum())
+}
+
+/// Replace every match of `pattern`, the same matches the counter counts. In regex mode `$1` and
+/// `${name}` in `replacement` refer to capture groups. Returns the new text and how many replacements were made.
+pub(crate) fn replace_all(text: &str, pattern: Option<&Pattern>, replacement: &str) -> (String, usize) {
+    let Some(pattern) = pattern else { return (text.to_string(), 0) };
+    let mut out = String::with_capacity(text.len());
+    let mut count = 0;
+    for line in text.split_inclusive('\n') {
+        // Matched without its line ending, like `str::lines` gives it to the views
+        let body = line.strip_suffix('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).unwrap_or(line);
+        let mut last = 0;
+        for caps in pattern.regex.captures_iter(body) {
+            let Some(m) = caps.get(0).filter(|m| !m.is_empty()) else { continue };
+            out.push_str(&body[last..m.start()]);
+            if pattern.literal { out.push_str(replacement) } else { caps.expand(replacement, &mut out) }
+            last = m.end();
+            count += 1;
+        }
+        out.push_str(&line[last..]);
+    }
+    (out, count)
 }
 
 pub(crate) fn next_index(current: usize, count: usize) -> usize {
@@ -12,19 +66,124 @@ pub(crate) fn prev_index(current: usize, count: usize) -> usize {
     if count == 0 { 0 } else if current == 0 { count - 1 } else { current - 1 }
 }
 
-pub(crate) fn find_target_line(text: &str, query: &str, target_idx: usize) -> Option<usize> {
-    if query.is_empty() { return None; }
-    let q = query.to_ascii_lowercase();
-    let mut global = 0usize;
+pub(crate) fn find_target_line(text: &str, pattern: Option<&Pattern>, target_idx: usize) -> Option<usize> {
+    let pattern = pattern?;
+    let mut seen = 0;
     for (i, line) in text.lines().enumerate() {
-        let mut rest = line.to_ascii_lowercase();
-        while let Some(pos) = rest.find(&q) {
-            if global == target_idx { return Some(i); }
-            global += 1;
-            let next = pos + q.len();
-            if next >= rest.len() { break; }
-            rest = rest[next..].to_string();
+        seen += pattern.matches(line).count();
+        if seen > target_idx {
+            return Some(i);
         }
     }
     None
 }
+
+// Files bigger than this are skipped when searching a folder
+const MAX_FOLDER_FILE_BYTES: u64 = 10_000_000;
+// Stop collecting once this many lines have matched
+pub(crate) const MAX_FOLDER_HITS: usize = 5_000;
+const MAX_SNIPPET_CHARS: usize = 200;
+
+/// A line of a file in the searched folder that matches the query.
+pub(crate) struct FolderHit {
+    pub(crate) path: std::path::PathBuf,
+    /// 0-based
+    pub(crate) line: usize,
+    pub(crate) snippet: String,
+}
+
+/// What the folder search worker reports as it goes.
+pub(crate) enum FolderEvent {
+    Hits(Vec<FolderHit>),
+    /// Files searched so far, sent every so often
+    Progress(usize),
+    Done(usize),
+}
+
+/// Search every text file under `dir` line by line, in path order, sending hits per file.
+/// Binary files (a NUL byte near the start) and very large ones are skipped, as are hidden entries
+/// unless `include_hidden`. Stops early once `tx` is dropped or `MAX_FOLDER_HITS` lines have matched.
+pub(crate) fn search_folder(dir: &std::path::Path, pattern: &Pattern, include_hidden: bool, tx: &std::sync::mpsc::Sender<FolderEvent>) {
+    let mut pending = vec![dir.to_path_buf()];
+    let (mut files, mut hits) = (0, 0);
+    while let Some(dir) = pending.pop() {
+        let mut entries: Vec<_> = std::fs::read_dir(&dir).into_iter().flatten().filter_map(|e| e.ok()).collect();
+        entries.sort_by_key(|e| e.file_name());
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let path = entry.path();
+            if !include_hidden && crate::io::is_hidden(&path) {
+                continue;
+            }
+            let Ok(kind) = entry.file_type() else { continue };
+            if kind.is_dir() {
+                subdirs.push(path);
+                continue;
+            }
+            if !kind.is_file() || entry.metadata().map_or(true, |m| m.len() > MAX_FOLDER_FILE_BYTES) {
+                continue;
+            }
+            files += 1;
+            if files % 100 == 0 && tx.send(FolderEvent::Progress(files)).is_err() {
+                return;
+            }
+            let Ok(bytes) = std::fs::read(&path) else { continue };
+            if bytes[..bytes.len().min(8000)].contains(&0) {
+                continue;
+            }
+            let text = String::from_utf8_lossy(&bytes);
+            let mut found = Vec::new();
+            for (line, content) in text.lines().enumerate() {
+                if pattern.matches(content).next().is_some() {
+                    let snippet = content.trim();
+                    let snippet = match snippet.char_indices().nth(MAX_SNIPPET_CHARS) {
+                        Some((cut, _)) => format!("{}…", &snippet[..cut]),
+                        None => snippet.to_string(),
+                    };
+                    found.push(FolderHit { path: path.clone(), line, snippet });
+                    hits += 1;
+                    if hits >= MAX_FOLDER_HITS {
+                        break;
+                    }
+                }
+            }
+            if !found.is_empty() && tx.send(FolderEvent::Hits(found)).is_err() {
+                return;
+            }
+            if hits >= MAX_FOLDER_HITS {
+                let _ = tx.send(FolderEvent::Done(files));
+                return;
+            }
+        }
+        // Files of a folder come before its subfolders, which are popped in name order
+        pending.extend(subdirs.into_iter().rev());
+    }
+    let _ = tx.send(FolderEvent::Done(files));
+}
+
+/// Most matches collected in an indexed large file; the count stops there.
+pub(crate) const MAX_LARGE_MATCHES: usize = 1_000_000;
+
+/// Search an indexed large file from line `first` on, sending the line of every match (one entry
+/// per match) in batches. Stops early once `tx` is dropped or `MAX_LARGE_MATCHES` have been found.
+pub(crate) fn search_large_file(index: &crate::io::LineIndex, pattern: &Pattern, first: usize, tx: &std::sync::mpsc::Sender<Vec<usize>>) {
+    let mut batch = Vec::new();
+    let mut found = 0;
+    let _ = index.for_each_line(first, |i, line| {
+        for _ in pattern.matches(&line) {
+            batch.push(i);
+            found += 1;
+            if found >= MAX_LARGE_MATCHES {
+                return false;
+            }
+        }
+        // Report every so often so the count climbs while the search runs
+        if i % 100_000 == 99_999 && !batch.is_empty() {
+            return tx.send(std::mem::take(&mut batch)).is_ok();
+        }
+        true
+    });
+    if !batch.is_empty() {
+        let _ = tx.send(batch);
+    }
+}
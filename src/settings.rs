@@ -1,25 +1,135 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::app::FileViewState;
 
 pub(crate) fn settings_path() -> Option<PathBuf> {
     directories::ProjectDirs::from("", "", "gemini-file-viewer")
         .map(|dirs| dirs.config_dir().join("settings.json"))
 }
 
-pub(crate) fn load_settings_from_disk() -> Option<crate::app::FileViewerApp> {
-    let path = settings_path()?;
-    let data = fs::read(&path).ok()?;
-    serde_json::from_slice::<crate::app::FileViewerApp>(&data).ok()
+/// Saved settings, or None if there are none. A file that no longer parses is moved aside to
+/// `settings.json.bad` so defaults can take over without losing it for inspection; the error
+/// says so, for showing to the user.
+pub(crate) fn load_settings_from_disk() -> Result<Option<crate::app::FileViewerApp>, String> {
+    let Some(path) = settings_path() else { return Ok(None) };
+    let Ok(data) = fs::read(&path) else { return Ok(None) };
+    serde_json::from_slice::<crate::app::FileViewerApp>(&data).map(Some).map_err(|e| {
+        let bad = path.with_extension("json.bad");
+        let _ = fs::rename(&path, &bad);
+        format!("Settings were unreadable ({}); using defaults. The old file is at {}", e, bad.display())
+    })
+}
+
+/// Mark settings as changed; `flush_settings` writes them at most once per `SAVE_INTERVAL`.
+pub(crate) fn mark_settings_dirty(app: &crate::app::FileViewerApp) {
+    app.settings_dirty.set(true);
+    app.settings_changed.set(true);
+}
+
+/// Coalesces bursts of changes, e.g. while a slider is dragged, into one write.
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Write pending settings changes if the last write was long enough ago; otherwise returns how
+/// long until it will be. `force` writes right away, e.g. on exit.
+pub(crate) fn flush_settings(app: &mut crate::app::FileViewerApp, force: bool) -> Option<Duration> {
+    if !app.settings_dirty.get() {
+        return None;
+    }
+    if app.secondary {
+        app.settings_dirty.set(false);
+        return None;
+    }
+    if !force && let Some(last) = app.settings_written {
+        let since = last.elapsed();
+        if since < SAVE_INTERVAL {
+            return Some(SAVE_INTERVAL - since);
+        }
+    }
+    app.settings_dirty.set(false);
+    app.settings_written = Some(Instant::now());
+    // The query only goes to disk when it's meant to survive a restart
+    let query = (!app.remember_search).then(|| std::mem::take(&mut app.search_query));
+    write_settings_to_disk(app);
+    if let Some(query) = query {
+        app.search_query = query;
+    }
+    None
 }
 
-pub(crate) fn save_settings_to_disk(app: &crate::app::FileViewerApp) {
+fn write_settings_to_disk(app: &crate::app::FileViewerApp) {
     if let Some(path) = settings_path() {
         if let Some(parent) = path.parent() {
             let _ = fs::create_dir_all(parent);
         }
         if let Ok(s) = serde_json::to_vec_pretty(app) {
-            let _ = fs::write(path, s);
+            let _ = write_atomic(&path, &s);
         }
     }
 }
 
+/// Write through a temporary file renamed over `path`, so a crash mid-write leaves the old file intact.
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)
+}
+
+
+/// A named set of open files and the one that was active.
+#[derive(serde::Deserialize, serde::Serialize, Default, Clone)]
+pub(crate) struct Session {
+    pub(crate) files: Vec<FileViewState>,
+    pub(crate) active: usize,
+}
+
+fn sessions_dir() -> Option<PathBuf> {
+    settings_path()?.parent().map(|dir| dir.join("sessions"))
+}
+
+fn session_path(name: &str) -> Option<PathBuf> {
+    let safe: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
+        .collect();
+    if safe.is_empty() { return None; }
+    sessions_dir().map(|dir| dir.join(format!("{}.json", safe)))
+}
+
+pub(crate) fn list_sessions() -> Vec<String> {
+    let Some(dir) = sessions_dir() else { return Vec::new() };
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("json"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+pub(crate) fn save_session(name: &str, session: &Session) -> Result<(), String> {
+    let path = session_path(name).ok_or("Invalid session name")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create sessions folder: {}", e))?;
+    }
+    let data = serde_json::to_vec_pretty(session).map_err(|e| format!("Failed to encode session: {}", e))?;
+    write_atomic(&path, &data).map_err(|e| format!("Failed to save session: {}", e))
+}
+
+pub(crate) fn load_session(name: &str) -> Result<Session, String> {
+    let path = session_path(name).ok_or("Invalid session name")?;
+    let data = fs::read(&path).map_err(|e| format!("Failed to read session: {}", e))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Session file is corrupt: {}", e))
+}
+
+pub(crate) fn delete_session(name: &str) -> Result<(), String> {
+    let path = session_path(name).ok_or("Invalid session name")?;
+    fs::remove_file(path).map_err(|e| format!("Failed to delete session: {}", e))
+}
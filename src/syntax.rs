@@ -0,0 +1,156 @@
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use eframe::egui::{text::LayoutJob, Color32, FontId};
+use syntect::highlighting::{Color, Highlighter, HighlightState, RangedHighlightIterator, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use crate::highlight::Palette;
+
+/// Colored ranges of one line, relative to the line start.
+pub(crate) type LineSpans = Vec<(Range<usize>, Color32)>;
+
+/// `highlight` on a worker thread, repainting once the spans are ready.
+pub(crate) fn highlight_in_background(text: String, language: &'static str, theme: crate::app::Theme, ctx: &eframe::egui::Context) -> std::sync::mpsc::Receiver<Vec<LineSpans>> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let _ = tx.send(highlight(&text, language, &Palette::for_theme(theme)));
+        ctx.request_repaint();
+    });
+    rx
+}
+
+// Stands in for "no scope matched": such text keeps the view's own text color
+const BASE: Color = Color { r: 0, g: 0, b: 0, a: 0 };
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Names of the languages offered for a manual override, sorted.
+pub(crate) fn languages() -> Vec<&'static str> {
+    let mut names: Vec<&str> = syntaxes()
+        .syntaxes()
+        .iter()
+        .filter(|s| !s.hidden && s.name != "Plain Text")
+        .map(|s| s.name.as_str())
+        .collect();
+    names.sort_by_key(|n| n.to_lowercase());
+    names.dedup();
+    names
+}
+
+/// The language for a file: the override when set, else by extension, else by a first line such as `#!/bin/sh`.
+pub(crate) fn language_for(ext: &str, first_line: &str, override_name: Option<&str>) -> Option<&'static str> {
+    let set = syntaxes();
+    let syntax = match override_name {
+        Some(name) => set.find_syntax_by_name(name),
+        None => set.find_syntax_by_extension(ext).or_else(|| set.find_syntax_by_first_line(first_line)),
+    }?;
+    (syntax.name != "Plain Text").then_some(syntax.name.as_str())
+}
+
+fn theme_for(palette: &Palette) -> Theme {
+    let color = |c: Color32| Color { r: c.r(), g: c.g(), b: c.b(), a: 255 };
+    let rules = [
+        ("comment, punctuation.definition.comment", palette.comment),
+        ("string, punctuation.definition.string", palette.string),
+        ("constant.numeric", palette.number),
+        ("constant.language, support.constant", palette.literal),
+        ("keyword, storage, entity.name.tag", palette.keyword),
+    ];
+    Theme {
+        settings: ThemeSettings { foreground: Some(BASE), ..Default::default() },
+        scopes: rules
+            .into_iter()
+            .filter_map(|(scope, c)| {
+                let scope = ScopeSelectors::from_str(scope).ok()?;
+                Some(ThemeItem { scope, style: StyleModifier { foreground: Some(color(c)), ..Default::default() } })
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Colored spans for every line of `text` (one entry per `str::lines` line) in `language`.
+/// Runs of the base color are left out, so most lines hold only a few spans.
+pub(crate) fn highlight(text: &str, language: &str, palette: &Palette) -> Vec<LineSpans> {
+    let set = syntaxes();
+    let Some(syntax): Option<&SyntaxReference> = set.find_syntax_by_name(language) else { return Vec::new() };
+    let theme = theme_for(palette);
+    let highlighter = Highlighter::new(&theme);
+    let mut parse = ParseState::new(syntax);
+    let mut state = HighlightState::new(&highlighter, ScopeStack::new());
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(text) {
+        let mut spans = LineSpans::new();
+        // A line the grammar can't parse stays uncolored; the parser state carries on regardless
+        if let Ok(ops) = parse.parse_line(line, set) {
+            for (style, _, range) in RangedHighlightIterator::new(&mut state, &ops, line, &highlighter) {
+                let c = style.foreground;
+                if c != BASE {
+                    spans.push((range, Color32::from_rgb(c.r, c.g, c.b)));
+                }
+            }
+        }
+        lines.push(spans);
+    }
+    lines
+}
+
+/// Append `line` colored by its `spans`, with search matches marked, expanding tabs to `tab_width` stops when given.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn append_line(
+    job: &mut LayoutJob,
+    line: &str,
+    spans: &LineSpans,
+    tab_width: Option<usize>,
+    query: Option<&crate::search::Pattern>,
+    font_id: FontId,
+    base_color: Color32,
+    current_idx: usize,
+    counter: &mut usize,
+) {
+    let mut col = 0;
+    let mut append = |job: &mut LayoutJob, range: Range<usize>, color: Color32| {
+        let Some(part) = line.get(range).filter(|p| !p.is_empty()) else { return };
+        let part = match tab_width {
+            Some(width) => expand_tabs_from(part, width, &mut col),
+            None => std::borrow::Cow::Borrowed(part),
+        };
+        crate::highlight::append_with_search(job, &part, font_id.clone(), color, query, current_idx, counter);
+    };
+    let mut pos = 0;
+    for (range, color) in spans {
+        // Spans also cover the line ending, which isn't part of `line`
+        let (start, end) = (range.start.min(line.len()), range.end.min(line.len()));
+        append(job, pos..start, base_color);
+        append(job, start..end, *color);
+        pos = end;
+    }
+    append(job, pos..line.len(), base_color);
+}
+
+/// Like `highlight::expand_tabs`, but continuing from column `col` of a line split into parts.
+fn expand_tabs_from<'a>(part: &'a str, width: usize, col: &mut usize) -> std::borrow::Cow<'a, str> {
+    if !part.contains('\t') {
+        *col += part.chars().count();
+        return std::borrow::Cow::Borrowed(part);
+    }
+    let mut out = String::with_capacity(part.len() + width * 4);
+    for c in part.chars() {
+        if c == '\t' {
+            let pad = width - *col % width;
+            out.extend(std::iter::repeat_n(' ', pad));
+            *col += pad;
+        } else {
+            out.push(c);
+            *col += 1;
+        }
+    }
+    std::borrow::Cow::Owned(out)
+}
@@ -0,0 +1,62 @@
+use std::cmp::Ordering;
+
+/// A CSV/TSV file split into cells, with the row order it is shown in.
+pub(crate) struct Table {
+    pub(crate) header: Vec<String>,
+    pub(crate) rows: Vec<Vec<String>>,
+    /// Indexes into `rows` in display order
+    pub(crate) order: Vec<usize>,
+    /// Sorted column and whether ascending
+    pub(crate) sort: Option<(usize, bool)>,
+}
+
+/// Field delimiter for table files, by extension.
+pub(crate) fn delimiter(ext: &str) -> Option<u8> {
+    match ext {
+        "csv" => Some(b','),
+        "tsv" | "tab" => Some(b'\t'),
+        _ => None,
+    }
+}
+
+/// Parse `text` with its first record as the header. Rows of differing lengths make the file
+/// malformed, as does anything else the parser rejects; it is then shown as plain text.
+pub(crate) fn parse(text: &str, delimiter: u8) -> Result<Table, String> {
+    let mut reader = csv::ReaderBuilder::new().delimiter(delimiter).has_headers(true).from_reader(text.as_bytes());
+    let header: Vec<String> = reader.headers().map_err(|e| e.to_string())?.iter().map(str::to_string).collect();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+    if header.is_empty() {
+        return Err("no columns".to_string());
+    }
+    let order = (0..rows.len()).collect();
+    Ok(Table { header, rows, order, sort: None })
+}
+
+impl Table {
+    /// Sort rows by `column`. Columns of numbers sort numerically; empty cells go last either way.
+    pub(crate) fn sort_by(&mut self, column: usize, ascending: bool) {
+        self.sort = Some((column, ascending));
+        let cells: Vec<&str> = self.rows.iter().map(|row| row.get(column).map(|s| s.trim()).unwrap_or("")).collect();
+        // Numeric or not is decided once for the whole column, so the comparison is a total order
+        // whatever mix of cells it holds
+        let numbers: Option<Vec<f64>> = cells.iter().map(|c| if c.is_empty() { Some(0.0) } else { c.parse().ok() }).collect();
+        let words: Vec<String> = if numbers.is_some() { Vec::new() } else { cells.iter().map(|c| c.to_lowercase()).collect() };
+        self.order.sort_by(|&a, &b| {
+            match (cells[a].is_empty(), cells[b].is_empty()) {
+                (true, true) => return Ordering::Equal,
+                (true, false) => return Ordering::Greater,
+                (false, true) => return Ordering::Less,
+                _ => {}
+            }
+            let ord = match &numbers {
+                Some(numbers) => numbers[a].total_cmp(&numbers[b]),
+                None => words[a].cmp(&words[b]),
+            };
+            if ascending { ord } else { ord.reverse() }
+        });
+    }
+}
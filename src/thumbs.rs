@@ -0,0 +1,87 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use eframe::egui;
+
+/// Longest side of a thumbnail, in pixels.
+pub(crate) const THUMB_SIZE: u32 = 96;
+
+// A thumbnail from the worker, or None for an image it couldn't read
+type Made = (PathBuf, Option<egui::ColorImage>);
+
+/// A thumbnail of the image at `path`, its longest side `size` pixels. Call it off the UI thread.
+pub(crate) fn make(path: &Path, size: u32, max_bytes: u64, shared: bool) -> Result<egui::ColorImage, String> {
+    if std::fs::metadata(path).is_ok_and(|m| m.len() > max_bytes) {
+        return Err("Too large to preview".to_string());
+    }
+    let rgba = crate::io::load_image(path, shared)?;
+    Ok(crate::imaging::to_color_image(&crate::imaging::thumbnail(&rgba, size)))
+}
+
+/// Thumbnails of the images in a folder, made on a worker thread and kept while that folder is shown.
+#[derive(Default)]
+pub(crate) struct Thumbnails {
+    /// Finished thumbnails; None for images that couldn't be read
+    done: HashMap<PathBuf, Option<egui::TextureHandle>>,
+    // The folder `done` holds thumbnails of
+    folder: Option<PathBuf>,
+    // The folder being worked on and the worker's results
+    working: Option<(PathBuf, Receiver<Made>)>,
+    /// The image the strip was last scrolled to
+    pub(crate) focused: Option<PathBuf>,
+}
+
+impl Thumbnails {
+    /// Make thumbnails for those of `paths` (the images of `dir`) that have none yet, from the one at
+    /// `first` on and then wrapping around. A batch still running for another folder is abandoned.
+    pub(crate) fn request(&mut self, dir: &Path, paths: &[PathBuf], first: usize, max_bytes: u64, shared: bool, ctx: &egui::Context) {
+        if self.working.as_ref().is_some_and(|(working, _)| working == dir) {
+            return;
+        }
+        if self.folder.as_deref() != Some(dir) {
+            // Another folder's thumbnails are dropped rather than kept for the session
+            let wanted: HashSet<&PathBuf> = paths.iter().collect();
+            self.done.retain(|path, _| wanted.contains(path));
+            self.folder = Some(dir.to_path_buf());
+        }
+        let (earlier, rest) = paths.split_at(first.min(paths.len()));
+        let missing: Vec<PathBuf> = rest.iter().chain(earlier).filter(|p| !self.done.contains_key(*p)).cloned().collect();
+        if missing.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            for path in missing {
+                let thumb = make(&path, THUMB_SIZE, max_bytes, shared).ok();
+                if tx.send((path, thumb)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        self.working = Some((dir.to_path_buf(), rx));
+    }
+
+    /// Upload what the worker has made since the last frame.
+    pub(crate) fn poll(&mut self, ctx: &egui::Context) {
+        let Some((_, rx)) = &self.working else { return };
+        loop {
+            match rx.try_recv() {
+                Ok((path, image)) => {
+                    let texture = image.map(|image| ctx.load_texture(format!("thumb:{}", path.display()), image, egui::TextureOptions::LINEAR));
+                    self.done.insert(path, texture);
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+        self.working = None;
+    }
+
+    /// The thumbnail of `path`: None while still being made, Some(None) if the image couldn't be read.
+    pub(crate) fn get(&self, path: &Path) -> Option<Option<&egui::TextureHandle>> {
+        self.done.get(path).map(Option::as_ref)
+    }
+}
@@ -3,13 +3,12 @@ use eframe::egui;
 use eframe::egui::Stroke;
 
 pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ctx: &egui::Context, file_to_load: &mut Option<PathBuf>) {
-    use rfd::FileDialog;
     use egui::RichText;
 
     // Rainbow helpers (active only for Allison theme)
     let is_allison = matches!(app.theme, crate::app::Theme::Allison);
     let mut rainbow_idx: usize = 0;
-    let mut next_color = |idx: &mut usize| {
+    let next_color = |idx: &mut usize| {
         let palette = [
             egui::Color32::from_rgb(239, 83, 80),   // red
             egui::Color32::from_rgb(255, 167, 38),  // orange
@@ -23,7 +22,7 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
         *idx += 1;
         c
     };
-    let mut rainbow_button = |ui: &mut egui::Ui, label: &str, idx: &mut usize| {
+    let rainbow_button = |ui: &mut egui::Ui, label: &str, idx: &mut usize| {
         let bg = next_color(idx);
         let text_color = if bg == egui::Color32::from_rgb(255, 238, 88) { egui::Color32::BLACK } else { egui::Color32::WHITE };
         ui.add(egui::Button::new(RichText::new(label).strong().color(text_color)).fill(bg).stroke(Stroke::new(1.0, bg.gamma_multiply(0.5))))
@@ -32,16 +31,19 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
     if (if is_allison { rainbow_button(ui, "📂 Open", &mut rainbow_idx) } else { ui.button(RichText::new("📂 Open").strong()) })
         .on_hover_text("Open a file (Ctrl+O)")
         .clicked()
-        && let Some(path) = FileDialog::new()
-            .add_filter("All Supported", &["txt","rs","py","toml","md","json","js","html","css","png","jpg","jpeg","gif","bmp","webp"])
-            .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
-            .add_filter("Text/Source", &["txt","rs","py","toml","md","json","js","html","css"])
-            .pick_file()
+        && let Some(path) = crate::io::open_dialog().pick_file()
     {
+        app.tab_for_new_file();
         *file_to_load = Some(path);
     }
 
-    if is_allison {
+    if ui.button("🗔 New Window").on_hover_text("Open another viewer window (Ctrl+Shift+N)").clicked() {
+        app.new_window_requested = true;
+    }
+
+    if app.private_mode {
+        // Nothing is recorded, so there is no Recent menu to show
+    } else if is_allison {
         let bg = next_color(&mut rainbow_idx);
         let text_color = if bg == egui::Color32::from_rgb(255, 238, 88) { egui::Color32::BLACK } else { egui::Color32::WHITE };
         let id = ui.make_persistent_id("recent_menu");
@@ -53,37 +55,54 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
             &resp,
             egui::popup::PopupCloseBehavior::CloseOnClickOutside,
             |ui: &mut egui::Ui| {
-            ui.set_min_width(480.0);
-            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-            if app.recent_files.is_empty() { ui.label("(empty)"); }
-            for file in app.recent_files.clone().into_iter().rev() {
-                let name = file.file_name().and_then(|s| s.to_str()).unwrap_or("(unknown)");
-                let parent = file.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
-                let btn = egui::RichText::new(name).strong();
-                if ui.button(btn).on_hover_text(parent.clone()).clicked() { *file_to_load = Some(file); ui.memory_mut(|m| m.close_popup()); }
-                if !parent.is_empty() { ui.label(egui::RichText::new(parent).weak().small()); }
-            }
-            ui.separator();
-            if ui.button("🧹 Clear Recent").clicked() { app.recent_files.clear(); ui.memory_mut(|m| m.close_popup()); }
+                if recent_menu(ui, app, file_to_load) { ui.memory_mut(|m| m.close_popup()); }
             }
         );
     } else {
         ui.menu_button(egui::RichText::new("🕘 Recent").strong(), |ui| {
-            ui.set_min_width(480.0);
-            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-            if app.recent_files.is_empty() { ui.label("(empty)"); }
-            for file in app.recent_files.clone().into_iter().rev() {
-                let name = file.file_name().and_then(|s| s.to_str()).unwrap_or("(unknown)");
-                let parent = file.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
-                let btn = egui::RichText::new(name).strong();
-                if ui.button(btn).on_hover_text(parent.clone()).clicked() { *file_to_load = Some(file); ui.close_menu(); }
-                if !parent.is_empty() { ui.label(egui::RichText::new(parent).weak().small()); }
-            }
-            ui.separator();
-            if ui.button("🧹 Clear Recent").clicked() { app.recent_files.clear(); ui.close_menu(); }
+            if recent_menu(ui, app, file_to_load) { ui.close_menu(); }
         });
     }
 
+    ui.menu_button(egui::RichText::new("🗂 Sessions").strong(), |ui| {
+        ui.set_min_width(260.0);
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut app.session_name_input).hint_text("Session name").desired_width(160.0));
+            let can_save = app.current_path.is_some() && !app.session_name_input.trim().is_empty();
+            if ui.add_enabled(can_save, egui::Button::new("💾 Save")).on_hover_text("Save the open file and its view state").clicked() {
+                let name = app.session_name_input.trim().to_string();
+                app.save_session(&name);
+                ui.close_menu();
+            }
+        });
+        ui.separator();
+        let sessions = crate::settings::list_sessions();
+        if sessions.is_empty() { ui.label("(no saved sessions)"); }
+        for name in sessions {
+            ui.horizontal(|ui| {
+                if ui.button(egui::RichText::new(&name).strong()).on_hover_text("Restore this session").clicked() {
+                    app.load_session(&name, ctx);
+                    ui.close_menu();
+                }
+                if ui.small_button("🗑").on_hover_text("Delete session").clicked()
+                    && let Err(e) = crate::settings::delete_session(&name)
+                {
+                    app.error_message = Some(e);
+                }
+            });
+        }
+    });
+
+    if (if is_allison { rainbow_button(ui, "📋 Paste", &mut rainbow_idx) } else { ui.button("📋 Paste") })
+        .on_hover_text("View the clipboard image or text (Ctrl+V for text)")
+        .clicked()
+    {
+        app.paste_from_clipboard(None, ctx);
+    }
+    let printable = matches!(app.content, Some(crate::app::Content::Text(_) | crate::app::Content::LargeText(_) | crate::app::Content::Image(_)));
+    if ui.add_enabled(printable, egui::Button::new("🖨 Print")).on_hover_text("Print the text or image (Ctrl+P)").clicked() {
+        app.start_print();
+    }
     ui.separator();
     let prev_dark = app.dark_mode;
     let prev_lines = app.show_line_numbers;
@@ -103,7 +122,7 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
             style.visuals = visuals;
             ui.set_style(style);
             let mut theme_changed = false;
-            egui::ComboBox::from_id_source("theme_combo")
+            egui::ComboBox::from_id_salt("theme_combo")
                 .selected_text(egui::RichText::new(format!("🎨 {}", app.theme.name())).color(text_color))
                 .show_ui(ui, |ui| {
                     use crate::app::Theme;
@@ -118,6 +137,11 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
                 });
             if theme_changed { app.follow_system_theme = false; }
             ui.add_space(6.0);
+            if ui.add(egui::Button::new(egui::RichText::new("🌓").color(text_color)).fill(bg)).on_hover_text("Toggle light/dark (Ctrl+D)").clicked() {
+                app.set_dark_mode(!app.dark_mode);
+                app.follow_system_theme = false;
+            }
+            ui.add_space(6.0);
             ui.checkbox(&mut app.follow_system_theme, egui::RichText::new("Follow system").color(text_color));
             ui.add_space(6.0);
             if ui.add(egui::Button::new(egui::RichText::new("🎛 Theme").strong().color(text_color)).fill(bg).stroke(Stroke::new(1.0, bg.gamma_multiply(0.5)))).on_hover_text("Open Theme Editor").clicked() {
@@ -126,7 +150,7 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
         });
     } else {
         let mut theme_changed = false;
-        egui::ComboBox::from_id_source("theme_combo")
+        egui::ComboBox::from_id_salt("theme_combo")
             .selected_text(format!("🎨 {}", app.theme.name()))
             .show_ui(ui, |ui| {
                 use crate::app::Theme;
@@ -140,9 +164,16 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
                 theme_changed |= ui.selectable_value(&mut app.theme, Theme::Sepia, "Sepia").changed();
             });
         if theme_changed { app.follow_system_theme = false; }
+        if ui.button("🌓").on_hover_text("Toggle light/dark (Ctrl+D)").clicked() {
+            app.set_dark_mode(!app.dark_mode);
+            app.follow_system_theme = false;
+        }
         ui.checkbox(&mut app.follow_system_theme, "Follow system");
         if ui.button("🎛 Theme").on_hover_text("Open Theme Editor").clicked() { app.show_theme_editor = true; }
     }
+    if (if is_allison { rainbow_button(ui, "⚙ Settings", &mut rainbow_idx) } else { ui.button("⚙ Settings") }).on_hover_text("Open Settings").clicked() {
+        app.show_settings = true;
+    }
     // Accent picker removed per request
 
     // Always hide Dark Mode (since Light/Dark themes exist)
@@ -152,8 +183,10 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
         let text_color = if bg == egui::Color32::from_rgb(255, 238, 88) { egui::Color32::BLACK } else { egui::Color32::WHITE };
         egui::Frame::default().fill(bg).stroke(Stroke::new(1.0, bg.gamma_multiply(0.5))).show(ui, |ui| {
             let before = app.show_line_numbers;
-            if ui.checkbox(&mut app.show_line_numbers, "").on_hover_text("Toggle line numbers (Ctrl+L)").changed() {
-                if app.show_line_numbers != before { crate::settings::save_settings_to_disk(app); }
+            if ui.checkbox(&mut app.show_line_numbers, "").on_hover_text("Toggle line numbers (Ctrl+L)").changed()
+                && app.show_line_numbers != before
+            {
+                crate::settings::mark_settings_dirty(app);
             }
             ui.label(egui::RichText::new("Line Numbers").color(text_color));
         });
@@ -161,62 +194,1505 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
         ui.checkbox(&mut app.show_line_numbers, "Line Numbers").on_hover_text("Toggle line numbers (Ctrl+L)");
     }
     if app.dark_mode != prev_dark {
-        // Keep theme synced with quick toggle
-        app.theme = if app.dark_mode { crate::app::Theme::Dark } else { crate::app::Theme::Light };
         app.apply_theme(ctx);
     }
     if app.dark_mode != prev_dark || app.show_line_numbers != prev_lines {
-        crate::settings::save_settings_to_disk(app);
+        crate::settings::mark_settings_dirty(app);
     }
     // Applying selected theme if changed via combobox
     ui.ctx().style_mut(|_| {}); // force borrow split
     if app.dark_mode != app.theme.is_dark() {
         app.dark_mode = app.theme.is_dark();
         app.apply_theme(ctx);
-        crate::settings::save_settings_to_disk(app);
+        crate::settings::mark_settings_dirty(app);
     }
     ui.separator();
 
     if (if is_allison { rainbow_button(ui, "🧹 Clear", &mut rainbow_idx) } else { ui.button("🗑️ Clear") }).on_hover_text("Clear current view").clicked() {
-        app.content = None;
-        app.current_path = None;
-        app.error_message = None;
+        app.clear_view();
+    }
+    if ui.selectable_label(app.show_folder_search, "🔎 In Folder").on_hover_text("Search the files of a folder (Ctrl+Shift+F)").clicked() {
+        app.show_folder_search = !app.show_folder_search;
+    }
+    if ui.button("📖 Open beside…").on_hover_text("Show a second text file next to this one (text only)").clicked() {
+        app.open_beside(ctx);
+    }
+    if app.can_reload()
+        && ui.selectable_label(app.auto_reload, "🔄 Auto-reload").on_hover_text("Reload the file whenever it changes on disk").clicked()
+    {
+        app.auto_reload = !app.auto_reload;
+        crate::settings::mark_settings_dirty(app);
+    }
+    if app.is_archive_entry_view()
+        && let Some(archive) = app.archive_path.clone()
+        && (if is_allison { rainbow_button(ui, "⬆ Archive", &mut rainbow_idx) } else { ui.button("⬆ Archive") })
+            .on_hover_text("Back to the archive listing")
+            .clicked()
+    {
+        *file_to_load = Some(archive);
     }
 
     if matches!(app.content, Some(crate::app::Content::Image(_))) {
         ui.separator();
         let prev_fit = app.image_fit;
-        if let Some(cur) = app.current_path.clone() {
-            if (if is_allison { rainbow_button(ui, "Prev", &mut rainbow_idx) } else { ui.button("Prev") }).clicked() {
-                if let Some(prev) = crate::io::neighbor_image(&cur, false) {
-                    *file_to_load = Some(prev);
-                }
+        if app.current_path.is_some() {
+            // A PDF steps through its pages instead, with the page buttons below
+            if app.pdf.is_none()
+                && (if is_allison { rainbow_button(ui, "Prev", &mut rainbow_idx) } else { ui.button("Prev") }).clicked()
+                && let Some(prev) = app.neighbor(false)
+            {
+                *file_to_load = Some(prev);
             }
-            if (if is_allison { rainbow_button(ui, "Next", &mut rainbow_idx) } else { ui.button("Next") }).clicked() {
-                if let Some(next) = crate::io::neighbor_image(&cur, true) {
-                    *file_to_load = Some(next);
-                }
+            if app.pdf.is_none()
+                && (if is_allison { rainbow_button(ui, "Next", &mut rainbow_idx) } else { ui.button("Next") }).clicked()
+                && let Some(next) = app.neighbor(true)
+            {
+                *file_to_load = Some(next);
+            }
+            if app.pdf.is_none() && ui.selectable_label(app.slideshow.is_some(), "🎞 Slideshow").on_hover_text(format!("Step through the folder's images every {:.1} s (Space pauses, Esc stops)", app.slideshow_secs)).clicked() {
+                app.toggle_slideshow(ctx);
+            }
+            if app.pdf.is_none() && ui.selectable_label(app.show_thumbnails, "🖼 Thumbnails").on_hover_text("Show the folder's images along the bottom; click one to open it").clicked() {
+                app.show_thumbnails = !app.show_thumbnails;
+                crate::settings::mark_settings_dirty(app);
+            }
+            if let Some(paused) = app.slideshow.as_ref().map(|s| s.paused)
+                && ui.button(if paused { "▶" } else { "⏸" }).on_hover_text(if paused { "Resume slideshow (Space)" } else { "Pause slideshow (Space)" }).clicked()
+            {
+                app.toggle_slideshow_pause();
             }
             ui.separator();
         }
-        ui.checkbox(&mut app.image_fit, "Fit to Window").on_hover_text("Scale image to fit the window");
-        if app.image_fit != prev_fit { crate::settings::save_settings_to_disk(app); }
-        if (if is_allison { rainbow_button(ui, "🔍−", &mut rainbow_idx) } else { ui.button("🔍−") }).on_hover_text("Zoom out").clicked() { app.image_fit = false; app.image_zoom = (app.image_zoom / 1.10).clamp(0.1, 6.0); }
-        if (if is_allison { rainbow_button(ui, "🔍+", &mut rainbow_idx) } else { ui.button("🔍+") }).on_hover_text("Zoom in").clicked() { app.image_fit = false; app.image_zoom = (app.image_zoom * 1.10).clamp(0.1, 6.0); }
-        if (if is_allison { rainbow_button(ui, "100%", &mut rainbow_idx) } else { ui.button("100%") }).on_hover_text("Reset zoom").clicked() { app.image_fit = false; app.image_zoom = 1.0; }
-    } else if matches!(app.content, Some(crate::app::Content::Text(_))) {
-        if let Some(cur) = app.current_path.clone() {
+        if let Some((page, pages)) = app.pdf.as_ref().map(|pdf| (pdf.target(), pdf.pages)) {
+            if ui.add_enabled(page > 0, egui::Button::new("◀")).on_hover_text("Previous page (Page Up)").clicked() {
+                app.go_to_pdf_page(page - 1);
+            }
+            let mut number = page + 1;
+            if ui.add(egui::DragValue::new(&mut number).range(1..=pages)).on_hover_text("Page").changed() {
+                app.go_to_pdf_page(number - 1);
+            }
+            ui.label(format!("/ {}", pages));
+            if ui.add_enabled(page + 1 < pages, egui::Button::new("▶")).on_hover_text("Next page (Page Down)").clicked() {
+                app.go_to_pdf_page(page + 1);
+            }
+            ui.separator();
+        }
+        if let Some((playing, index, count)) = app.animation.as_ref().map(|a| (a.playing, a.index, a.frames.len())) {
+            if ui.button("⏮").on_hover_text("Previous frame").clicked() {
+                app.step_frame(false, ctx);
+            }
+            if ui.button(if playing { "⏸" } else { "▶" }).on_hover_text(if playing { "Pause" } else { "Play" }).clicked() {
+                app.toggle_animation();
+            }
+            if ui.button("⏭").on_hover_text("Next frame").clicked() {
+                app.step_frame(true, ctx);
+            }
+            ui.label(format!("{} / {}", index + 1, count));
             ui.separator();
-            if (if is_allison { rainbow_button(ui, "Prev", &mut rainbow_idx) } else { ui.button("Prev") }).clicked() {
-                if let Some(prev) = crate::io::neighbor_text(&cur, false) { *file_to_load = Some(prev); }
+        }
+        use crate::imaging::Transform;
+        if ui.button("↺").on_hover_text("Rotate counter-clockwise (Shift+R)").clicked() {
+            app.transform_image(Transform::RotateCounterClockwise);
+        }
+        if ui.button("↻").on_hover_text("Rotate clockwise (R)").clicked() {
+            app.transform_image(Transform::RotateClockwise);
+        }
+        if ui.button("↔").on_hover_text("Flip horizontally").clicked() {
+            app.transform_image(Transform::FlipHorizontal);
+        }
+        if ui.button("↕").on_hover_text("Flip vertically").clicked() {
+            app.transform_image(Transform::FlipVertical);
+        }
+        ui.separator();
+        ui.checkbox(&mut app.image_fit, "Fit to Window").on_hover_text("Scale image to fit the window");
+        if ui.checkbox(&mut app.show_histogram, "Histogram").on_hover_text("Show RGB and luminance histogram").changed() {
+            crate::settings::mark_settings_dirty(app);
+        }
+        let measuring = app.image_tool == crate::app::ImageTool::Measure;
+        if ui.selectable_label(measuring, "📏 Measure").on_hover_text("Click two points to measure a distance (Esc clears)").clicked() {
+            app.image_tool = if measuring { crate::app::ImageTool::None } else { crate::app::ImageTool::Measure };
+            app.measure_points.clear();
+        }
+        let cropping = app.image_tool == crate::app::ImageTool::Crop;
+        if ui.selectable_label(cropping, "✂ Crop").on_hover_text("Drag over the image to select a region (Esc clears)").clicked() {
+            app.image_tool = if cropping { crate::app::ImageTool::None } else { crate::app::ImageTool::Crop };
+            app.crop_rect = None;
+        }
+        if ui.selectable_label(app.image_nearest, "▦ Nearest").on_hover_text("Nearest-neighbor sampling for pixel art; off uses smooth linear sampling").clicked() {
+            app.image_nearest = !app.image_nearest;
+            app.rebuild_texture();
+            crate::settings::mark_settings_dirty(app);
+        }
+        if app.compare.is_some() {
+            if ui.button("⚖ End compare").on_hover_text("Stop comparing against the second image").clicked() {
+                app.compare = None;
+            }
+            ui.checkbox(&mut app.compare_diff, "Differences").on_hover_text("Mark pixels that differ between the two images in red");
+        } else if app.compare_job.is_some() {
+            ui.spinner();
+        } else if ui.button("⚖ Compare…").on_hover_text("Overlay a second image with a draggable divider").clicked() {
+            app.pick_compare_image(ui.ctx());
+        }
+        if ui.selectable_label(app.show_adjust, "🎚 Adjust").on_hover_text("Brightness, contrast and gamma preview").clicked() {
+            app.show_adjust = !app.show_adjust;
+        }
+        if ui.add_enabled(app.clipboard.is_some(), egui::Button::new("📋 Copy Image"))
+            .on_hover_text("Copy the image to the clipboard")
+            .on_disabled_hover_text("Clipboard is not available on this system")
+            .clicked()
+        {
+            app.copy_image_to_clipboard();
+        }
+        if app.crop_rect.is_some() && ui.button("💾 Export selection").on_hover_text("Save the selected region as a new image").clicked() {
+            app.export_crop();
+        }
+        if app.image_fit != prev_fit { crate::settings::mark_settings_dirty(app); }
+        if (if is_allison { rainbow_button(ui, "🔍−", &mut rainbow_idx) } else { ui.button("🔍−") }).on_hover_text("Zoom out").clicked() { app.step_zoom(false); }
+        if (if is_allison { rainbow_button(ui, "🔍+", &mut rainbow_idx) } else { ui.button("🔍+") }).on_hover_text("Zoom in").clicked() { app.step_zoom(true); }
+        for (n, zoom) in crate::app::IMAGE_ZOOM_PRESETS.into_iter().enumerate() {
+            let active = !app.image_fit && (app.image_zoom - zoom).abs() < 0.001;
+            if ui.selectable_label(active, format!("{:.0}%", zoom * 100.0)).on_hover_text(format!("Zoom to {:.0}% ({})", zoom * 100.0, n + 1)).clicked() {
+                app.image_fit = false;
+                app.image_zoom = zoom;
+            }
+        }
+    } else if matches!(app.content, Some(crate::app::Content::Hex(_))) {
+        ui.separator();
+        ui.label("Offset:");
+        let input = ui.add(egui::TextEdit::singleline(&mut app.hex_goto_input).desired_width(90.0).hint_text(if app.hex_decimal_offsets { "1234 or 0x4d2" } else { "4d2" }))
+            .on_hover_text("Go to a byte offset; a 0x prefix is always hexadecimal");
+        if (input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))) | ui.button("Go").clicked() {
+            app.goto_hex_offset();
+        }
+    } else if matches!(app.content, Some(crate::app::Content::LargeText(_))) {
+        ui.separator();
+        if ui.checkbox(&mut app.show_minimap, "Minimap").on_hover_text("Show an overview of the file beside the text").changed() {
+            crate::settings::mark_settings_dirty(app);
+        }
+        if app.can_tail() && ui.selectable_label(app.tail_mode, "📜 Tail").on_hover_text("Follow lines appended to the file and keep the view at the end; scrolling up stops following").clicked() {
+            app.set_tail_mode(!app.tail_mode);
+        }
+    } else if matches!(app.content, Some(crate::app::Content::Text(_))) && app.current_path.is_some() {
+        ui.separator();
+        ui.checkbox(&mut app.split_view, "Split").on_hover_text("Show two panes of the same file");
+        if ui
+            .add_enabled(app.minimap_fits(), egui::Checkbox::new(&mut app.show_minimap, "Minimap"))
+            .on_hover_text("Show an overview of the file beside the text")
+            .on_disabled_hover_text("Hidden while lines wrap or are folded")
+            .changed()
+        {
+            crate::settings::mark_settings_dirty(app);
+        }
+        if app.can_tail() && ui.selectable_label(app.tail_mode, "📜 Tail").on_hover_text("Reload as the file grows and keep the view at the end; scrolling up stops following").clicked() {
+            app.set_tail_mode(!app.tail_mode);
+        }
+        let ext = app.current_ext();
+        let mut proportional = app.proportional_exts.contains(&ext);
+        if ui.checkbox(&mut proportional, "Proportional").on_hover_text(if ext.is_empty() { "Use the proportional font for files without an extension".to_string() } else { format!("Use the proportional font for .{} files", ext) }).changed() {
+            if proportional { app.proportional_exts.insert(ext); } else { app.proportional_exts.remove(&ext); }
+            crate::settings::mark_settings_dirty(app);
+        }
+        let shown = app.language_override.clone().unwrap_or_else(|| format!("Auto ({})", app.language().unwrap_or("plain")));
+        let mut picked: Option<Option<String>> = None;
+        egui::ComboBox::from_id_salt("language").selected_text(shown).height(400.0).show_ui(ui, |ui| {
+            if ui.selectable_label(app.language_override.is_none(), "Auto").clicked() {
+                picked = Some(None);
+            }
+            for language in crate::syntax::languages() {
+                if ui.selectable_label(app.language_override.as_deref() == Some(language), language).clicked() {
+                    picked = Some(Some(language.to_string()));
+                }
+            }
+        }).response.on_hover_text("Language used for syntax highlighting");
+        if let Some(language) = picked {
+            app.set_language_override(language);
+        }
+        if ui
+            .add_enabled(!app.text_is_big, egui::Button::new("💾 Export HTML"))
+            .on_hover_text("Save the text as a web page, highlighted in the current theme's colors")
+            .on_disabled_hover_text("Text this big is shown without colors and isn't exported")
+            .clicked()
+        {
+            app.export_html(ui.ctx());
+        }
+        if app.table.is_some()
+            && ui.selectable_label(app.table_view, "▦ Table").on_hover_text("Switch between the table and the raw text").clicked()
+        {
+            app.table_view = !app.table_view;
+            crate::settings::mark_settings_dirty(app);
+        }
+        if app.text_diff.is_some() {
+            if ui.button("⚖ End compare").on_hover_text("Stop comparing against the second file").clicked() {
+                app.end_compare();
+            }
+            if ui.checkbox(&mut app.diff_unified, "Unified").on_hover_text("One column with removed and added lines instead of side by side").changed() {
+                crate::settings::mark_settings_dirty(app);
+            }
+        } else if ui.button("⚖ Compare with…").on_hover_text("Show the differences from a second text file").clicked() {
+            app.pick_compare_text();
+        }
+        if app.json_tree.is_some()
+            && ui.selectable_label(app.json_view, "🌲 Tree").on_hover_text("Switch between the JSON tree and the raw text").clicked()
+        {
+            app.json_view = !app.json_view;
+            crate::settings::mark_settings_dirty(app);
+        }
+        if app.format_alt.is_some()
+            && ui.selectable_label(app.format_shown, "{ } Formatted").on_hover_text("Switch between the pretty-printed and original text").clicked()
+        {
+            app.toggle_formatted();
+        }
+        if matches!(app.current_ext().as_str(), "html" | "htm")
+            && !app.is_archive_entry_view()
+            && ui.button("🌐 Open in browser").on_hover_text("Preview the page rendered in the default browser").clicked()
+        {
+            app.open_in_browser();
+        }
+        if ui.checkbox(&mut app.show_outline, "Outline").on_hover_text("Show headings and definitions in a side panel").changed() {
+            crate::settings::mark_settings_dirty(app);
+        }
+        if (if is_allison { rainbow_button(ui, "Prev", &mut rainbow_idx) } else { ui.button("Prev") }).clicked()
+            && let Some(prev) = app.neighbor(false)
+        {
+            *file_to_load = Some(prev);
+        }
+        if (if is_allison { rainbow_button(ui, "Next", &mut rainbow_idx) } else { ui.button("Next") }).clicked()
+            && let Some(next) = app.neighbor(true)
+        {
+            *file_to_load = Some(next);
+        }
+    }
+
+    // Focused widgets share the pressed look in egui, so outline the keyboard focus explicitly
+    if let Some(id) = ctx.memory(|m| m.focused())
+        && let Some(resp) = ctx.read_response(id)
+        && ui.min_rect().intersects(resp.rect)
+    {
+        let accent = egui::Color32::from_rgb(app.accent_rgb[0], app.accent_rgb[1], app.accent_rgb[2]);
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("toolbar_focus")));
+        painter.rect_stroke(resp.rect.expand(2.0), 4.0, Stroke::new(2.0, accent), egui::StrokeKind::Outside);
+    }
+}
+
+/// Actions requested from the text view, applied by the caller once rendering is done.
+#[derive(Default)]
+pub(crate) struct TextViewActions {
+    pub(crate) toggled_bookmark: Option<usize>,
+    pub(crate) copied_line: Option<String>,
+    // Line last clicked, and the topmost line in view of the primary pane
+    pub(crate) clicked_line: Option<usize>,
+    pub(crate) first_visible: Option<usize>,
+    pub(crate) scrolled_to_line: bool,
+    pub(crate) scroll_offset: Option<egui::Vec2>,
+    pub(crate) toggled_fold: Option<usize>,
+    // The tailed view was scrolled away from the end
+    pub(crate) left_end: bool,
+    // Vertical offset picked on the minimap
+    pub(crate) minimap_scroll: Option<f32>,
+}
+
+/// Renders `text` in its own scroll area. Only the `primary` view follows search matches and line jumps.
+pub(crate) fn text_view(ui: &mut egui::Ui, app: &crate::app::FileViewerApp, text: &str, id_salt: &str, primary: bool, actions: &mut TextViewActions) {
+    text_frame(ui, app, Rows::Text(text), id_salt, primary, actions, |ui, scroll, fill, actions| {
+        if app.text_is_big && !app.text_line_starts.is_empty() { big_text_rows(ui, app, Rows::Text(text), scroll, primary, actions) } else { scroll.show(ui, |ui| {
+            let ext = app.current_ext();
+            let text_style = if app.proportional_exts.contains(&ext) { egui::TextStyle::Body } else { egui::TextStyle::Monospace };
+            let mut font_id = text_style.resolve(ui.style());
+            font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+            // The gutter stays monospace so numbers line up
+            let gutter_font = egui::FontId::monospace(font_id.size);
+            let text_color = ui.visuals().text_color();
+            // Row height applied to every section so wrapped rows and the gutter stay in step
+            let line_height = (app.line_height_scale > 1.0)
+                .then(|| ui.fonts(|f| f.row_height(&font_id)) * app.line_height_scale);
+
+            let do_line_numbers = app.show_line_numbers && !app.text_is_big;
+            let do_highlight = !app.text_is_big && text.len() <= app.highlight_threshold;
+            let marks = app.current_bookmarks();
+            let mark_color = ui.visuals().selection.bg_fill;
+            let do_control = app.show_control_chars && !app.text_is_big;
+            let palette = crate::highlight::Palette::for_theme(app.theme);
+            let wrap_long = !app.word_wrap && app.wrap_long_lines && !app.text_is_big;
+            let do_offsets = app.show_byte_offsets && !app.text_is_big && !app.text_file_offsets().is_empty();
+            let do_gutter = do_line_numbers || do_offsets;
+            // The gutter (or just bookmark markers) is painted beside the text rather than part of it, so
+            // selecting lines copies only the file's text; at the viewport edge it stays put while scrolling sideways
+            let frozen_gutter = do_gutter || !marks.is_empty();
+            let gutter = Gutter::new(app, do_line_numbers, do_offsets);
+            let gutter_text = |i: usize| if do_gutter { gutter.text(i) } else { "◆".to_string() };
+            let gutter_chars = if do_gutter { gutter.chars() } else { 1 };
+            let gutter_width = ui.fonts(|f| f.glyph_width(&gutter_font, '0')) * (gutter_chars + 1) as f32;
+            let mut gutter_rows: Vec<(usize, egui::Rect, bool)> = Vec::new();
+            // egui gives a tab a fixed advance; soft tabs expand to real stops in the content only
+            let expand_tabs = app.expand_tabs && !app.text_is_big && text.contains('\t');
+            let folding = !app.fold_ranges.is_empty();
+            let fold_slot_width = ui.fonts(|f| f.glyph_width(&gutter_font, '0')) * 2.0;
+            // Lines changed by the last tail reload, or jumped to, fade from highlighted to normal
+            let flash = line_flash(app, primary);
+            if flash.is_some() { ui.ctx().request_repaint(); }
+            // Where the text starts after the gutter and fold arrows, and the ruler column from there
+            let text_indent = if frozen_gutter { gutter_width } else { 0.0 } + if folding { fold_slot_width + ui.spacing().item_spacing.x } else { 0.0 };
+            let ruler = app.wrap_column.map(|column| {
+                let x = text_indent + ui.fonts(|f| f.glyph_width(&font_id, '0')) * column as f32;
+                (x, ui.painter().add(egui::Shape::Noop))
+            });
+            // A window narrower than the column still wraps at its edge
+            if app.word_wrap && let Some((x, _)) = ruler {
+                ui.set_max_width(x.min(ui.available_width()));
+            }
+            if do_gutter || do_highlight || do_control || wrap_long || expand_tabs || folding || flash.is_some() || !app.search_query.is_empty() || !marks.is_empty() {
+                let mut bracket_depth: i32 = 0;
+                let mut in_block_comment = false;
+                // Matches stay marked after the find field loses focus only in persistent mode
+                let highlight_query = if app.persistent_highlights || app.search_focused { app.search_pattern.as_ref() } else { None };
+                // Render per line and capture rect
+                let mut counter: usize = 0;
+                let mut jump_rect: Option<egui::Rect> = None;
+                // Inside a collapsed element: skip lines up to its end, standing in the fold's first line for jumps
+                let mut skip_until: Option<(usize, egui::Rect)> = None;
+                for (i, line) in text.lines().enumerate() {
+                    if let Some((end, fold_rect)) = skip_until {
+                        if i <= end {
+                            counter += crate::search::recompute_count(app.search_pattern.as_ref(), line);
+                            if primary && app.scroll_to_line == Some(i) { jump_rect = Some(fold_rect); }
+                            continue;
+                        }
+                        skip_until = None;
+                    }
+                    let mut line_job = egui::text::LayoutJob::default();
+                    let bookmarked = marks.binary_search(&i).is_ok();
+                    let line_start = line.as_ptr() as usize - text.as_ptr() as usize;
+                    let crlf = text.as_bytes().get(line_start + line.len()) == Some(&b'\r');
+                    if do_control && (crlf || crate::highlight::needs_control_markers(line, line_start, &app.text_invalid_offsets)) {
+                        crate::highlight::append_control_visible(&mut line_job, line, line_start, &app.text_invalid_offsets, crlf, font_id.clone(), text_color);
+                        // Keep the running match counter in step with lines rendered without search marks
+                        counter += crate::search::recompute_count(app.search_pattern.as_ref(), line);
+                    } else if do_highlight && let Some(spans) = app.syntax_spans.get(i) {
+                        crate::syntax::append_line(&mut line_job, line, spans, expand_tabs.then_some(app.tab_width), highlight_query, font_id.clone(), text_color, app.search_current, &mut counter);
+                    } else {
+                        // Languages the highlighter doesn't know, and any text while it's still working
+                        let shown = if expand_tabs { crate::highlight::expand_tabs(line, app.tab_width) } else { std::borrow::Cow::Borrowed(line) };
+                        crate::highlight::append_highlighted(&mut line_job, &shown, &ext, highlight_query, font_id.clone(), text_color, &palette, do_highlight, &mut bracket_depth, app.search_current, &mut counter, &mut in_block_comment);
+                    }
+                    let fold_end = app.fold_ranges.get(&i).copied();
+                    let collapsed = fold_end.is_some() && app.folded.contains(&i);
+                    if collapsed {
+                        line_job.append(" ⋯", 0.0, egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::GRAY, ..Default::default() });
+                    }
+                    if line_height.is_some() {
+                        for section in &mut line_job.sections { section.format.line_height = line_height; }
+                    }
+                    let mut label = egui::Label::new(line_job).sense(egui::Sense::click());
+                    if wrap_long && line.len() > app.wrap_line_threshold && line.chars().count() > app.wrap_line_threshold {
+                        label = label.wrap_mode(egui::TextWrapMode::Wrap);
+                    }
+                    // Reserve a slot behind the line for its flash, filled once the row's size is known
+                    let flash_slot = flash.as_ref().filter(|(lines, _)| lines.contains(&i)).map(|(_, fade)| (ui.painter().add(egui::Shape::Noop), *fade));
+                    let resp = if frozen_gutter || folding {
+                        ui.horizontal(|ui| {
+                            if frozen_gutter { ui.add_space(gutter_width); }
+                            if folding {
+                                match fold_end {
+                                    Some(end) => {
+                                        let arrow = egui::RichText::new(if collapsed { "▶" } else { "▼" }).font(gutter_font.clone()).color(egui::Color32::GRAY);
+                                        let hint = if collapsed { format!("Expand lines {}–{}", i + 1, end + 1) } else { format!("Collapse lines {}–{}", i + 1, end + 1) };
+                                        if ui.add_sized([fold_slot_width, gutter_font.size], egui::Label::new(arrow).sense(egui::Sense::click())).on_hover_text(hint).clicked() {
+                                            actions.toggled_fold = Some(i);
+                                        }
+                                    }
+                                    None => ui.add_space(fold_slot_width),
+                                }
+                            }
+                            ui.add(label)
+                        })
+                        .inner
+                    } else {
+                        ui.add(label)
+                    };
+                    if let Some((slot, fade)) = flash_slot {
+                        let row = egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), resp.rect.y_range());
+                        ui.painter().set(slot, egui::Shape::rect_filled(row, 0.0, flash_color(fade)));
+                    }
+                    if collapsed && let Some(end) = fold_end { skip_until = Some((end, resp.rect)); }
+                    if frozen_gutter && ui.is_rect_visible(resp.rect) { gutter_rows.push((i, resp.rect, bookmarked)); }
+                    if primary && actions.first_visible.is_none() && resp.rect.bottom() > ui.clip_rect().top() { actions.first_visible = Some(i); }
+                    if resp.clicked() { line_clicked(ui, app, i, line, actions); }
+                    if primary && app.scroll_to_line == Some(i) { jump_rect = Some(resp.rect); }
+                }
+                if frozen_gutter {
+                    let clip = ui.clip_rect();
+                    let gutter = egui::Rect::from_min_max(clip.left_top(), egui::pos2(clip.left() + gutter_width, clip.bottom()));
+                    let painter = ui.painter();
+                    painter.rect_filled(gutter, 0.0, fill);
+                    for (i, rect, bookmarked) in gutter_rows {
+                        let row = egui::Rect::from_x_y_ranges(gutter.x_range(), rect.y_range());
+                        // Clicking the gutter beside a line toggles its bookmark
+                        if ui.interact(row, ui.id().with(("gutter", i)), egui::Sense::click()).on_hover_cursor(egui::CursorIcon::PointingHand).clicked() {
+                            actions.toggled_bookmark = Some(i);
+                        }
+                        // Without numbers only bookmarked lines show a marker
+                        if !do_gutter && !bookmarked { continue; }
+                        if bookmarked && do_gutter { painter.rect_filled(row, 0.0, mark_color.gamma_multiply(0.5)); }
+                        let color = if !do_gutter { mark_color } else if bookmarked { text_color } else { egui::Color32::GRAY };
+                        // Center on the first visual row so wrapped lines keep their number at the top
+                        let first_row = line_height.unwrap_or_else(|| ui.fonts(|f| f.row_height(&font_id))).min(row.height());
+                        let anchor = egui::pos2(row.right() - gutter_width / (gutter_chars + 1) as f32, row.top() + first_row / 2.0);
+                        painter.text(anchor, egui::Align2::RIGHT_CENTER, gutter_text(i), gutter_font.clone(), color);
+                    }
+                }
+                if let Some(rect) = jump_rect {
+                    ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                    actions.scrolled_to_line = true;
+                }
+            } else {
+                ui.label(egui::RichText::new(crate::io::trim_final_newline(text)).font(font_id.clone()).line_height(line_height));
+            }
+            if let Some((x, slot)) = ruler {
+                let clip = ui.clip_rect();
+                let x = ui.max_rect().left() + x;
+                let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.4));
+                ui.painter().set(slot, egui::Shape::line_segment([egui::pos2(x, clip.top()), egui::pos2(x, clip.bottom())], stroke));
+            }
+        }) }
+    });
+}
+
+/// A file too large to load, read from its index a screenful at a time.
+pub(crate) fn large_text_view(ui: &mut egui::Ui, app: &crate::app::FileViewerApp, index: &crate::io::LineIndex, actions: &mut TextViewActions) {
+    text_frame(ui, app, Rows::Index(index), "text_main", true, actions, |ui, scroll, _, actions| big_text_rows(ui, app, Rows::Index(index), scroll, true, actions));
+}
+
+/// Changes asked for in the side pane, applied by the caller once it is drawn.
+#[derive(Default)]
+pub(crate) struct SidePaneActions {
+    pub(crate) close: bool,
+    pub(crate) zoom: Option<f32>,
+    pub(crate) direction: Option<crate::pane::SplitDirection>,
+    pub(crate) sync_scroll: Option<bool>,
+    // Where scrolling the pane puts the main view when both scroll in sync
+    pub(crate) main_offset: Option<f32>,
+}
+
+/// A second file beside the current one: its name, zoom and split controls above its text.
+/// With synchronized scrolling the pane follows the main view, and scrolling it moves the main view.
+pub(crate) fn side_pane_view(ui: &mut egui::Ui, app: &crate::app::FileViewerApp, pane: &crate::pane::SidePane) -> SidePaneActions {
+    use crate::pane::SplitDirection;
+    let mut actions = SidePaneActions::default();
+    ui.horizontal(|ui| {
+        let name = pane.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        ui.label(egui::RichText::new(name).strong()).on_hover_text(pane.path.to_string_lossy());
+        ui.separator();
+        let factor = 1.0 + app.text_zoom_step / 100.0;
+        if ui.small_button("−").on_hover_text("Zoom out").clicked() {
+            actions.zoom = Some((pane.zoom / factor).clamp(0.6, 3.0));
+        }
+        ui.label(format!("{:.0}%", pane.zoom * 100.0));
+        if ui.small_button("+").on_hover_text("Zoom in").clicked() {
+            actions.zoom = Some((pane.zoom * factor).clamp(0.6, 3.0));
+        }
+        ui.separator();
+        let mut sync = app.sync_scroll;
+        if ui.checkbox(&mut sync, "Sync scroll").on_hover_text("Scroll both files together").changed() {
+            actions.sync_scroll = Some(sync);
+        }
+        let (label, other) = match app.split_direction {
+            SplitDirection::Vertical => ("Stack", SplitDirection::Horizontal),
+            SplitDirection::Horizontal => ("Side by side", SplitDirection::Vertical),
+        };
+        if ui.small_button(label).on_hover_text("Change how the two files share the window").clicked() {
+            actions.direction = Some(other);
+        }
+        if ui.small_button("×").on_hover_text("Close this pane").clicked() {
+            actions.close = true;
+        }
+    });
+    // Offsets scale with the zoom so both views keep roughly the same lines in sight
+    let ratio = pane.zoom / app.text_zoom;
+    let synced = (app.sync_scroll && matches!(app.content, Some(crate::app::Content::Text(_) | crate::app::Content::LargeText(_))))
+        .then_some(app.text_scroll_offset.y * ratio);
+    let mut unused = TextViewActions::default();
+    text_frame(ui, app, Rows::Text(&pane.text), "side_pane", false, &mut unused, |ui, mut scroll, _, _| {
+        if let Some(y) = synced {
+            scroll = scroll.vertical_scroll_offset(y);
+        }
+        let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        font_id.size = (font_id.size * pane.zoom).clamp(8.0, 48.0);
+        let row_height = ui.fonts(|f| f.row_height(&font_id));
+        let text_color = ui.visuals().text_color();
+        let digits = pane.lines.max(1).ilog10() as usize + 1;
+        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+        let output = scroll.show_rows(ui, row_height, pane.lines, |ui, rows| {
+            for i in rows {
+                let line = pane.line(i);
+                let mut job = egui::text::LayoutJob::default();
+                let gutter = egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::GRAY, ..Default::default() };
+                job.append(&format!("{:>digits$} ", i + 1), 0.0, gutter);
+                match pane.spans.get(i) {
+                    Some(spans) => crate::syntax::append_line(&mut job, line, spans, None, None, font_id.clone(), text_color, usize::MAX, &mut 0),
+                    None => job.append(line, 0.0, egui::TextFormat { font_id: font_id.clone(), color: text_color, ..Default::default() }),
+                }
+                ui.label(job);
+            }
+        });
+        let y = output.state.offset.y;
+        // Moved from where it was put, within what it can scroll, only by the user
+        let max_y = (output.content_size.y - output.inner_rect.height()).max(0.0);
+        if synced.is_some_and(|synced| (synced.clamp(0.0, max_y) - y).abs() > 0.5) {
+            actions.main_offset = Some(y / ratio);
+        }
+        output
+    });
+    actions
+}
+
+/// The framed scroll area the text views draw into; `body` fills it, given the frame's fill color.
+/// The primary view of `source` gets a minimap beside it when turned on.
+fn text_frame(
+    ui: &mut egui::Ui,
+    app: &crate::app::FileViewerApp,
+    source: Rows,
+    id_salt: &str,
+    primary: bool,
+    actions: &mut TextViewActions,
+    body: impl FnOnce(&mut egui::Ui, egui::ScrollArea, egui::Color32, &mut TextViewActions) -> egui::scroll_area::ScrollAreaOutput<()>,
+) {
+    let mut frame = egui::Frame::group(ui.style());
+    frame.fill = if app.dark_mode { egui::Color32::from_rgb(28, 28, 30) } else { egui::Color32::from_rgb(255, 255, 255) };
+    frame.inner_margin = egui::Margin::symmetric(12, 10);
+    frame = frame.corner_radius(egui::CornerRadius::same(8));
+    let fill = frame.fill;
+    frame.show(ui, |ui| {
+        if source.is_empty() {
+            ui.set_min_size(ui.available_size());
+            ui.vertical_centered(|ui| {
+                ui.add_space(24.0);
+                ui.label(egui::RichText::new("(empty file)").italics().weak());
+            });
+            return;
+        }
+        // Wrap preference
+        ui.style_mut().wrap_mode = Some(if app.word_wrap { egui::TextWrapMode::Wrap } else { egui::TextWrapMode::Extend });
+        let mut scroll = egui::ScrollArea::both().id_salt(id_salt).auto_shrink([false, false]);
+        if primary && let Some(offset) = app.pending_scroll_offset {
+            scroll = scroll.scroll_offset(offset);
+        }
+        if primary && app.tail_mode {
+            scroll = scroll.stick_to_bottom(true);
+        }
+        let full = ui.available_rect_before_wrap();
+        let show_minimap = primary && app.show_minimap && app.minimap_fits();
+        let text_rect = if show_minimap { full.with_max_x(full.right() - MINIMAP_WIDTH - ui.spacing().item_spacing.x) } else { full };
+        let output = ui.scope_builder(egui::UiBuilder::new().max_rect(text_rect), |ui| body(ui, scroll, fill, actions)).inner;
+        if show_minimap {
+            minimap(ui, app, source, full.with_min_x(full.right() - MINIMAP_WIDTH), &output, actions);
+        }
+        if primary { actions.scroll_offset = Some(output.state.offset); }
+        if primary && app.tail_mode && app.pending_scroll_offset.is_none() {
+            let max_y = output.content_size.y - output.inner_rect.height();
+            actions.left_end = output.state.offset.y < max_y - 1.0;
+        }
+    });
+}
+
+/// Tab strip request, applied by the caller once the strip is drawn.
+pub(crate) enum TabAction {
+    Switch(usize),
+    // Next (true) or previous tab
+    Cycle(bool),
+    Close(usize),
+    New,
+}
+
+pub(crate) fn tab_bar(ui: &mut egui::Ui, app: &crate::app::FileViewerApp) -> Option<TabAction> {
+    let mut action = None;
+    egui::ScrollArea::horizontal().id_salt("tab_bar").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            for i in 0..app.tabs.len() {
+                let active = i == app.active_tab;
+                let resp = ui.selectable_label(active, app.tab_title(i));
+                let path = if active { app.shown_path().map(PathBuf::from) } else { app.tabs[i].path().map(PathBuf::from) };
+                let resp = match path {
+                    Some(path) => resp.on_hover_text(path.to_string_lossy()),
+                    None => resp,
+                };
+                if resp.clicked() {
+                    action = Some(TabAction::Switch(i));
+                }
+                if resp.middle_clicked() || ui.small_button("×").on_hover_text(format!("Close tab ({})", app.key_bindings.label(crate::keys::Action::CloseTab))).clicked() {
+                    action = Some(TabAction::Close(i));
+                }
+                ui.separator();
             }
-            if (if is_allison { rainbow_button(ui, "Next", &mut rainbow_idx) } else { ui.button("Next") }).clicked() {
-                if let Some(next) = crate::io::neighbor_text(&cur, true) { *file_to_load = Some(next); }
+            if ui.small_button("➕").on_hover_text(format!("New tab ({})", app.key_bindings.label(crate::keys::Action::NewTab))).clicked() {
+                action = Some(TabAction::New);
             }
+        });
+    });
+    action
+}
+
+/// Fixed-width gutter text: line number, byte offset, or both.
+struct Gutter<'a> {
+    offsets: &'a [usize],
+    line_numbers: bool,
+    byte_offsets: bool,
+    decimal: bool,
+    digits: usize,
+    offset_digits: usize,
+}
+
+impl<'a> Gutter<'a> {
+    fn new(app: &'a crate::app::FileViewerApp, line_numbers: bool, byte_offsets: bool) -> Self {
+        let max_offset = app.text_file_offsets().last().copied().unwrap_or(0);
+        let decimal = app.hex_decimal_offsets;
+        Gutter {
+            offsets: app.text_file_offsets(),
+            line_numbers,
+            byte_offsets,
+            decimal,
+            digits: (app.text_line_count.max(1).ilog10() as usize + 1).max(4),
+            offset_digits: if decimal { max_offset.to_string().len() } else { format!("{:x}", max_offset).len() }.max(4),
+        }
+    }
+
+    fn text(&self, i: usize) -> String {
+        let (digits, offset_digits) = (self.digits, self.offset_digits);
+        let offset = self.offsets.get(i).copied().unwrap_or(0);
+        let offset = if self.decimal { format!("{:>offset_digits$}", offset) } else { format!("{:>0offset_digits$x}", offset) };
+        match (self.line_numbers, self.byte_offsets) {
+            (true, true) => format!("{:>digits$}  {}", i + 1, offset),
+            (false, true) => offset,
+            _ => format!("{:>digits$}", i + 1),
+        }
+    }
+
+    fn chars(&self) -> usize {
+        match (self.line_numbers, self.byte_offsets) {
+            (true, true) => self.digits + 2 + self.offset_digits,
+            (false, true) => self.offset_digits,
+            _ => self.digits,
+        }
+    }
+}
+
+/// Ctrl+Click toggles a bookmark; a plain click copies `path:line`, Shift+Click also the line text.
+fn line_clicked(ui: &egui::Ui, app: &crate::app::FileViewerApp, i: usize, line: &str, actions: &mut TextViewActions) {
+    let (command, shift) = ui.input(|inp| (inp.modifiers.command, inp.modifiers.shift));
+    if command {
+        actions.toggled_bookmark = Some(i);
+    } else if let Some(path) = &app.current_path {
+        actions.clicked_line = Some(i);
+        let reference = format!("{}:{}", path.to_string_lossy(), i + 1);
+        actions.copied_line = Some(if shift { format!("{}: {}", reference, line) } else { reference });
+    }
+}
+
+/// Where `big_text_rows` and the minimap get their lines: loaded text, or a file indexed on disk.
+#[derive(Clone, Copy)]
+enum Rows<'a> {
+    Text(&'a str),
+    Index(&'a crate::io::LineIndex),
+}
+
+impl Rows<'_> {
+    fn is_empty(&self) -> bool {
+        match self {
+            Rows::Text(text) => text.is_empty(),
+            Rows::Index(index) => index.len() == 0,
         }
     }
 }
 
+/// Big files skip syntax colors, folding and wrapping, and only the rows in view are laid out,
+/// so even files of hundreds of megabytes scroll smoothly.
+fn big_text_rows(
+    ui: &mut egui::Ui,
+    app: &crate::app::FileViewerApp,
+    source: Rows,
+    mut scroll: egui::ScrollArea,
+    primary: bool,
+    actions: &mut TextViewActions,
+) -> egui::scroll_area::ScrollAreaOutput<()> {
+    let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+    let text_color = ui.visuals().text_color();
+    let line_height = (app.line_height_scale > 1.0).then(|| ui.fonts(|f| f.row_height(&font_id)) * app.line_height_scale);
+    let row_height = line_height.unwrap_or_else(|| ui.fonts(|f| f.row_height(&font_id)));
+    let row_stride = row_height + ui.spacing().item_spacing.y;
+    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+    if primary && let Some(line) = app.scroll_to_line {
+        scroll = scroll.vertical_scroll_offset((line as f32 * row_stride - ui.available_height() / 2.0).max(0.0));
+        actions.scrolled_to_line = true;
+    }
+    let gutter = Gutter::new(app, app.show_line_numbers, app.show_byte_offsets && !app.text_file_offsets().is_empty());
+    let show_gutter = gutter.line_numbers || gutter.byte_offsets;
+    let marks = app.current_bookmarks();
+    let mark_color = ui.visuals().selection.bg_fill;
+    let palette = crate::highlight::Palette::for_theme(app.theme);
+    let highlight_query = if app.persistent_highlights || app.search_focused { app.search_pattern.as_ref() } else { None };
+    let flash = line_flash(app, primary);
+    if flash.is_some() { ui.ctx().request_repaint(); }
+    scroll.show_rows(ui, row_height, app.text_line_count, |ui, rows| {
+        if primary { actions.first_visible = Some(rows.start); }
+        let lines: Vec<std::borrow::Cow<str>> = match source {
+            Rows::Text(text) => {
+                let starts = &app.text_line_starts;
+                rows.clone()
+                    .map(|i| {
+                        let line = &text[starts[i]..starts.get(i + 1).copied().unwrap_or(text.len())];
+                        line.strip_suffix('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).unwrap_or(line).into()
+                    })
+                    .collect()
+            }
+            Rows::Index(index) => index.lines(rows.clone()).into_iter().map(Into::into).collect(),
+        };
+        for (i, line) in rows.zip(&lines) {
+            // The gutter is a label of its own that can't be selected, so copied text has no line numbers
+            let mut gutter_job = egui::text::LayoutJob::default();
+            let bookmarked = marks.binary_search(&i).is_ok();
+            let gutter_font = egui::FontId::monospace(font_id.size);
+            if show_gutter {
+                let mut fmt = egui::TextFormat { font_id: gutter_font, color: egui::Color32::GRAY, ..Default::default() };
+                if bookmarked {
+                    fmt.color = text_color;
+                    fmt.background = mark_color.gamma_multiply(0.5);
+                }
+                gutter_job.append(&format!("{} ", gutter.text(i)), 0.0, fmt);
+            } else if bookmarked {
+                gutter_job.append("◆ ", 0.0, egui::TextFormat { font_id: gutter_font, color: mark_color, ..Default::default() });
+            } else if !marks.is_empty() {
+                gutter_job.append("  ", 0.0, egui::TextFormat { font_id: gutter_font, ..Default::default() });
+            }
+            let mut job = egui::text::LayoutJob::default();
+            // Matches are marked, but without a running count none of them is the current one
+            let mut counter = 0;
+            crate::highlight::append_highlighted(&mut job, line, "", highlight_query, font_id.clone(), text_color, &palette, false, &mut 0, usize::MAX, &mut counter, &mut false);
+            if line_height.is_some() {
+                for section in gutter_job.sections.iter_mut().chain(&mut job.sections) { section.format.line_height = line_height; }
+            }
+            let flash_slot = flash.as_ref().filter(|(lines, _)| lines.contains(&i)).map(|(_, fade)| (ui.painter().add(egui::Shape::Noop), *fade));
+            let resp = ui
+                .horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    if !gutter_job.sections.is_empty()
+                        && ui.add(egui::Label::new(gutter_job).selectable(false).sense(egui::Sense::click())).on_hover_cursor(egui::CursorIcon::PointingHand).clicked()
+                    {
+                        actions.toggled_bookmark = Some(i);
+                    }
+                    ui.add(egui::Label::new(job).sense(egui::Sense::click()))
+                })
+                .inner;
+            if let Some((slot, fade)) = flash_slot {
+                let row = egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), resp.rect.y_range());
+                ui.painter().set(slot, egui::Shape::rect_filled(row, 0.0, flash_color(fade)));
+            }
+            if resp.clicked() {
+                line_clicked(ui, app, i, line, actions);
+            }
+        }
+    })
+}
+
+/// Width of the minimap beside the text view.
+const MINIMAP_WIDTH: f32 = 80.0;
+// Columns of text the minimap's width stands for
+const MINIMAP_COLUMNS: f32 = 120.0;
+
+/// A pixel row per line showing where the text is, squeezed when there are more lines than pixels,
+/// with search matches marked at the edge and the part in view shaded. Pressing or dragging on it
+/// scrolls there. Indexed files only get the matches and the view, as their lines aren't in memory.
+fn minimap(
+    ui: &mut egui::Ui,
+    app: &crate::app::FileViewerApp,
+    source: Rows,
+    rect: egui::Rect,
+    view: &egui::scroll_area::ScrollAreaOutput<()>,
+    actions: &mut TextViewActions,
+) {
+    let lines = app.text_line_count.max(1);
+    let map_height = rect.height().min(lines as f32).max(1.0);
+    let rows = map_height as usize;
+    let row_of = |i: usize| (i * rows / lines).min(rows - 1);
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().faint_bg_color);
+    let ink = ui.visuals().text_color().gamma_multiply(0.35);
+    let column = rect.width() / MINIMAP_COLUMNS;
+    let draw_line = |row: usize, line: &str| {
+        let indent = line.len() - line.trim_start().len();
+        let end = line.trim_end().len();
+        if end > indent {
+            let y = rect.top() + row as f32;
+            let left = rect.left() + (indent as f32).min(MINIMAP_COLUMNS) * column;
+            let right = rect.left() + (end as f32).min(MINIMAP_COLUMNS) * column;
+            painter.rect_filled(egui::Rect::from_x_y_ranges(left..=right, y..=y + 1.0), 0.0, ink);
+        }
+    };
+    // Rows holding a match, and the row of the current one
+    let mut match_rows: Vec<usize> = Vec::new();
+    let mut current_row = None;
+    match source {
+        Rows::Text(text) if app.text_is_big => {
+            // Big text isn't searched; one line stands in for each row
+            let starts = &app.text_line_starts;
+            for row in 0..rows {
+                let i = row * lines / rows;
+                let Some(&start) = starts.get(i) else { break };
+                draw_line(row, &text[start..starts.get(i + 1).copied().unwrap_or(text.len())]);
+            }
+        }
+        Rows::Text(text) => {
+            let pattern = app.search_pattern.as_ref().filter(|_| app.search_count > 0);
+            let (mut last_row, mut counter) = (None, 0);
+            for (i, line) in text.lines().enumerate() {
+                let row = row_of(i);
+                if last_row != Some(row) {
+                    last_row = Some(row);
+                    draw_line(row, line);
+                }
+                let found = pattern.map_or(0, |p| p.matches(line).count());
+                if found > 0 {
+                    if match_rows.last() != Some(&row) { match_rows.push(row); }
+                    if (counter..counter + found).contains(&app.search_current) { current_row = Some(row); }
+                    counter += found;
+                }
+            }
+        }
+        Rows::Index(_) => {
+            for &i in &app.large_matches {
+                if match_rows.last() != Some(&row_of(i)) { match_rows.push(row_of(i)); }
+            }
+            current_row = app.large_matches.get(app.search_current).map(|&i| row_of(i));
+        }
+    }
+    let marker = |row: usize, color: egui::Color32| {
+        let y = rect.top() + row as f32;
+        painter.rect_filled(egui::Rect::from_x_y_ranges(rect.right() - 4.0..=rect.right(), y - 0.5..=y + 1.5), 0.0, color);
+    };
+    for row in match_rows {
+        marker(row, egui::Color32::from_rgb(230, 200, 0));
+    }
+    if let Some(row) = current_row {
+        marker(row, egui::Color32::from_rgb(224, 108, 117));
+    }
+    let content = view.content_size.y.max(1.0);
+    let seen = view.inner_rect.height();
+    let top = rect.top() + view.state.offset.y / content * map_height;
+    let height = (seen / content * map_height).clamp(2.0, map_height);
+    painter.rect_filled(egui::Rect::from_min_size(egui::pos2(rect.left(), top), egui::vec2(rect.width(), height)), 0.0, ui.visuals().text_color().gamma_multiply(0.12));
+    let resp = ui.allocate_rect(rect, egui::Sense::click_and_drag()).on_hover_cursor(egui::CursorIcon::PointingHand);
+    if resp.is_pointer_button_down_on() && let Some(pos) = resp.interact_pointer_pos() {
+        // Center the view on the pressed spot
+        let at = ((pos.y - rect.top()) / map_height).clamp(0.0, 1.0) * content;
+        actions.minimap_scroll = Some((at - seen / 2.0).clamp(0.0, (content - seen).max(0.0)));
+    }
+}
+
+/// The flashing lines and how far their highlight has faded (1 = fresh), while it lasts.
+fn line_flash(app: &crate::app::FileViewerApp, primary: bool) -> Option<(std::ops::Range<usize>, f32)> {
+    let (lines, at) = app.line_flash.as_ref()?;
+    let fade = 1.0 - at.elapsed().as_secs_f32() / crate::app::LINE_FLASH_SECS;
+    (primary && !app.low_power && fade > 0.0).then(|| (lines.clone(), fade))
+}
+
+fn flash_color(fade: f32) -> egui::Color32 {
+    egui::Color32::from_rgb(255, 200, 0).gamma_multiply(0.35 * fade)
+}
+
+/// Folder search: folder and query fields, then the matching lines grouped by file.
+/// Returns the file and 0-based line of a clicked result.
+pub(crate) fn folder_search_panel(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) -> Option<(PathBuf, usize)> {
+    ui.heading("Search in Folder");
+    ui.separator();
+    ui.horizontal(|ui| {
+        if ui.button("📁 Folder…").on_hover_text("Choose the folder to search").clicked()
+            && let Some(dir) = rfd::FileDialog::new().pick_folder()
+        {
+            app.folder_search_dir = Some(dir);
+        }
+        match &app.folder_search_dir {
+            Some(dir) => {
+                let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy();
+                ui.label(name).on_hover_text(dir.to_string_lossy());
+            }
+            None => {
+                ui.label(egui::RichText::new("(no folder)").weak());
+            }
+        }
+    });
+    let running = app.folder_search.as_ref().is_some_and(|s| !s.done);
+    let mut start = false;
+    ui.horizontal(|ui| {
+        let input = ui.add(egui::TextEdit::singleline(&mut app.folder_search_query).hint_text("Find in files").desired_width(ui.available_width() - 60.0));
+        start |= input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if running {
+            if ui.button("Stop").clicked() {
+                app.stop_folder_search();
+            }
+        } else {
+            start |= ui.button("Search").clicked();
+        }
+    });
+    ui.horizontal(|ui| {
+        // Shared with the find bar
+        let mut options_changed = ui.checkbox(&mut app.search_match_case, "Match case").changed();
+        options_changed |= ui.checkbox(&mut app.search_whole_word, "Whole word").changed();
+        if ui.selectable_label(app.search_regex, ".*").on_hover_text("Regular expression").clicked() {
+            app.search_regex = !app.search_regex;
+            options_changed = true;
+        }
+        if options_changed {
+            app.refresh_search();
+            crate::settings::mark_settings_dirty(app);
+        }
+    });
+    if start {
+        app.start_folder_search();
+    }
+    if let Some(error) = &app.folder_search_error {
+        ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", error));
+    }
+    let search = app.folder_search.as_ref()?;
+    let files: std::collections::BTreeSet<&std::path::Path> = search.hits.iter().map(|h| h.path.as_path()).collect();
+    let mut status = format!("{} line(s) in {} file(s), {} searched", search.hits.len(), files.len(), search.files);
+    if search.hits.len() >= crate::search::MAX_FOLDER_HITS {
+        status.push_str(" (stopped at the limit)");
+    } else if !search.done {
+        status.push('…');
+    }
+    ui.label(status);
+    ui.separator();
+    let base = app.folder_search_dir.as_deref();
+    let mut clicked = None;
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        let mut last: Option<&std::path::Path> = None;
+        for hit in &search.hits {
+            if last != Some(hit.path.as_path()) {
+                let shown = base.and_then(|b| hit.path.strip_prefix(b).ok()).unwrap_or(&hit.path);
+                ui.label(egui::RichText::new(shown.to_string_lossy()).strong()).on_hover_text(hit.path.to_string_lossy());
+                last = Some(hit.path.as_path());
+            }
+            let row = egui::RichText::new(format!("{:>5}: {}", hit.line + 1, hit.snippet)).monospace();
+            if ui.add(egui::Label::new(row).truncate().sense(egui::Sense::click())).on_hover_text(&hit.snippet).clicked() {
+                clicked = Some((hit.path.clone(), hit.line));
+            }
+        }
+    });
+    clicked
+}
+
+/// Expandable diagnostics under a load error: message, path, size and sniffed type, with a copy button.
+pub(crate) fn error_details(ui: &mut egui::Ui, err: &str, path: Option<&std::path::Path>) {
+    egui::CollapsingHeader::new("Details").id_salt("error_details").show(ui, |ui| {
+        let mut rows = vec![("Error", err.to_string())];
+        if let Some(path) = path {
+            rows.push(("Path", path.to_string_lossy().into_owned()));
+            let size = match std::fs::metadata(path) {
+                Ok(m) => format!("{} bytes", m.len()),
+                Err(e) => format!("unavailable ({})", e),
+            };
+            rows.push(("Size", size));
+            let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_else(|| "(none)".to_string());
+            rows.push(("Extension", ext));
+            rows.push(("Detected type", crate::io::sniff_type(path).unwrap_or_else(|e| format!("unavailable ({})", e))));
+        }
+        egui::Grid::new("error_details_grid").num_columns(2).striped(true).show(ui, |ui| {
+            for (key, value) in &rows {
+                ui.label(egui::RichText::new(*key).strong());
+                ui.add(egui::Label::new(egui::RichText::new(value).monospace()).wrap());
+                ui.end_row();
+            }
+        });
+        if ui.button("📋 Copy error").clicked() {
+            let report: Vec<String> = rows.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+            ui.ctx().copy_text(report.join("\n"));
+        }
+    });
+}
+
+/// Selection changes and copy requests from the hex view, applied by the caller.
+#[derive(Default)]
+pub(crate) struct HexViewActions {
+    pub(crate) selection: Option<(usize, usize)>,
+    pub(crate) copied: Option<(crate::hexfmt::CopyFormat, String)>,
+}
+
+/// Classic hex dump: offset, 16 bytes in hex, then their printable ASCII. Only visible rows are laid out.
+pub(crate) fn hex_view(ui: &mut egui::Ui, app: &crate::app::FileViewerApp, bytes: &[u8], actions: &mut HexViewActions) {
+    use std::fmt::Write;
+    let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    let char_width = ui.fonts(|f| f.glyph_width(&font_id, '0'));
+    let row_stride = row_height + ui.spacing().item_spacing.y;
+    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+    let per_row = app.hex_row_bytes;
+    let offset_cols = if app.hex_decimal_offsets { 12 } else { 10 };
+    // Column where each byte's hex cell starts (one extra space after every group of eight), and the ASCII column
+    let hex_col = |i: usize| offset_cols + 3 * i + i / 8;
+    let ascii_col = hex_col(per_row);
+    let text_color = ui.visuals().text_color();
+    let mark_bg = egui::Color32::from_rgb(app.accent_rgb[0], app.accent_rgb[1], app.accent_rgb[2]).gamma_multiply(0.5);
+    let select_bg = ui.visuals().selection.bg_fill;
+    let selected = app.hex_selection.map(|(a, b)| a.min(b)..=a.max(b));
+    let background = |offset: usize| {
+        if app.hex_marked == Some(offset) {
+            mark_bg
+        } else if selected.as_ref().is_some_and(|r| r.contains(&offset)) {
+            select_bg
+        } else {
+            egui::Color32::TRANSPARENT
+        }
+    };
+    let format = |background: egui::Color32| egui::TextFormat { font_id: font_id.clone(), color: text_color, background, ..Default::default() };
+    let mut scroll = egui::ScrollArea::both().id_salt("hex_view").auto_shrink([false, false]).drag_to_scroll(false);
+    if let Some(offset) = app.hex_scroll_to {
+        scroll = scroll.vertical_scroll_offset((offset / per_row) as f32 * row_stride);
+    }
+    scroll.show_rows(ui, row_height, bytes.len().div_ceil(per_row), |ui, rows| {
+        for row in rows {
+            let start = row * per_row;
+            let chunk = &bytes[start..(start + per_row).min(bytes.len())];
+            let mut job = egui::text::LayoutJob::default();
+            let mut run = if app.hex_decimal_offsets { format!("{:>10}  ", start) } else { format!("{:08x}  ", start) };
+            for i in 0..per_row {
+                match chunk.get(i) {
+                    Some(b) if background(start + i) != egui::Color32::TRANSPARENT => {
+                        job.append(&std::mem::take(&mut run), 0.0, format(egui::Color32::TRANSPARENT));
+                        job.append(&format!("{:02x}", b), 0.0, format(background(start + i)));
+                        run.push(' ');
+                    }
+                    Some(b) => { let _ = write!(run, "{:02x} ", b); }
+                    None => run.push_str("   "),
+                }
+                // Extra gap between groups of eight
+                if i % 8 == 7 { run.push(' '); }
+            }
+            if app.hex_show_ascii {
+                for (i, &b) in chunk.iter().enumerate() {
+                    let c = if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' };
+                    let bg = background(start + i);
+                    if bg != egui::Color32::TRANSPARENT {
+                        job.append(&std::mem::take(&mut run), 0.0, format(egui::Color32::TRANSPARENT));
+                        job.append(&c.to_string(), 0.0, format(bg));
+                    } else {
+                        run.push(c);
+                    }
+                }
+            }
+            job.append(&run, 0.0, format(egui::Color32::TRANSPARENT));
+            let response = ui.add(egui::Label::new(job).sense(egui::Sense::click_and_drag()));
+
+            // Byte under the pointer; rows below or above this one are reached by the drag that started here
+            let byte_at = |pos: egui::Pos2| {
+                let row_delta = ((pos.y - response.rect.top()) / row_stride).floor() as isize;
+                let row_start = (start as isize + row_delta * per_row as isize).max(0) as usize;
+                let col = ((pos.x - response.rect.left()) / char_width).max(0.0) as usize;
+                let i = if app.hex_show_ascii && col >= ascii_col {
+                    col - ascii_col
+                } else {
+                    (0..per_row).rev().find(|&i| hex_col(i) <= col).unwrap_or(0)
+                };
+                (row_start + i.min(per_row - 1)).min(bytes.len().saturating_sub(1))
+            };
+            if let Some(pos) = response.interact_pointer_pos() {
+                if response.drag_started() || response.clicked() {
+                    let at = byte_at(pos);
+                    actions.selection = Some((at, at));
+                } else if response.dragged()
+                    && let Some((anchor, _)) = app.hex_selection
+                {
+                    actions.selection = Some((anchor, byte_at(pos)));
+                }
+            }
+            response.context_menu(|ui| {
+                let Some(range) = selected.clone() else {
+                    ui.label(egui::RichText::new("Drag across bytes to select them").weak());
+                    return;
+                };
+                ui.label(egui::RichText::new(format!("{} bytes selected", range.end() - range.start() + 1)).weak());
+                for copy_format in crate::hexfmt::CopyFormat::ALL {
+                    if ui.button(format!("Copy as {}", copy_format.name())).clicked() {
+                        actions.copied = Some((copy_format, copy_format.format(&bytes[range.clone()])));
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// CSV/TSV cells in a grid with a fixed header and resizable columns. Returns a column whose
+/// header was clicked, to sort by.
+pub(crate) fn table_view(ui: &mut egui::Ui, table: &crate::table::Table, zoom: f32) -> Option<usize> {
+    use egui_extras::{Column, TableBuilder};
+    let mut clicked = None;
+    let font_id = egui::FontId::proportional((egui::TextStyle::Body.resolve(ui.style()).size * zoom).clamp(8.0, 48.0));
+    let row_height = ui.fonts(|f| f.row_height(&font_id)) + 4.0;
+    let number_width = ui.fonts(|f| f.glyph_width(&font_id, '0')) * (table.rows.len().max(1).ilog10() + 2) as f32;
+    let mut builder = TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::exact(number_width));
+    for _ in &table.header {
+        builder = builder.column(Column::initial(140.0).at_least(30.0).clip(true));
+    }
+    builder
+        .header(row_height, |mut header| {
+            header.col(|ui| { ui.label(egui::RichText::new("#").font(font_id.clone()).color(egui::Color32::GRAY)); });
+            for (i, name) in table.header.iter().enumerate() {
+                header.col(|ui| {
+                    let arrow = match table.sort {
+                        Some((column, true)) if column == i => " ▲",
+                        Some((column, false)) if column == i => " ▼",
+                        _ => "",
+                    };
+                    let label = egui::RichText::new(format!("{}{}", name, arrow)).font(font_id.clone()).strong();
+                    if ui.add(egui::Label::new(label).sense(egui::Sense::click()).truncate()).on_hover_text("Click to sort").clicked() {
+                        clicked = Some(i);
+                    }
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, table.order.len(), |mut row| {
+                let index = table.order[row.index()];
+                row.col(|ui| { ui.label(egui::RichText::new((index + 1).to_string()).font(font_id.clone()).color(egui::Color32::GRAY)); });
+                let cells = &table.rows[index];
+                for i in 0..table.header.len() {
+                    row.col(|ui| {
+                        let cell = cells.get(i).map(String::as_str).unwrap_or("");
+                        ui.add(egui::Label::new(egui::RichText::new(cell).font(font_id.clone())).truncate()).on_hover_text(cell);
+                    });
+                }
+            });
+        });
+    clicked
+}
+
+/// The current text beside (or merged with) the compared file, changed lines tinted. One scroll
+/// area holds both sides, so they always scroll together.
+pub(crate) fn diff_view(ui: &mut egui::Ui, diff: &crate::diff::TextDiff, unified: bool, zoom: f32) {
+    use crate::diff::Change;
+    let font_id = egui::FontId::monospace((egui::TextStyle::Monospace.resolve(ui.style()).size * zoom).clamp(8.0, 48.0));
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    let char_width = ui.fonts(|f| f.glyph_width(&font_id, '0'));
+    let digits = diff.left.len().max(diff.right.len()).max(1).ilog10() as usize + 1;
+    let text_color = ui.visuals().text_color();
+    let tint = |change: Change| match change {
+        Change::Same => egui::Color32::TRANSPARENT,
+        Change::Removed => egui::Color32::from_rgb(220, 60, 60).gamma_multiply(0.25),
+        Change::Added => egui::Color32::from_rgb(60, 180, 75).gamma_multiply(0.25),
+        Change::Changed => egui::Color32::from_rgb(230, 170, 30).gamma_multiply(0.25),
+    };
+    fn line_text(lines: &[String], line: Option<usize>) -> &str {
+        line.map_or("", |l| lines[l].as_str())
+    }
+    let rows = if unified { &diff.unified } else { &diff.rows };
+    // Unified rows carry both line numbers and a +/- marker; side-by-side halves one number each
+    let gutter = if unified { (digits * 2 + 4) as f32 * char_width } else { (digits + 1) as f32 * char_width };
+    let text_width = diff.widest as f32 * char_width + char_width;
+    let half = (ui.available_width() / 2.0).max(gutter + text_width);
+    let width = if unified { (gutter + text_width).max(ui.available_width()) } else { half * 2.0 };
+    egui::ScrollArea::both().auto_shrink([false, false]).show_rows(ui, row_height, rows.len(), |ui, range| {
+        for row in &rows[range] {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(width, row_height), egui::Sense::hover());
+            let painter = ui.painter();
+            let number = |line: Option<usize>| line.map_or(String::new(), |l| format!("{:>digits$}", l + 1));
+            if unified {
+                painter.rect_filled(rect, 0.0, tint(row.change));
+                let marker = match row.change {
+                    Change::Removed => "-",
+                    Change::Added => "+",
+                    _ => " ",
+                };
+                let numbers = format!("{} {} {}", number(row.left), number(row.right), marker);
+                painter.text(rect.left_center(), egui::Align2::LEFT_CENTER, numbers, font_id.clone(), egui::Color32::GRAY);
+                let text = if row.left.is_some() { line_text(&diff.left, row.left) } else { line_text(&diff.right, row.right) };
+                painter.text(rect.left_center() + egui::vec2(gutter, 0.0), egui::Align2::LEFT_CENTER, text, font_id.clone(), text_color);
+            } else {
+                for (side, lines, line) in [(0.0, &diff.left, row.left), (1.0, &diff.right, row.right)] {
+                    let cell = egui::Rect::from_min_size(rect.min + egui::vec2(half * side, 0.0), egui::vec2(half, row_height));
+                    // The empty side of an added or removed row stays blank
+                    if line.is_some() {
+                        painter.rect_filled(cell, 0.0, tint(row.change));
+                    }
+                    painter.text(cell.left_center(), egui::Align2::LEFT_CENTER, number(line), font_id.clone(), egui::Color32::GRAY);
+                    let text_pos = cell.left_center() + egui::vec2(gutter, 0.0);
+                    painter.with_clip_rect(cell.intersect(ui.clip_rect())).text(text_pos, egui::Align2::LEFT_CENTER, line_text(lines, line), font_id.clone(), text_color);
+                }
+                painter.vline(rect.left() + half, rect.y_range(), egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color));
+            }
+        }
+    });
+}
+
+// Children listed under one JSON node; the rest are only counted
+const MAX_JSON_CHILDREN: usize = 1000;
+
+/// Collapsible tree of a JSON document, with a filter on key names above it.
+pub(crate) fn json_tree_view(ui: &mut egui::Ui, tree: &mut crate::jsontree::JsonTree, palette: &crate::highlight::Palette, zoom: f32) {
+    let font_id = egui::FontId::monospace((egui::TextStyle::Monospace.resolve(ui.style()).size * zoom).clamp(8.0, 48.0));
+    ui.horizontal(|ui| {
+        let mut filter = tree.filter.clone();
+        if ui.add(egui::TextEdit::singleline(&mut filter).hint_text("Filter keys").desired_width(200.0)).changed() {
+            tree.set_filter(&filter);
+        }
+        if tree.visible.is_some() {
+            ui.label(format!("{} matching key(s)", tree.matches));
+        }
+    });
+    ui.separator();
+    egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+        json_node(ui, None, &tree.root, "", tree.visible.as_ref(), palette, &font_id);
+    });
+}
+
+fn json_node(
+    ui: &mut egui::Ui,
+    key: Option<&str>,
+    value: &serde_json::Value,
+    pointer: &str,
+    visible: Option<&std::collections::HashSet<String>>,
+    palette: &crate::highlight::Palette,
+    font_id: &egui::FontId,
+) {
+    use serde_json::Value;
+    let format = |color: egui::Color32| egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() };
+    let mut job = egui::text::LayoutJob::default();
+    if let Some(key) = key {
+        job.append(key, 0.0, format(ui.visuals().strong_text_color()));
+        job.append(": ", 0.0, format(egui::Color32::GRAY));
+    }
+    let summary = match value {
+        Value::Object(map) => format!("{{…}} {} key(s)", map.len()),
+        Value::Array(items) => format!("[…] {} item(s)", items.len()),
+        scalar => {
+            let (text, color) = match scalar {
+                Value::String(s) => (serde_json::to_string(s).unwrap_or_default(), palette.string),
+                Value::Number(n) => (n.to_string(), palette.number),
+                other => (other.to_string(), palette.literal),
+            };
+            let shown = match text.char_indices().nth(300) {
+                Some((cut, _)) => format!("{}…", &text[..cut]),
+                None => text,
+            };
+            job.append(&shown, 0.0, format(color));
+            job.append(&format!("  {}", crate::jsontree::type_name(scalar)), 0.0, format(egui::Color32::GRAY));
+            ui.add(egui::Label::new(job).extend()).on_hover_text(if pointer.is_empty() { "/" } else { pointer });
+            return;
+        }
+    };
+    job.append(&summary, 0.0, format(egui::Color32::GRAY));
+    egui::CollapsingHeader::new(job)
+        .id_salt(("json", pointer))
+        .default_open(pointer.is_empty())
+        // While filtering every node leading to a match is expanded
+        .open(visible.map(|_| true))
+        .show(ui, |ui| {
+            // Only built once expanded, so big collapsed nodes cost nothing
+            let children: Vec<(String, String, &Value)> = match value {
+                Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), crate::jsontree::child_pointer(pointer, k), v)).collect(),
+                Value::Array(items) => items.iter().enumerate().map(|(i, v)| (i.to_string(), format!("{}/{}", pointer, i), v)).collect(),
+                _ => Vec::new(),
+            };
+            let mut shown = 0;
+            for (key, child_pointer, child) in &children {
+                if visible.is_some_and(|v| !v.contains(child_pointer)) {
+                    continue;
+                }
+                if shown == MAX_JSON_CHILDREN {
+                    ui.label(egui::RichText::new(format!("… {} more", children.len() - shown)).weak());
+                    break;
+                }
+                json_node(ui, Some(key), child, child_pointer, visible, palette, font_id);
+                shown += 1;
+            }
+        });
+}
+
+/// The folder's images as a row of thumbnails, scrolled to the shown one whenever it changes.
+/// Returns the image clicked.
+pub(crate) fn thumbnail_strip(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) -> Option<PathBuf> {
+    let images = app.siblings.as_ref()?.images();
+    let index = app.current_path.as_ref().and_then(|c| images.iter().position(|p| p == c));
+    app.request_thumbnails(index.unwrap_or(0), ui.ctx());
+    let images = app.siblings.as_ref()?.images();
+    let current = app.current_path.as_ref();
+    let thumb = crate::thumbs::THUMB_SIZE as f32;
+    let slot = thumb + 8.0;
+    let stride = slot + ui.spacing().item_spacing.x;
+    let mut scroll = egui::ScrollArea::horizontal().id_salt("thumbnails").auto_shrink([false, true]);
+    if let Some(i) = index.filter(|_| app.thumbnails.focused.as_ref() != current) {
+        scroll = scroll.horizontal_scroll_offset((i as f32 * stride + slot / 2.0 - ui.available_width() / 2.0).max(0.0));
+    }
+    let mut clicked = None;
+    // Only the thumbnails in view are laid out, so folders of thousands of photos stay quick
+    scroll.show_viewport(ui, |ui, viewport| {
+        ui.set_min_size(egui::vec2(stride * images.len() as f32, slot));
+        let origin = ui.max_rect().min;
+        let first = (viewport.min.x / stride).floor().max(0.0) as usize;
+        let last = (viewport.max.x / stride).ceil() as usize + 1;
+        for (i, path) in images.iter().enumerate().take(last).skip(first) {
+            let rect = egui::Rect::from_min_size(origin + egui::vec2(i as f32 * stride, 0.0), egui::vec2(slot, slot));
+            let selected = Some(i) == index;
+            let button = match app.thumbnails.get(path) {
+                Some(Some(texture)) => egui::Button::image(egui::Image::new(texture).max_size(egui::vec2(thumb, thumb))),
+                Some(None) => egui::Button::new("⚠"),
+                None => egui::Button::new("…"),
+            };
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            if ui.put(rect, button.selected(selected)).on_hover_text(name).clicked() && !selected {
+                clicked = Some(path.clone());
+            }
+        }
+    });
+    app.thumbnails.focused = current.cloned();
+    clicked
+}
+
+/// Directory tree of an archive; clicking a file entry requests it through `open`.
+pub(crate) fn archive_view(ui: &mut egui::Ui, listing: &crate::archive::Listing, open: &mut Option<String>) {
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        if listing.entries.is_empty() {
+            ui.label(egui::RichText::new("(empty archive)").italics().weak());
+        }
+        archive_node(ui, listing, &listing.root, "", open);
+    });
+}
+
+fn archive_node(ui: &mut egui::Ui, listing: &crate::archive::Listing, node: &crate::archive::Node, prefix: &str, open: &mut Option<String>) {
+    for (name, child) in &node.children {
+        let path = format!("{}{}", prefix, name);
+        let entry = child.entry.map(|i| &listing.entries[i]);
+        if !child.children.is_empty() || entry.is_none_or(|e| e.is_dir) {
+            egui::CollapsingHeader::new(format!("📁 {}", name))
+                .id_salt(("archive_dir", &path))
+                .show(ui, |ui| archive_node(ui, listing, child, &format!("{}/", path), open));
+            continue;
+        }
+        let Some(entry) = entry else { continue };
+        ui.horizontal(|ui| {
+            if let Some(note) = entry.note {
+                ui.label(egui::RichText::new(format!("📄 {}", name)).weak());
+                ui.label(egui::RichText::new(format!("({})", note)).weak().italics());
+            } else if ui.link(format!("📄 {}", name)).on_hover_text(&entry.name).clicked() {
+                *open = Some(entry.name.clone());
+            }
+            ui.label(egui::RichText::new(format!("{:.1} KB", entry.size as f64 / 1024.0)).weak().small());
+        });
+    }
+}
+
+/// Contents of the Recent popup; returns true when the popup should close.
+fn recent_menu(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, file_to_load: &mut Option<PathBuf>) -> bool {
+    let mut close = false;
+    ui.set_min_width(480.0);
+    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+    ui.add(egui::TextEdit::singleline(&mut app.recent_filter).hint_text("🔎 Filter by name or path").desired_width(f32::INFINITY));
+    let filter = app.recent_filter.to_lowercase();
+    let matches = |file: &PathBuf| filter.is_empty() || file.to_string_lossy().to_lowercase().contains(&filter);
+    // Pinned or unpinned from the menu, applied once it is drawn
+    let mut pin_toggled: Option<PathBuf> = None;
+    if let Some(current) = app.current_path.clone().filter(|p| p.is_file()) {
+        let label = if app.is_pinned(&current) { "📌 Unpin current file" } else { "📌 Pin current file" };
+        if ui.button(label).on_hover_text("Pinned files stay at the top of this menu however many others are opened").clicked() {
+            pin_toggled = Some(current);
+        }
+    }
+    let pinned: Vec<PathBuf> = app.pinned_files.iter().filter(|f| matches(f)).cloned().collect();
+    if !pinned.is_empty() {
+        ui.label(egui::RichText::new("Pinned").strong());
+        for file in pinned {
+            // A file that was moved or deleted stays listed, greyed out, until it is removed
+            let exists = file.exists();
+            let name = file.file_name().and_then(|s| s.to_str()).unwrap_or("(unknown)");
+            let parent = file.parent().map(|p| app.display_path(p)).unwrap_or_default();
+            ui.horizontal(|ui| {
+                if ui.small_button("×").on_hover_text(if exists { "Unpin" } else { "Remove" }).clicked() {
+                    pin_toggled = Some(file.clone());
+                }
+                ui.vertical(|ui| {
+                    let text = if exists { egui::RichText::new(name).strong() } else { egui::RichText::new(name).weak().strikethrough() };
+                    let resp = ui
+                        .add_enabled(exists, egui::Button::new(text))
+                        .on_hover_text(file.to_string_lossy())
+                        .on_disabled_hover_text(format!("Not found: {}", file.display()));
+                    if resp.clicked() { *file_to_load = Some(file.clone()); close = true; }
+                    if !parent.is_empty() { ui.label(egui::RichText::new(parent).weak().small()); }
+                });
+            });
+        }
+        ui.separator();
+    }
+    if app.recent_files.is_empty() { ui.label("(no recent files)"); }
+    let mut shown = 0usize;
+    // (from, to) indices into `recent_files` when an entry is dropped onto another
+    let mut moved: Option<(usize, usize)> = None;
+    for (idx, file) in app.recent_files.clone().into_iter().enumerate().rev() {
+        if !matches(&file) { continue; }
+        shown += 1;
+        let name = file.file_name().and_then(|s| s.to_str()).unwrap_or("(unknown)");
+        let parent = file.parent().map(|p| app.display_path(p)).unwrap_or_default();
+        let row = ui.horizontal(|ui| {
+            ui.dnd_drag_source(egui::Id::new(("recent_drag", idx)), idx, |ui| {
+                ui.label(egui::RichText::new("☰").weak());
+            })
+            .response
+            .on_hover_text("Drag to reorder");
+            ui.vertical(|ui| {
+                let btn = egui::RichText::new(name).strong();
+                let resp = ui.button(btn).on_hover_ui(|ui| {
+                    ui.label(file.to_string_lossy());
+                    ui.separator();
+                    match app.recent_preview(&file, ui.ctx()) {
+                        Some(crate::app::RecentPreview::Text(text)) => { ui.label(egui::RichText::new(text).monospace().small()); }
+                        Some(crate::app::RecentPreview::Image(texture)) => { ui.image((texture.id(), texture.size_vec2())); }
+                        Some(crate::app::RecentPreview::Unavailable(reason)) => { ui.label(egui::RichText::new(reason).weak().italics()); }
+                        None => { ui.spinner(); }
+                    }
+                });
+                if resp.clicked() { *file_to_load = Some(file.clone()); close = true; }
+                if !parent.is_empty() { ui.label(egui::RichText::new(parent).weak().small()); }
+            });
+            if ui.small_button("📌").on_hover_text("Pin").clicked() {
+                pin_toggled = Some(file.clone());
+            }
+        })
+        .response;
+        if row.dnd_hover_payload::<usize>().is_some_and(|from| *from != idx) {
+            let y = row.rect.top() - ui.spacing().item_spacing.y / 2.0;
+            ui.painter().hline(row.rect.x_range(), y, ui.visuals().selection.stroke);
+        }
+        if let Some(from) = row.dnd_release_payload::<usize>() {
+            moved = Some((*from, idx));
+        }
+    }
+    if let Some((from, to)) = moved
+        && from != to
+    {
+        let entry = app.recent_files.remove(from);
+        // The list is drawn newest first, so dropping above a row means just after it in the
+        // vector; removing the entry first shifts the target down a slot when it came later
+        let to = if from < to { to } else { to + 1 };
+        app.recent_files.insert(to, entry);
+        crate::settings::mark_settings_dirty(app);
+    }
+    if let Some(file) = pin_toggled {
+        if file.exists() {
+            app.toggle_pin(&file);
+        } else {
+            app.pinned_files.retain(|p| p != &file);
+            crate::settings::mark_settings_dirty(app);
+        }
+    }
+    if shown == 0 && !app.recent_files.is_empty() { ui.label("(no matches)"); }
+    ui.separator();
+    if ui.button("🧹 Clear Recent").clicked() { app.recent_files.clear(); close = true; }
+    if close { app.recent_filter.clear(); }
+    close
+}
+
 pub(crate) fn search_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
     ui.horizontal_wrapped(|ui| {
         ui.label("Find:");
@@ -226,49 +1702,116 @@ pub(crate) fn search_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp)
             resp.request_focus();
             app.search_active = false;
         }
+        app.search_focused = resp.has_focus();
         // Enter / Shift+Enter navigate matches
         let (enter, shift) = ui.input(|i| (i.key_pressed(egui::Key::Enter), i.modifiers.shift));
         if enter && app.search_count > 0 {
             if shift {
-                if app.search_current == 0 { app.search_current = app.search_count.saturating_sub(1); } else { app.search_current -= 1; }
+                app.search_current = crate::search::prev_index(app.search_current, app.search_count);
             } else {
-                app.search_current = (app.search_current + 1) % app.search_count;
+                app.search_current = crate::search::next_index(app.search_current, app.search_count);
             }
+            app.jump_to_current_match();
         }
 
+        // Find as you type: the first match comes into view on every edit
         if resp.changed() || (prev.is_empty() && !app.search_query.is_empty()) {
-            app.search_count = 0;
-            app.search_current = 0;
-            if let Some(crate::app::Content::Text(ref text)) = app.content {
-                if !app.search_query.is_empty() && text.len() <= crate::app::HIGHLIGHT_CHAR_THRESHOLD {
-                    app.search_count = crate::search::recompute_count(&app.search_query, text);
-                }
-            }
+            app.refresh_search();
+            app.jump_to_current_match();
+            app.update_replace_preview();
+        }
+        let mut options_changed = ui.checkbox(&mut app.search_match_case, "Match case").changed();
+        options_changed |= ui.checkbox(&mut app.search_whole_word, "Whole word").changed();
+        if ui.selectable_label(app.search_regex, ".*").on_hover_text("Regular expression").clicked() {
+            app.search_regex = !app.search_regex;
+            options_changed = true;
+        }
+        if options_changed {
+            app.refresh_search();
+            app.jump_to_current_match();
+            app.update_replace_preview();
+            crate::settings::mark_settings_dirty(app);
         }
-        if !app.search_query.is_empty() {
-            ui.label(format!("{} match(es)", app.search_count));
+        if let Some(error) = &app.search_error {
+            ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", error));
+        } else if !app.search_query.is_empty() {
+            if app.large_search_running() {
+                ui.label(format!("{} match(es), searching…", app.search_count));
+            } else {
+                ui.label(format!("{} match(es)", app.search_count));
+            }
             ui.add_space(8.0);
             if ui.button("Prev").clicked() && app.search_count > 0 {
-                if app.search_current == 0 { app.search_current = app.search_count.saturating_sub(1); } else { app.search_current -= 1; }
+                app.search_current = crate::search::prev_index(app.search_current, app.search_count);
+                app.jump_to_current_match();
             }
             if ui.button("Next").clicked() && app.search_count > 0 {
-                app.search_current = (app.search_current + 1) % app.search_count;
+                app.search_current = crate::search::next_index(app.search_current, app.search_count);
+                app.jump_to_current_match();
             }
             if app.search_count > 0 {
                 ui.label(format!("{}/{}", app.search_current + 1, app.search_count));
             }
+            ui.add_space(8.0);
+            // Replacing writes a whole new copy, which an indexed file is too large for
+            let large = matches!(app.content, Some(crate::app::Content::LargeText(_)));
+            if !large && ui.selectable_label(app.show_replace, "Replace…").on_hover_text("Preview replacing every match and save it as a new file").clicked() {
+                app.show_replace = !app.show_replace;
+            }
+            if app.show_replace && !large {
+                ui.label("with:");
+                let replace = ui.text_edit_singleline(&mut app.replace_with);
+                let replace = if app.search_regex { replace.on_hover_text("$1 or ${name} inserts a captured group") } else { replace };
+                if replace.changed() {
+                    app.update_replace_preview();
+                }
+                if ui.add_enabled(app.search_count > 0, egui::Button::new("Preview")).clicked() {
+                    app.preview_replace();
+                }
+            }
+            ui.add_space(8.0);
+            if ui.checkbox(&mut app.persistent_highlights, "Keep highlights")
+                .on_hover_text("Keep every match highlighted after the find field loses focus")
+                .changed()
+            {
+                crate::settings::mark_settings_dirty(app);
+            }
         }
     });
 }
 
-pub(crate) fn status_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+pub(crate) fn status_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, file_to_load: &mut Option<PathBuf>) {
     use std::fs;
     ui.horizontal(|ui| {
         if let Some(path) = &app.current_path {
-            ui.monospace(format!("📄 {}", path.to_string_lossy()));
+            ui.monospace(format!("📄 {}", app.display_path(path))).on_hover_text(path.to_string_lossy());
+            if let Some(target) = &app.link_target {
+                ui.label(egui::RichText::new(format!("→ {}", target.to_string_lossy())).weak()).on_hover_text("Symbolic link target");
+                if ui.small_button("Open target").clicked() {
+                    *file_to_load = Some(target.clone());
+                }
+            }
+            // Only big folders take long enough to be worth showing
+            if let Some((dir, started, _)) = &app.sibling_scan
+                && started.elapsed() > std::time::Duration::from_millis(150)
+            {
+                ui.spinner();
+                ui.label(egui::RichText::new("Scanning folder…").weak()).on_hover_text(dir.to_string_lossy());
+            }
             if let Ok(metadata) = fs::metadata(path) {
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.label(format!("({:.1} KB)", metadata.len() as f64 / 1024.0));
+                    if app.show_file_times {
+                        // Creation time isn't recorded on every platform/filesystem
+                        if let Ok(created) = metadata.created() {
+                            ui.label(egui::RichText::new(format!("created {}", format_age(created))).weak())
+                                .on_hover_text(format_timestamp(created));
+                        }
+                        if let Ok(modified) = metadata.modified() {
+                            ui.label(format!("modified {}", format_age(modified)))
+                                .on_hover_text(format_timestamp(modified));
+                        }
+                    }
                 });
             }
             if ui.button("📋 Copy Path").on_hover_text("Copy path to clipboard").clicked() {
@@ -293,20 +1836,171 @@ pub(crate) fn status_extra(ui: &mut egui::Ui, app: &mut crate::app::FileViewerAp
         match &app.content {
             Some(crate::app::Content::Image(texture)) => {
                 let size = texture.size();
+                if let Some(pdf) = &app.pdf {
+                    ui.label(format!("📑 Page {} of {}", pdf.page + 1, pdf.pages));
+                }
+                if let Some(animation) = &app.animation {
+                    ui.label(format!("🎞 Frame {} of {}", animation.index + 1, animation.frames.len()));
+                }
                 ui.label(format!("🖼️ {}x{} px", size[0], size[1]));
                 let eff = if app.image_fit { None } else { Some(app.image_zoom) };
                 if let Some(z) = eff { ui.label(format!("🔍 {:.0}%", z * 100.0)); }
-                let est = (size[0] as usize).saturating_mul(size[1] as usize).saturating_mul(4);
-                ui.label(format!("🧮 ~{:.1} MB", est as f64 / (1024.0 * 1024.0)));
+                ui.label(format!("🧮 ~{:.1} MB", app.approx_content_bytes() as f64 / (1024.0 * 1024.0)));
                 if app.image_fit { ui.label("Fit: on"); }
+                ui.label(if app.image_nearest { "Sampling: nearest" } else { "Sampling: linear" });
+                if !app.image_adjust.is_identity() { ui.label("🎚 Adjusted preview"); }
+                if let Some(sel) = app.crop_rect {
+                    ui.label(format!("✂ {:.0}x{:.0} at ({:.0}, {:.0})", sel.width(), sel.height(), sel.min.x, sel.min.y));
+                }
+                if let [a, b] = app.measure_points[..] {
+                    let d = b - a;
+                    ui.label(format!("📏 {:.1} px (Δx {:.0}, Δy {:.0})", a.distance(b), d.x, d.y));
+                }
+                if let Some(compare) = &app.compare {
+                    let name = compare.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    ui.label(format!("⚖ Right: {}", name));
+                    let other = compare.texture.size();
+                    if other != size {
+                        ui.label(format!("⚠️ Sizes differ ({}x{}), aligned top-left", other[0], other[1]));
+                    }
+                    ui.label(format!("{} pixels differ", compare.diff_count));
+                }
+            }
+            Some(crate::app::Content::LargeText(index)) => {
+                ui.label(format!("📄 Lines: {}", app.text_line_count));
+                ui.label(format!("🔍 {:.0}%", app.text_zoom * 100.0));
+                ui.label(format!("📚 Indexed: {:.1}MB", index.len() as f64 / 1_000_000.0))
+                    .on_hover_text("Too large to load whole: lines are read from the file as they come into view");
+                ui.label(format!("ℹ️ {}", app.text_encoding));
             }
             Some(crate::app::Content::Text(_)) => {
                 ui.label(format!("📄 Lines: {}", app.text_line_count));
                 ui.label(format!("🔍 {:.0}%", app.text_zoom * 100.0));
                 if app.text_is_big { ui.label("⚠️ Large file: reduced features"); }
-                if app.text_is_lossy { ui.label("ℹ️ UTF-8 (lossy)"); }
+                if let Some(warning) = &app.format_warning { ui.label(format!("⚠️ Shown as-is: {}", warning)); }
+                if let Some(diff) = &app.text_diff {
+                    let name = diff.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    ui.label(format!("⚖ vs {}: +{} −{} ~{}", name, diff.added, diff.removed, diff.changed))
+                        .on_hover_text(format!("{} added, {} removed, {} changed line(s)", diff.added, diff.removed, diff.changed));
+                }
+                if let Some(table) = app.table.as_ref().filter(|_| app.table_view) {
+                    ui.label(format!("▦ {} rows × {} columns", table.rows.len(), table.header.len()));
+                }
+                if let Some(error) = &app.table_error { ui.label(format!("⚠️ Shown as text: {}", error)); }
+                let encoding_label = if app.text_is_lossy {
+                    format!("ℹ️ {} (lossy, {} invalid)", app.text_encoding, app.text_invalid_offsets.len())
+                } else {
+                    format!("ℹ️ {}", app.text_encoding)
+                };
+                let mut reopen_as = None;
+                ui.add_enabled_ui(app.can_tail(), |ui| {
+                    ui.menu_button(encoding_label, |ui| {
+                        ui.label("Reopen with encoding");
+                        ui.separator();
+                        if ui.selectable_label(app.encoding_override.is_none(), "Auto-detect").clicked() {
+                            reopen_as = Some(None);
+                            ui.close_menu();
+                        }
+                        for encoding in crate::io::reopen_encodings() {
+                            if ui.selectable_label(app.encoding_override == Some(encoding), encoding.name()).clicked() {
+                                reopen_as = Some(Some(encoding));
+                                ui.close_menu();
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text(if app.encoding_override.is_some() {
+                        "Chosen by hand; click to pick another or detect again"
+                    } else {
+                        "Detected from a byte order mark, the pattern of NUL bytes or the text itself; click to pick another"
+                    });
+                });
+                if let Some(encoding) = reopen_as {
+                    app.reopen_with_encoding(encoding, ui.ctx());
+                }
+            }
+            Some(crate::app::Content::Hex(bytes)) => {
+                ui.label(format!("🔢 {} bytes", bytes.len()));
+                if let Some((a, b)) = app.hex_selection {
+                    let (lo, hi) = (a.min(b), a.max(b));
+                    ui.label(format!("Selected {} bytes (0x{:x}–0x{:x})", hi - lo + 1, lo, hi));
+                }
+                ui.label(format!("🔍 {:.0}%", app.text_zoom * 100.0));
+            }
+            Some(crate::app::Content::Archive(listing)) => {
+                let files = listing.entries.iter().filter(|e| !e.is_dir).count();
+                let total: u64 = listing.entries.iter().map(|e| e.size).sum();
+                ui.label(format!("🗜 {} files, {:.1} KB uncompressed", files, total as f64 / 1024.0));
+                if app.is_opening_archive_entry() {
+                    ui.spinner();
+                    ui.label("Opening entry…");
+                }
             }
             _ => {}
         }
     });
 }
+
+/// Coarse "how long ago" text, e.g. "5 min ago".
+fn format_age(time: std::time::SystemTime) -> String {
+    let Ok(age) = time.elapsed() else { return "in the future".to_string() };
+    let secs = age.as_secs();
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{} min ago", secs / 60),
+        3_600..86_400 => format!("{} h ago", secs / 3_600),
+        86_400..2_592_000 => format!("{} days ago", secs / 86_400),
+        2_592_000..31_536_000 => format!("{} months ago", secs / 2_592_000),
+        _ => format!("{} years ago", secs / 31_536_000),
+    }
+}
+
+/// Absolute UTC timestamp, `YYYY-MM-DD HH:MM:SS UTC`.
+fn format_timestamp(time: std::time::SystemTime) -> String {
+    let Ok(since_epoch) = time.duration_since(std::time::UNIX_EPOCH) else { return "before 1970".to_string() };
+    let secs = since_epoch.as_secs() as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, rem / 3_600, rem % 3_600 / 60, rem % 60)
+}
+
+pub(crate) fn histogram_plot(ui: &mut egui::Ui, hist: &crate::imaging::Histogram) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(320.0, 140.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+    let peak = hist.peak().max(1) as f32;
+    let colors = [
+        egui::Color32::from_rgba_unmultiplied(239, 83, 80, 200),
+        egui::Color32::from_rgba_unmultiplied(102, 187, 106, 200),
+        egui::Color32::from_rgba_unmultiplied(66, 165, 245, 200),
+        ui.visuals().text_color().gamma_multiply(0.8),
+    ];
+    for (channel, color) in hist.channels.iter().zip(colors) {
+        let points: Vec<egui::Pos2> = channel
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let x = rect.left() + rect.width() * i as f32 / 255.0;
+                // Square-root scale keeps small peaks visible next to dominant ones
+                let y = rect.bottom() - rect.height() * (count as f32 / peak).sqrt();
+                egui::pos2(x, y)
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, Stroke::new(1.0, color)));
+    }
+    ui.horizontal(|ui| {
+        ui.colored_label(colors[0], "R");
+        ui.colored_label(colors[1], "G");
+        ui.colored_label(colors[2], "B");
+        ui.label("Luma");
+    });
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use notify::{RecursiveMode, Watcher};
+
+/// Notices changes to one file. The folder is watched rather than the file, so editors that save
+/// by replacing the file are still seen. Where the platform watcher can't be set up (some network
+/// drives, exhausted watch limits) the caller's stamp check runs on a timer instead.
+pub(crate) struct FileWatch {
+    pub(crate) path: PathBuf,
+    watcher: Option<(notify::RecommendedWatcher, Receiver<()>)>,
+    pending: bool,
+    fired: Option<Instant>,
+}
+
+impl FileWatch {
+    pub(crate) fn new(path: PathBuf, ctx: &egui::Context) -> Self {
+        let watcher = Self::watch(&path, ctx);
+        FileWatch { path, watcher, pending: false, fired: None }
+    }
+
+    fn watch(path: &std::path::Path, ctx: &egui::Context) -> Option<(notify::RecommendedWatcher, Receiver<()>)> {
+        let path = std::fs::canonicalize(path).ok()?;
+        let name = path.file_name()?.to_os_string();
+        let dir = path.parent()?.to_path_buf();
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && !event.kind.is_access()
+                && event.paths.iter().any(|p| p.file_name() == Some(name.as_os_str()))
+            {
+                let _ = tx.send(());
+                ctx.request_repaint();
+            }
+        })
+        .ok()?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive).ok()?;
+        Some((watcher, rx))
+    }
+
+    /// Whether the file may have changed since the last time this said so. Bursts of events are
+    /// merged into one every `gap`; without a watcher this simply fires every `poll`.
+    pub(crate) fn changed(&mut self, ctx: &egui::Context, gap: Duration, poll: Duration) -> bool {
+        let wait = match &self.watcher {
+            Some((_, rx)) => {
+                self.pending |= rx.try_iter().count() > 0;
+                if !self.pending {
+                    return false;
+                }
+                gap
+            }
+            None => poll,
+        };
+        let since = self.fired.map_or(wait, |at| at.elapsed());
+        if since < wait {
+            ctx.request_repaint_after(wait - since);
+            return false;
+        }
+        self.pending = false;
+        self.fired = Some(Instant::now());
+        if self.watcher.is_none() {
+            ctx.request_repaint_after(poll);
+        }
+        true
+    }
+}